@@ -0,0 +1,168 @@
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+use zkpoor_core::auth::{ApiKey, KeyManager, Role};
+use zkpoor_core::types::{ProofData, ProofStatus};
+use zkpoor_core::Error;
+
+use crate::config::{Config, KeyCommand, StoreBackend};
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// The API key that authenticated the request, set by `require_api_key`.
+/// `None` only when authentication is not required, in which case every
+/// request is allowed.
+#[derive(Debug, Clone)]
+pub struct Caller(pub Option<ApiKey>);
+
+impl Caller {
+    pub fn key_id(&self) -> Option<Uuid> {
+        self.0.as_ref().map(|key| key.key_id)
+    }
+
+    /// Fail with `Forbidden` unless the caller holds `role`
+    pub fn require(&self, role: Role) -> Result<(), Error> {
+        match &self.0 {
+            Some(key) if !key.has_role(role) => Err(Error::Forbidden(format!(
+                "API key {} does not have the '{}' role",
+                key.key_id, role
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// What the caller may see of a proof job. Auditors see every job and the
+    /// private inputs of completed ones; admins see every job and managers
+    /// their own, without inputs. Other jobs are reported as not found.
+    pub fn view(&self, proof: ProofData) -> Result<ProofData, Error> {
+        let Some(key) = &self.0 else { return Ok(proof) };
+        if key.has_role(Role::Auditor) {
+            return Ok(if proof.status == ProofStatus::Completed { proof } else { proof.redacted() });
+        }
+        if key.has_role(Role::Admin) || self.owns(&proof) {
+            return Ok(proof.redacted());
+        }
+        Err(Error::ProofNotFound(proof.proof_id))
+    }
+
+    /// Only the manager who submitted a job, or an admin, may cancel it
+    pub fn check_cancel(&self, proof: &ProofData) -> Result<(), Error> {
+        let Some(key) = &self.0 else { return Ok(()) };
+        if key.has_role(Role::Admin) || self.owns(proof) {
+            return Ok(());
+        }
+        // Managers learn nothing about other managers' jobs
+        self.view(proof.clone())?;
+        Err(Error::Forbidden(format!("API key {} may not cancel proof {}", key.key_id, proof.proof_id)))
+    }
+
+    fn owns(&self, proof: &ProofData) -> bool {
+        self.0.as_ref().is_some_and(|key| key.has_role(Role::Manager) && proof.created_by == Some(key.key_id))
+    }
+}
+
+/// Authenticate `Authorization: Bearer <key>` on the proof management routes
+pub async fn require_api_key(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let key = match bearer_token(request.headers())? {
+        Some(secret) => Some(state.keys.authenticate(secret).await?),
+        None if state.auth_required => {
+            return Err(Error::Unauthorized("Missing 'Authorization: Bearer <api key>' header".to_string()).into());
+        }
+        None => None,
+    };
+
+    request.extensions_mut().insert(Caller(key));
+    Ok(next.run(request).await)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    token: Option<String>,
+}
+
+/// Authenticate `/proof/{proof_id}/events` with a bearer API key or, for a
+/// browser `EventSource`, which cannot set headers, a `?token=` from
+/// `POST /proof/{proof_id}/events/token`
+pub async fn require_stream_auth(
+    State(state): State<AppState>,
+    Path(proof_id): Path<Uuid>,
+    Query(query): Query<StreamQuery>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let key = match (bearer_token(request.headers())?, &query.token) {
+        (Some(secret), _) => Some(state.keys.authenticate(secret).await?),
+        (None, Some(token)) => state.keys.authenticate_stream_token(token, proof_id).await?,
+        (None, None) if state.auth_required => {
+            return Err(Error::Unauthorized(
+                "Missing 'Authorization: Bearer <api key>' header or '?token=<stream token>'".to_string(),
+            )
+            .into());
+        }
+        (None, None) => None,
+    };
+
+    request.extensions_mut().insert(Caller(key));
+    Ok(next.run(request).await)
+}
+
+fn bearer_token(headers: &HeaderMap) -> Result<Option<&str>, Error> {
+    let Some(value) = headers.get(header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| Some(token.trim()))
+        .ok_or_else(|| Error::Unauthorized("Expected 'Authorization: Bearer <api key>'".to_string()))
+}
+
+/// Run a `zkpoor-api keys ...` command against the configured store
+pub async fn run_key_command(config: &Config, command: &KeyCommand) -> anyhow::Result<()> {
+    if config.store.backend == StoreBackend::Memory {
+        return Err(anyhow::anyhow!(
+            "API keys in the memory store would be lost when this command exits; use the sqlite store"
+        ));
+    }
+    let keys = KeyManager::new(config.store_config().open()?);
+
+    match command {
+        KeyCommand::Create { name, roles } => {
+            let issued = keys.create(name, roles).await?;
+            println!("Created API key {} ({}) with roles {}", issued.key.key_id, issued.key.name, role_list(&issued.key));
+            println!("{}", issued.secret);
+            eprintln!("Store this key now; it cannot be shown again.");
+        }
+        KeyCommand::List => {
+            for key in keys.list().await? {
+                let state = match key.revoked_at {
+                    Some(at) => format!("revoked {}", at.to_rfc3339()),
+                    None => "active".to_string(),
+                };
+                println!(
+                    "{}  {}  {}  created {}  {}",
+                    key.key_id, key.name, role_list(&key), key.created_at.to_rfc3339(), state
+                );
+            }
+        }
+        KeyCommand::Revoke { key_id } => {
+            let key = keys.revoke(*key_id).await?;
+            println!("Revoked API key {} ({})", key.key_id, key.name);
+        }
+    }
+    Ok(())
+}
+
+fn role_list(key: &ApiKey) -> String {
+    key.roles.iter().map(Role::as_str).collect::<Vec<_>>().join(",")
+}
@@ -0,0 +1,1102 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use axum::http::{header, HeaderValue, Method};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use zkpoor_core::artifacts::ArtifactStore;
+use zkpoor_core::auth::{KeyManager, Role};
+use zkpoor_core::challenge::{ChallengeStore, DEFAULT_CHALLENGE_TTL, DEFAULT_OPERATOR};
+use zkpoor_core::output::ExpectedPrograms;
+use zkpoor_core::ownership::{MockOwnershipVerifier, OwnershipVerifier, SignatureVerifier};
+use zkpoor_core::script::Network;
+use zkpoor_core::proof::ProofVerifier;
+use zkpoor_core::program::{ChainDataFiles, Felt};
+use zkpoor_core::prover::{ExternalProver, MockProver, ProverBackend};
+use zkpoor_core::published::PublishedProofs;
+use zkpoor_core::queue::QueueConfig;
+use zkpoor_core::store::StoreConfig;
+#[cfg(feature = "stwo")]
+use zkpoor_core::stwo::{StwoProver, StwoVerifier};
+use zkpoor_core::toolchain::{StepTimeouts, ToolchainConfig, ToolchainProver, DEFAULT_TIME_BINARY};
+
+use crate::state::DEFAULT_MAX_PROOF_BYTES;
+
+/// Command-line flags. Each one overrides the matching setting from the
+/// configuration file and the environment.
+#[derive(Debug, Default, Parser)]
+#[command(about = "zkpoor proving and verification API")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// TOML configuration file [env: ZKPOOR_CONFIG]
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+    /// Address to listen on, e.g. 127.0.0.1:8080
+    #[arg(long)]
+    pub bind: Option<SocketAddr>,
+    /// Bitcoin network addresses are shown for: mainnet, testnet, signet or regtest
+    #[arg(long)]
+    pub network: Option<Network>,
+    /// Origin allowed to call the public verification endpoints; repeat for several, `*` for any
+    #[arg(long = "cors-public-origin")]
+    pub cors_public_origins: Vec<String>,
+    /// Origin allowed to submit and manage proofs; repeat for several
+    #[arg(long = "cors-manager-origin")]
+    pub cors_manager_origins: Vec<String>,
+    /// Where proof jobs are kept
+    #[arg(long, value_enum)]
+    pub store: Option<StoreBackend>,
+    /// SQLite database used by `--store sqlite`
+    #[arg(long)]
+    pub database: Option<PathBuf>,
+    /// Directory of proof artifacts
+    #[arg(long)]
+    pub artifacts_dir: Option<PathBuf>,
+    /// Directory of published proofs, verified at startup
+    #[arg(long)]
+    pub published_dir: Option<PathBuf>,
+    #[arg(long, value_enum)]
+    pub prover: Option<ProverKind>,
+    /// Prover program: the external prover binary, or the Cairo executable proved by stwo
+    #[arg(long)]
+    pub program: Option<PathBuf>,
+    /// Chain state and blocks (`batch.json`) proved against by the toolchain and stwo provers
+    #[arg(long)]
+    pub chain_batch: Option<PathBuf>,
+    /// Cairo serde proof of the chain state (`proof_serde.json`)
+    #[arg(long)]
+    pub chain_state_proof: Option<PathBuf>,
+    /// Time limit of the toolchain's `cairo_program_runner` step
+    #[arg(long)]
+    pub cairo_runner_timeout_secs: Option<u64>,
+    /// Time limit of the toolchain's `cairo-execute` diagnostics step
+    #[arg(long)]
+    pub cairo_execute_timeout_secs: Option<u64>,
+    /// Time limit of the toolchain's `adapted_stwo` step
+    #[arg(long)]
+    pub prove_timeout_secs: Option<u64>,
+    /// Jobs proved concurrently
+    #[arg(long)]
+    pub workers: Option<usize>,
+    /// Jobs allowed to wait for a worker
+    #[arg(long)]
+    pub queue_depth: Option<usize>,
+    /// Largest body accepted by `/proof-verify`
+    #[arg(long)]
+    pub max_proof_bytes: Option<usize>,
+    /// `tracing` filter, e.g. `zkpoor_api=info,tower_http=warn`
+    #[arg(long)]
+    pub log_filter: Option<String>,
+    /// Let anonymous clients submit and manage proofs (development only)
+    #[arg(long)]
+    pub no_auth: bool,
+    /// Accept ownership proofs without checking their signatures (development only)
+    #[arg(long)]
+    pub no_signature_check: bool,
+    /// Answer `/proof-verify` with the mock verifier, which accepts any mock proof (development only)
+    #[arg(long)]
+    pub allow_mock_proofs: bool,
+}
+
+/// Without a command the server is started
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Manage API keys in the configured store
+    Keys {
+        #[command(subcommand)]
+        command: KeyCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum KeyCommand {
+    /// Issue a new key and print its secret
+    Create {
+        /// Who the key is for, e.g. `treasury-portal`
+        name: String,
+        /// Role granted to the key: manager, auditor or admin; repeat for several
+        #[arg(long = "role", default_value = "manager")]
+        roles: Vec<Role>,
+    },
+    /// List keys, including revoked ones
+    List,
+    /// Revoke a key; requests using it are rejected from then on
+    Revoke { key_id: uuid::Uuid },
+}
+
+/// Server configuration. Settings are layered: built-in defaults, then the
+/// TOML file, then `ZKPOOR_*` environment variables, then command-line flags.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bind: SocketAddr,
+    pub log_filter: String,
+    /// Network of the submitted UTXOs, for rendering their addresses
+    pub network: Network,
+    pub auth: AuthSettings,
+    pub ownership: OwnershipSettings,
+    pub verifier: VerifierSettings,
+    pub cors: CorsSettings,
+    pub store: StoreSettings,
+    pub prover: ProverSettings,
+    pub queue: QueueSettings,
+    pub limits: LimitSettings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            log_filter: "zkpoor_api=debug,tower_http=debug".to_string(),
+            network: Network::Mainnet,
+            auth: AuthSettings::default(),
+            ownership: OwnershipSettings::default(),
+            verifier: VerifierSettings::default(),
+            cors: CorsSettings::default(),
+            store: StoreSettings::default(),
+            prover: ProverSettings::default(),
+            queue: QueueSettings::default(),
+            limits: LimitSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthSettings {
+    /// Require an API key on the proof management routes
+    pub required: bool,
+    /// Hex SHA-256 of an admin key secret, created in the store at startup.
+    /// The only way to administer a server using the memory store.
+    pub admin_key_hash: Option<String>,
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        Self { required: true, admin_key_hash: None }
+    }
+}
+
+impl AuthSettings {
+    fn validate(&self, problems: &mut Vec<String>) {
+        if let Some(hash) = &self.admin_key_hash {
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                problems.push("auth.admin_key_hash must be a hex SHA-256 digest (64 characters)".to_string());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OwnershipSettings {
+    /// Check the signature of each ownership proof against its UTXO's script
+    pub verify_signatures: bool,
+    /// Operator named in the challenges ownership proofs sign
+    pub operator: String,
+    /// How long a challenge from `POST /challenge` can be used
+    pub challenge_ttl_secs: u64,
+}
+
+impl Default for OwnershipSettings {
+    fn default() -> Self {
+        Self {
+            verify_signatures: true,
+            operator: DEFAULT_OPERATOR.to_string(),
+            challenge_ttl_secs: DEFAULT_CHALLENGE_TTL.num_seconds() as u64,
+        }
+    }
+}
+
+impl OwnershipSettings {
+    fn validate(&self, problems: &mut Vec<String>) {
+        if self.operator.trim().is_empty() || self.operator.contains('\n') {
+            problems.push("ownership.operator must be a non-empty single line".to_string());
+        }
+        if self.challenge_ttl_secs == 0 {
+            problems.push("ownership.challenge_ttl_secs must be positive".to_string());
+        }
+    }
+
+    pub fn challenges(&self) -> ChallengeStore {
+        ChallengeStore::new(self.operator.trim(), chrono::Duration::seconds(self.challenge_ttl_secs as i64))
+    }
+
+    pub fn verifier(&self) -> Arc<dyn OwnershipVerifier> {
+        if self.verify_signatures {
+            Arc::new(SignatureVerifier::new())
+        } else {
+            Arc::new(MockOwnershipVerifier)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VerifierSettings {
+    /// Let `/proof-verify` answer with the mock verifier. Without a real STARK
+    /// verifier it is refused, so mock proofs are never reported as valid.
+    pub allow_mock: bool,
+    /// Hash of the recursively verified program proofs must carry; required
+    /// with a real STARK verifier, see `ExpectedPrograms`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program_hash: Option<Felt>,
+    /// Hash of the bootloader that ran the recursively verified proof
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bootloader_hash: Option<Felt>,
+    /// Hash of the zkpoor program run by the simple bootloader
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_program_hash: Option<Felt>,
+}
+
+impl VerifierSettings {
+    /// The stwo verifier, pinned to the expected programs
+    #[cfg(feature = "stwo")]
+    pub fn build(&self) -> ProofVerifier {
+        ProofVerifier::new(Arc::new(StwoVerifier)).with_expected_programs(self.expected_programs())
+    }
+
+    /// The mock verifier: this binary was built without the `stwo` feature
+    #[cfg(not(feature = "stwo"))]
+    pub fn build(&self) -> ProofVerifier {
+        ProofVerifier::mock()
+    }
+
+    pub fn expected_programs(&self) -> ExpectedPrograms {
+        ExpectedPrograms {
+            task_program_hash: self.task_program_hash,
+            bootloader_hash: self.bootloader_hash,
+            program_hash: self.program_hash,
+        }
+    }
+}
+
+/// Browser origins allowed per group of routes. An empty list allows
+/// same-origin requests only.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsSettings {
+    /// Origins allowed on `/proof-verify`, `/published-proofs` and `/health`, or `["*"]` for any
+    pub public_origins: Vec<String>,
+    /// Origins allowed to submit, read and cancel proof jobs and to use admin routes.
+    /// These carry UTXOs and ownership proofs, so `*` is not accepted.
+    pub manager_origins: Vec<String>,
+}
+
+impl Default for CorsSettings {
+    fn default() -> Self {
+        Self {
+            public_origins: vec!["*".to_string()],
+            manager_origins: Vec::new(),
+        }
+    }
+}
+
+impl CorsSettings {
+    /// Policy for the public verification routes
+    pub fn public_layer(&self) -> CorsLayer {
+        CorsLayer::new()
+            .allow_origin(allow_origin(&self.public_origins))
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers(Any)
+    }
+
+    /// Policy for proof generation and admin routes
+    pub fn manager_layer(&self) -> CorsLayer {
+        CorsLayer::new()
+            .allow_origin(allow_origin(&self.manager_origins))
+            .allow_methods([Method::GET, Method::POST, Method::DELETE])
+            .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+    }
+
+    fn validate(&self, problems: &mut Vec<String>) {
+        if self.public_origins.len() > 1 && self.public_origins.iter().any(|origin| origin == "*") {
+            problems.push("cors.public_origins cannot mix \"*\" with specific origins".to_string());
+        }
+        if self.manager_origins.iter().any(|origin| origin == "*") {
+            problems.push("cors.manager_origins must list origins explicitly, not \"*\"".to_string());
+        }
+
+        let public = self.public_origins.iter().map(|origin| ("cors.public_origins", origin));
+        let manager = self.manager_origins.iter().map(|origin| ("cors.manager_origins", origin));
+        for (setting, origin) in public.chain(manager).filter(|(_, origin)| *origin != "*") {
+            let valid = (origin.starts_with("http://") || origin.starts_with("https://"))
+                && !origin.ends_with('/')
+                && origin.parse::<HeaderValue>().is_ok();
+            if !valid {
+                problems.push(format!("{} entry '{}' must look like https://example.com", setting, origin));
+            }
+        }
+    }
+}
+
+fn allow_origin(origins: &[String]) -> AllowOrigin {
+    if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().filter_map(|origin| origin.parse().ok()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    Memory,
+    Sqlite,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreSettings {
+    pub backend: StoreBackend,
+    /// SQLite database, used by the `sqlite` backend
+    pub database: PathBuf,
+    pub artifacts_dir: PathBuf,
+    /// Published proofs verified at startup, see `PublishedProofs`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_dir: Option<PathBuf>,
+}
+
+impl Default for StoreSettings {
+    fn default() -> Self {
+        Self {
+            backend: StoreBackend::Memory,
+            database: PathBuf::from("zkpoor.db"),
+            artifacts_dir: PathBuf::from("artifacts"),
+            published_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ProverKind {
+    /// Fake proofs, for development
+    Mock,
+    /// A program following the `ExternalProver` protocol
+    External,
+    /// The Cairo toolchain (`cairo_program_runner` and `adapted_stwo`)
+    Toolchain,
+    /// In-process stwo, only built with the `stwo` feature
+    #[cfg(feature = "stwo")]
+    Stwo,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProverSettings {
+    pub backend: ProverKind,
+    /// External prover binary, or the Cairo executable (`zkpoor.executable.json`) proved by `stwo`
+    pub program: PathBuf,
+    /// Extra arguments for the external prover
+    pub args: Vec<String>,
+    /// Per-job working directories of the external and toolchain provers
+    pub work_dir: PathBuf,
+    /// Chain state and blocks each UTXO is proved against (`batch.json` from
+    /// `program/scripts/prove.py`), for the toolchain and stwo provers
+    pub chain_batch: PathBuf,
+    /// Cairo serde proof of that chain state (`proof_serde.json`)
+    pub chain_state_proof: PathBuf,
+    pub toolchain: ToolchainSettings,
+}
+
+impl Default for ProverSettings {
+    fn default() -> Self {
+        Self {
+            backend: ProverKind::Mock,
+            program: PathBuf::from("program.json"),
+            args: Vec::new(),
+            work_dir: PathBuf::from("jobs"),
+            chain_batch: PathBuf::from("batch.json"),
+            chain_state_proof: PathBuf::from("proof_serde.json"),
+            toolchain: ToolchainSettings::default(),
+        }
+    }
+}
+
+impl ProverSettings {
+    pub fn build(&self) -> Result<Arc<dyn ProverBackend>> {
+        Ok(match self.backend {
+            ProverKind::Mock => Arc::new(MockProver::new()),
+            ProverKind::External => Arc::new(
+                ExternalProver::new(&self.program, &self.work_dir).with_args(self.args.iter().cloned()),
+            ),
+            ProverKind::Toolchain => Arc::new(ToolchainProver::new(
+                self.toolchain.config(),
+                self.chain_data(),
+                &self.work_dir,
+            )),
+            #[cfg(feature = "stwo")]
+            ProverKind::Stwo => Arc::new(StwoProver::new(&self.program, self.chain_data())),
+        })
+    }
+
+    pub fn chain_data(&self) -> ChainDataFiles {
+        ChainDataFiles::new(&self.chain_batch, &self.chain_state_proof)
+    }
+}
+
+/// Binary and file locations, time limits and memory measurement for the
+/// `toolchain` prover, see `ToolchainConfig`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolchainSettings {
+    pub cairo_program_runner: PathBuf,
+    pub cairo_execute: PathBuf,
+    pub adapted_stwo: PathBuf,
+    pub bootloader: PathBuf,
+    pub executable: PathBuf,
+    pub prover_params: PathBuf,
+    pub cairo_runner_timeout_secs: u64,
+    pub cairo_execute_timeout_secs: u64,
+    pub prove_timeout_secs: u64,
+    /// Run each step under GNU `time -v` to record its peak memory
+    pub measure_memory: bool,
+    pub time_binary: PathBuf,
+}
+
+impl Default for ToolchainSettings {
+    fn default() -> Self {
+        let defaults = ToolchainConfig::default();
+        Self {
+            cairo_program_runner: defaults.cairo_program_runner,
+            cairo_execute: defaults.cairo_execute,
+            adapted_stwo: defaults.adapted_stwo,
+            bootloader: defaults.bootloader,
+            executable: defaults.executable,
+            prover_params: defaults.prover_params,
+            cairo_runner_timeout_secs: defaults.timeouts.cairo_runner.as_secs(),
+            cairo_execute_timeout_secs: defaults.timeouts.cairo_execute.as_secs(),
+            prove_timeout_secs: defaults.timeouts.prove.as_secs(),
+            measure_memory: true,
+            time_binary: PathBuf::from(DEFAULT_TIME_BINARY),
+        }
+    }
+}
+
+impl ToolchainSettings {
+    pub fn config(&self) -> ToolchainConfig {
+        ToolchainConfig {
+            cairo_program_runner: self.cairo_program_runner.clone(),
+            cairo_execute: self.cairo_execute.clone(),
+            adapted_stwo: self.adapted_stwo.clone(),
+            bootloader: self.bootloader.clone(),
+            executable: self.executable.clone(),
+            prover_params: self.prover_params.clone(),
+            // A missing `time` only costs the memory figures, see `Config::log_warnings`
+            time_binary: (self.measure_memory && self.time_binary.is_file()).then(|| self.time_binary.clone()),
+            timeouts: StepTimeouts {
+                cairo_runner: Duration::from_secs(self.cairo_runner_timeout_secs),
+                cairo_execute: Duration::from_secs(self.cairo_execute_timeout_secs),
+                prove: Duration::from_secs(self.prove_timeout_secs),
+            },
+            ..ToolchainConfig::default()
+        }
+    }
+
+    fn validate(&self, problems: &mut Vec<String>) {
+        let timeouts = [
+            ("cairo_runner_timeout_secs", self.cairo_runner_timeout_secs),
+            ("cairo_execute_timeout_secs", self.cairo_execute_timeout_secs),
+            ("prove_timeout_secs", self.prove_timeout_secs),
+        ];
+        for (setting, secs) in timeouts {
+            if secs == 0 {
+                problems.push(format!("prover.toolchain.{} must be positive", setting));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueSettings {
+    pub workers: usize,
+    pub max_depth: usize,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        let defaults = QueueConfig::default();
+        Self { workers: defaults.workers, max_depth: defaults.max_depth }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitSettings {
+    /// Largest request body accepted by `/proof-verify`
+    pub max_proof_bytes: usize,
+}
+
+impl Default for LimitSettings {
+    fn default() -> Self {
+        Self { max_proof_bytes: DEFAULT_MAX_PROOF_BYTES }
+    }
+}
+
+/// Comma-separated origins from the environment; an empty value clears the list
+fn origin_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|origin| !origin.is_empty()).map(String::from).collect()
+}
+
+impl Config {
+    /// Load from the process environment and `cli`, on top of the defaults
+    pub fn load(cli: &Cli) -> Result<Self> {
+        Self::load_from(Self::default(), cli, |name| std::env::var(name).ok())
+    }
+
+    /// Layer the configuration file, `env` and `cli` over `base`, then validate
+    pub fn load_from(base: Config, cli: &Cli, env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let path = cli.config.clone().or_else(|| env("ZKPOOR_CONFIG").map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => base.merge_file(&path)?,
+            None => base,
+        };
+        config.apply_env(&env)?;
+        config.apply_cli(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn merge_file(self, path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read config file {}: {}", path.display(), e))?;
+        self.merge_toml(&text)
+            .map_err(|e| anyhow!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    /// Settings present in `text` replace those of `self`, table by table
+    fn merge_toml(self, text: &str) -> Result<Self> {
+        fn merge(base: &mut toml::Value, overlay: toml::Value) {
+            match (base, overlay) {
+                (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+                    for (key, value) in overlay {
+                        match base.get_mut(&key) {
+                            Some(existing) => merge(existing, value),
+                            None => {
+                                base.insert(key, value);
+                            }
+                        }
+                    }
+                }
+                (base, overlay) => *base = overlay,
+            }
+        }
+
+        let overlay: toml::Value = toml::from_str(text)?;
+        let mut value = toml::Value::try_from(&self)?;
+        merge(&mut value, overlay);
+        Ok(value.try_into()?)
+    }
+
+    fn apply_env(&mut self, env: &impl Fn(&str) -> Option<String>) -> Result<()> {
+        fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T>
+        where
+            T::Err: std::fmt::Display,
+        {
+            value.parse().map_err(|e| anyhow!("Invalid {} '{}': {}", name, value, e))
+        }
+        fn choice<T: ValueEnum>(name: &str, value: &str) -> Result<T> {
+            T::from_str(value, true).map_err(|e| anyhow!("Invalid {} '{}': {}", name, value, e))
+        }
+        fn felt(name: &str, value: &str) -> Result<Felt> {
+            Felt::from_hex(value).map_err(|e| anyhow!("Invalid {} '{}': {}", name, value, e))
+        }
+
+        if let Some(bind) = env("ZKPOOR_BIND") {
+            self.bind = parse("ZKPOOR_BIND", &bind)?;
+        }
+        // Kept for hosts that only hand out a port
+        if let Some(port) = env("PORT") {
+            self.bind.set_port(parse("PORT", &port)?);
+        }
+        if let Some(network) = env("ZKPOOR_NETWORK") {
+            self.network = parse("ZKPOOR_NETWORK", &network)?;
+        }
+        if let Some(required) = env("ZKPOOR_AUTH_REQUIRED") {
+            self.auth.required = parse("ZKPOOR_AUTH_REQUIRED", &required)?;
+        }
+        if let Some(hash) = env("ZKPOOR_ADMIN_KEY_HASH") {
+            self.auth.admin_key_hash = Some(hash);
+        }
+        if let Some(verify) = env("ZKPOOR_VERIFY_SIGNATURES") {
+            self.ownership.verify_signatures = parse("ZKPOOR_VERIFY_SIGNATURES", &verify)?;
+        }
+        if let Some(operator) = env("ZKPOOR_OPERATOR") {
+            self.ownership.operator = operator;
+        }
+        if let Some(secs) = env("ZKPOOR_CHALLENGE_TTL_SECS") {
+            self.ownership.challenge_ttl_secs = parse("ZKPOOR_CHALLENGE_TTL_SECS", &secs)?;
+        }
+        if let Some(allow) = env("ZKPOOR_ALLOW_MOCK_PROOFS") {
+            self.verifier.allow_mock = parse("ZKPOOR_ALLOW_MOCK_PROOFS", &allow)?;
+        }
+        if let Some(hash) = env("ZKPOOR_PROGRAM_HASH") {
+            self.verifier.program_hash = Some(felt("ZKPOOR_PROGRAM_HASH", &hash)?);
+        }
+        if let Some(hash) = env("ZKPOOR_BOOTLOADER_HASH") {
+            self.verifier.bootloader_hash = Some(felt("ZKPOOR_BOOTLOADER_HASH", &hash)?);
+        }
+        if let Some(hash) = env("ZKPOOR_TASK_PROGRAM_HASH") {
+            self.verifier.task_program_hash = Some(felt("ZKPOOR_TASK_PROGRAM_HASH", &hash)?);
+        }
+        if let Some(origins) = env("ZKPOOR_CORS_PUBLIC_ORIGINS") {
+            self.cors.public_origins = origin_list(&origins);
+        }
+        if let Some(origins) = env("ZKPOOR_CORS_MANAGER_ORIGINS") {
+            self.cors.manager_origins = origin_list(&origins);
+        }
+        if let Some(store) = env("ZKPOOR_STORE") {
+            self.store.backend = choice("ZKPOOR_STORE", &store)?;
+        }
+        if let Some(database) = env("ZKPOOR_DATABASE") {
+            self.store.database = database.into();
+        }
+        if let Some(dir) = env("ZKPOOR_ARTIFACTS_DIR") {
+            self.store.artifacts_dir = dir.into();
+        }
+        if let Some(dir) = env("ZKPOOR_PUBLISHED_DIR") {
+            self.store.published_dir = Some(dir.into());
+        }
+        if let Some(prover) = env("ZKPOOR_PROVER") {
+            self.prover.backend = choice("ZKPOOR_PROVER", &prover)?;
+        }
+        if let Some(program) = env("ZKPOOR_PROGRAM") {
+            self.prover.program = program.into();
+        }
+        if let Some(dir) = env("ZKPOOR_WORK_DIR") {
+            self.prover.work_dir = dir.into();
+        }
+        if let Some(batch) = env("ZKPOOR_CHAIN_BATCH") {
+            self.prover.chain_batch = batch.into();
+        }
+        if let Some(proof) = env("ZKPOOR_CHAIN_STATE_PROOF") {
+            self.prover.chain_state_proof = proof.into();
+        }
+        let toolchain = &mut self.prover.toolchain;
+        if let Some(secs) = env("ZKPOOR_CAIRO_RUNNER_TIMEOUT_SECS") {
+            toolchain.cairo_runner_timeout_secs = parse("ZKPOOR_CAIRO_RUNNER_TIMEOUT_SECS", &secs)?;
+        }
+        if let Some(secs) = env("ZKPOOR_CAIRO_EXECUTE_TIMEOUT_SECS") {
+            toolchain.cairo_execute_timeout_secs = parse("ZKPOOR_CAIRO_EXECUTE_TIMEOUT_SECS", &secs)?;
+        }
+        if let Some(secs) = env("ZKPOOR_PROVE_TIMEOUT_SECS") {
+            toolchain.prove_timeout_secs = parse("ZKPOOR_PROVE_TIMEOUT_SECS", &secs)?;
+        }
+        if let Some(measure) = env("ZKPOOR_MEASURE_MEMORY") {
+            toolchain.measure_memory = parse("ZKPOOR_MEASURE_MEMORY", &measure)?;
+        }
+        if let Some(time) = env("ZKPOOR_TIME_BINARY") {
+            toolchain.time_binary = time.into();
+        }
+        if let Some(workers) = env("ZKPOOR_WORKERS") {
+            self.queue.workers = parse("ZKPOOR_WORKERS", &workers)?;
+        }
+        if let Some(depth) = env("ZKPOOR_QUEUE_DEPTH") {
+            self.queue.max_depth = parse("ZKPOOR_QUEUE_DEPTH", &depth)?;
+        }
+        if let Some(bytes) = env("ZKPOOR_MAX_PROOF_BYTES") {
+            self.limits.max_proof_bytes = parse("ZKPOOR_MAX_PROOF_BYTES", &bytes)?;
+        }
+        if let Some(filter) = env("RUST_LOG") {
+            self.log_filter = filter;
+        }
+        Ok(())
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(bind) = cli.bind {
+            self.bind = bind;
+        }
+        if let Some(network) = cli.network {
+            self.network = network;
+        }
+        if cli.no_auth {
+            self.auth.required = false;
+        }
+        if cli.no_signature_check {
+            self.ownership.verify_signatures = false;
+        }
+        if cli.allow_mock_proofs {
+            self.verifier.allow_mock = true;
+        }
+        if !cli.cors_public_origins.is_empty() {
+            self.cors.public_origins = cli.cors_public_origins.clone();
+        }
+        if !cli.cors_manager_origins.is_empty() {
+            self.cors.manager_origins = cli.cors_manager_origins.clone();
+        }
+        if let Some(store) = cli.store {
+            self.store.backend = store;
+        }
+        if let Some(database) = &cli.database {
+            self.store.database = database.clone();
+        }
+        if let Some(dir) = &cli.artifacts_dir {
+            self.store.artifacts_dir = dir.clone();
+        }
+        if let Some(dir) = &cli.published_dir {
+            self.store.published_dir = Some(dir.clone());
+        }
+        if let Some(prover) = cli.prover {
+            self.prover.backend = prover;
+        }
+        if let Some(program) = &cli.program {
+            self.prover.program = program.clone();
+        }
+        if let Some(batch) = &cli.chain_batch {
+            self.prover.chain_batch = batch.clone();
+        }
+        if let Some(proof) = &cli.chain_state_proof {
+            self.prover.chain_state_proof = proof.clone();
+        }
+        if let Some(secs) = cli.cairo_runner_timeout_secs {
+            self.prover.toolchain.cairo_runner_timeout_secs = secs;
+        }
+        if let Some(secs) = cli.cairo_execute_timeout_secs {
+            self.prover.toolchain.cairo_execute_timeout_secs = secs;
+        }
+        if let Some(secs) = cli.prove_timeout_secs {
+            self.prover.toolchain.prove_timeout_secs = secs;
+        }
+        if let Some(workers) = cli.workers {
+            self.queue.workers = workers;
+        }
+        if let Some(depth) = cli.queue_depth {
+            self.queue.max_depth = depth;
+        }
+        if let Some(bytes) = cli.max_proof_bytes {
+            self.limits.max_proof_bytes = bytes;
+        }
+        if let Some(filter) = &cli.log_filter {
+            self.log_filter = filter.clone();
+        }
+    }
+
+    /// Check the settings hang together, reporting every problem at once
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.log_filter) {
+            problems.push(format!("log_filter '{}': {}", self.log_filter, e));
+        }
+
+        self.auth.validate(&mut problems);
+        self.ownership.validate(&mut problems);
+        self.cors.validate(&mut problems);
+        self.prover.toolchain.validate(&mut problems);
+
+        if self.queue.workers == 0 {
+            problems.push("queue.workers must be at least 1".to_string());
+        }
+        if self.queue.max_depth == 0 {
+            problems.push("queue.max_depth must be at least 1".to_string());
+        }
+        if self.limits.max_proof_bytes == 0 {
+            problems.push("limits.max_proof_bytes must be positive".to_string());
+        }
+
+        if let Some(dir) = &self.store.published_dir {
+            if !dir.is_dir() {
+                problems.push(format!("store.published_dir {} is not a directory", dir.display()));
+            }
+        }
+
+        let mut require_file = |setting: &str, path: &Path| {
+            if !path.is_file() {
+                problems.push(format!("{} {} does not exist", setting, path.display()));
+            }
+        };
+        match self.prover.backend {
+            ProverKind::Mock => {}
+            ProverKind::External => require_file("prover.program", &self.prover.program),
+            #[cfg(feature = "stwo")]
+            ProverKind::Stwo => {
+                require_file("prover.program", &self.prover.program);
+                require_file("prover.chain_batch", &self.prover.chain_batch);
+                require_file("prover.chain_state_proof", &self.prover.chain_state_proof);
+            }
+            ProverKind::Toolchain => {
+                let toolchain = &self.prover.toolchain;
+                require_file("prover.toolchain.bootloader", &toolchain.bootloader);
+                require_file("prover.toolchain.executable", &toolchain.executable);
+                require_file("prover.toolchain.prover_params", &toolchain.prover_params);
+                require_file("prover.chain_batch", &self.prover.chain_batch);
+                require_file("prover.chain_state_proof", &self.prover.chain_state_proof);
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid configuration:\n  - {}", problems.join("\n  - ")))
+        }
+    }
+
+    /// Reject settings that need real STARK verification when `verifier` is
+    /// the mock: its proofs would be self-verified, and published proofs
+    /// checked, against the `mock_proof_` prefix only. A real verifier must
+    /// be pinned to the zkpoor program, or it accepts proofs of any program.
+    pub fn check_verifier(&self, verifier: &ProofVerifier) -> Result<()> {
+        let mut problems = Vec::new();
+        if !verifier.is_mock() {
+            if self.verifier.program_hash.is_none() {
+                problems.push("verifier.program_hash must be set, or proofs of any program are accepted".to_string());
+            }
+        } else {
+            if self.prover.backend != ProverKind::Mock {
+                problems.push(format!(
+                    "prover.backend {:?} needs a real STARK verifier; build with the stwo feature",
+                    self.prover.backend
+                ));
+            }
+            if self.store.published_dir.is_some() {
+                problems.push("store.published_dir needs a real STARK verifier; build with the stwo feature".to_string());
+            }
+            if !self.verifier.expected_programs().is_empty() {
+                problems.push("verifier program hashes need a real STARK verifier; mock proofs have no output".to_string());
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid verifier settings for the {} verifier:\n  - {}", verifier.name(), problems.join("\n  - ")))
+        }
+    }
+
+    /// Settings that are valid but probably not what a deployment wants
+    pub fn log_warnings(&self) {
+        if !self.auth.required {
+            tracing::warn!("Authentication is disabled: anyone can submit and read proof jobs");
+        } else if self.store.backend == StoreBackend::Memory && self.auth.admin_key_hash.is_none() {
+            tracing::warn!(
+                "No API key can be created in the memory store without an admin key, so every proof \
+                 management request will be rejected; set auth.admin_key_hash or use the sqlite store"
+            );
+        }
+        if !self.ownership.verify_signatures {
+            tracing::warn!("Signature checks are disabled: ownership proofs are accepted without verification");
+        }
+        if self.verifier.allow_mock {
+            tracing::warn!("Mock proofs are allowed: with the mock verifier, /proof-verify reports any mock proof as valid");
+        }
+        let toolchain = &self.prover.toolchain;
+        if self.prover.backend == ProverKind::Toolchain && toolchain.measure_memory && !toolchain.time_binary.is_file() {
+            tracing::warn!(
+                "{} not found: toolchain steps run without memory measurement",
+                toolchain.time_binary.display()
+            );
+        }
+    }
+
+    pub fn store_config(&self) -> StoreConfig {
+        match self.store.backend {
+            StoreBackend::Memory => StoreConfig::Memory,
+            StoreBackend::Sqlite => StoreConfig::Sqlite { path: self.store.database.clone() },
+        }
+    }
+
+    pub fn artifact_store(&self) -> ArtifactStore {
+        ArtifactStore::new(&self.store.artifacts_dir)
+    }
+
+    pub fn queue_config(&self) -> QueueConfig {
+        QueueConfig { workers: self.queue.workers, max_depth: self.queue.max_depth }
+    }
+
+    /// Create the configured bootstrap admin key, if any
+    pub async fn bootstrap_admin(&self, keys: &KeyManager) -> Result<()> {
+        if let Some(hash) = &self.auth.admin_key_hash {
+            let key = keys.bootstrap_admin(&hash.to_ascii_lowercase()).await?;
+            if key.is_revoked() {
+                tracing::warn!("The bootstrap admin key {} has been revoked", key.key_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Published proofs, verified with `verifier`; none unless a directory is set
+    pub async fn published_proofs(&self, verifier: &ProofVerifier) -> Result<PublishedProofs> {
+        match &self.store.published_dir {
+            Some(dir) => PublishedProofs::load(dir, verifier).await,
+            None => Ok(PublishedProofs::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(file: Option<&str>, env: &[(&str, &str)], args: &[&str]) -> Result<Config> {
+        let mut cli = Cli::try_parse_from(std::iter::once("zkpoor-api").chain(args.iter().copied())).unwrap();
+        let path = file.map(|text| {
+            let path = std::env::temp_dir().join(format!("zkpoor-config-{}.toml", uuid::Uuid::new_v4()));
+            std::fs::write(&path, text).unwrap();
+            path
+        });
+        cli.config = path.clone();
+        let env: HashMap<String, String> = env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+
+        let config = Config::load_from(Config::default(), &cli, |name| env.get(name).cloned());
+        if let Some(path) = path {
+            std::fs::remove_file(path).unwrap();
+        }
+        config
+    }
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(load(None, &[], &[]).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_layers_file_env_then_flags() {
+        let file = r#"
+            bind = "127.0.0.1:9000"
+
+            [queue]
+            workers = 2
+            max_depth = 4
+
+            [cors]
+            manager_origins = ["https://manager.zkpoor.example"]
+        "#;
+        let env = [("ZKPOOR_WORKERS", "3"), ("ZKPOOR_STORE", "sqlite"), ("ZKPOOR_NETWORK", "signet")];
+        let config = load(Some(file), &env, &["--workers", "5", "--log-filter", "info", "--no-signature-check"]).unwrap();
+
+        assert_eq!(config.bind, "127.0.0.1:9000".parse().unwrap());
+        assert_eq!(config.queue.workers, 5);
+        assert_eq!(config.queue.max_depth, 4);
+        assert_eq!(config.store.backend, StoreBackend::Sqlite);
+        assert_eq!(config.store.database, PathBuf::from("zkpoor.db"));
+        assert_eq!(config.cors.public_origins, ["*"]);
+        assert_eq!(config.cors.manager_origins, ["https://manager.zkpoor.example"]);
+        assert_eq!(config.log_filter, "info");
+        assert_eq!(config.network, Network::Signet);
+        assert!(!config.ownership.verify_signatures);
+        assert_eq!(config.ownership.operator, DEFAULT_OPERATOR);
+    }
+
+    #[test]
+    fn test_port_overrides_bind_port() {
+        let config = load(None, &[("ZKPOOR_BIND", "127.0.0.1:1"), ("PORT", "3000")], &[]).unwrap();
+        assert_eq!(config.bind, "127.0.0.1:3000".parse().unwrap());
+        assert!(load(None, &[("PORT", "http")], &[]).is_err());
+    }
+
+    #[test]
+    fn test_toolchain_limits_and_memory_measurement() {
+        let file = "[prover.toolchain]\ncairo_runner_timeout_secs = 60\nmeasure_memory = false\n";
+        let env = [("ZKPOOR_PROVE_TIMEOUT_SECS", "7200"), ("ZKPOOR_TIME_BINARY", "/opt/time")];
+        let config = load(Some(file), &env, &["--cairo-execute-timeout-secs", "30"]).unwrap();
+
+        let toolchain = config.prover.toolchain.config();
+        assert_eq!(toolchain.timeouts.cairo_runner, Duration::from_secs(60));
+        assert_eq!(toolchain.timeouts.cairo_execute, Duration::from_secs(30));
+        assert_eq!(toolchain.timeouts.prove, Duration::from_secs(7200));
+        assert_eq!(config.prover.toolchain.time_binary, PathBuf::from("/opt/time"));
+        assert_eq!(toolchain.time_binary, None);
+
+        let error = load(None, &[("ZKPOOR_PROVE_TIMEOUT_SECS", "0")], &[]).unwrap_err();
+        assert!(error.to_string().contains("prover.toolchain.prove_timeout_secs must be positive"));
+    }
+
+    #[test]
+    fn test_mock_verifier_only_serves_mock_proofs() {
+        let config = load(None, &[], &[]).unwrap();
+        config.check_verifier(&ProofVerifier::mock()).unwrap();
+        assert!(!config.verifier.allow_mock);
+        assert!(load(None, &[("ZKPOOR_ALLOW_MOCK_PROOFS", "true")], &[]).unwrap().verifier.allow_mock);
+        assert!(load(None, &[], &["--allow-mock-proofs"]).unwrap().verifier.allow_mock);
+
+        let dir = std::env::temp_dir();
+        let config = load(None, &[], &["--published-dir", dir.to_str().unwrap(), "--prover", "external", "--program", "/bin/sh"]).unwrap();
+        let message = config.check_verifier(&ProofVerifier::mock()).unwrap_err().to_string();
+        assert!(message.contains("prover.backend External"));
+        assert!(message.contains("store.published_dir"));
+    }
+
+    #[cfg(not(feature = "stwo"))]
+    #[test]
+    fn test_stwo_prover_needs_the_stwo_feature() {
+        assert!(Cli::try_parse_from(["zkpoor-api", "--prover", "stwo"]).is_err());
+        assert!(load(None, &[("ZKPOOR_PROVER", "stwo")], &[]).is_err());
+        let error = load(Some("[prover]\nbackend = \"stwo\"\n"), &[], &[]).unwrap_err();
+        assert!(format!("{:#}", error).contains("unknown variant `stwo`"), "{:#}", error);
+    }
+
+    #[test]
+    fn test_real_verifier_is_pinned_to_the_program() {
+        struct Real;
+        impl zkpoor_core::stark::StarkVerifier for Real {
+            fn name(&self) -> &'static str {
+                "real"
+            }
+            fn verify(&self, _proof: &str) -> Result<zkpoor_core::stark::StarkVerdict> {
+                unreachable!()
+            }
+        }
+        let real = ProofVerifier::new(Arc::new(Real));
+
+        let message = load(None, &[], &[]).unwrap().check_verifier(&real).unwrap_err().to_string();
+        assert!(message.contains("verifier.program_hash must be set"));
+
+        let file = "[verifier]\nprogram_hash = \"0x1f\"\nbootloader_hash = \"0x2\"\n";
+        let config = load(Some(file), &[("ZKPOOR_TASK_PROGRAM_HASH", "0x3")], &[]).unwrap();
+        config.check_verifier(&real).unwrap();
+        assert_eq!(config.verifier.expected_programs().program_hash, Some(Felt::from(0x1fu32)));
+        assert_eq!(config.verifier.expected_programs().task_program_hash, Some(Felt::from(3u32)));
+        assert!(config.check_verifier(&ProofVerifier::mock()).unwrap_err().to_string().contains("program hashes"));
+
+        assert!(load(None, &[("ZKPOOR_PROGRAM_HASH", "31")], &[]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_settings() {
+        let error = load(Some("[queue]\nworkres = 2\n"), &[], &[]).unwrap_err();
+        assert!(error.to_string().contains("workres"));
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let file = r#"
+            [queue]
+            workers = 0
+
+            [cors]
+            public_origins = ["*", "https://zkpoor.example"]
+            manager_origins = ["*"]
+        "#;
+        let error = load(Some(file), &[], &["--prover", "external", "--program", "/nonexistent"]).unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("queue.workers"));
+        assert!(message.contains("cors.public_origins"));
+        assert!(message.contains("cors.manager_origins"));
+        assert!(message.contains("prover.program"));
+
+        let hash = zkpoor_core::auth::hash_secret("zkpoor_secret");
+        let config = load(None, &[("ZKPOOR_ADMIN_KEY_HASH", &hash)], &[]).unwrap();
+        assert_eq!(config.auth.admin_key_hash, Some(hash));
+        let error = load(None, &[("ZKPOOR_ADMIN_KEY_HASH", "zkpoor_secret")], &[]).unwrap_err();
+        assert!(error.to_string().contains("auth.admin_key_hash"));
+
+        let config = load(Some("[ownership]\noperator = \"Acme Treasury\"\n"), &[("ZKPOOR_CHALLENGE_TTL_SECS", "60")], &[]).unwrap();
+        assert!(config.ownership.challenges().issue().message.contains("operator: Acme Treasury"));
+        let error = load(None, &[("ZKPOOR_OPERATOR", " "), ("ZKPOOR_CHALLENGE_TTL_SECS", "0")], &[]).unwrap_err();
+        assert!(error.to_string().contains("ownership.operator"));
+        assert!(error.to_string().contains("ownership.challenge_ttl_secs"));
+
+        let error = load(None, &[("ZKPOOR_CHAIN_BATCH", "/nonexistent/batch.json")], &["--prover", "toolchain"]).unwrap_err();
+        assert!(error.to_string().contains("prover.chain_batch /nonexistent/batch.json does not exist"));
+    }
+}
@@ -0,0 +1,130 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use zkpoor_core::Error;
+
+use crate::types::{ApiError, ItemError};
+
+/// Handler error: a core `Error` rendered as `ApiError` JSON with the matching status
+#[derive(Debug)]
+pub struct AppError(pub Error);
+
+impl<E: Into<Error>> From<E> for AppError {
+    fn from(error: E) -> Self {
+        AppError(error.into())
+    }
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match &self.0 {
+            Error::InvalidRequest(_)
+            | Error::InvalidUtxo { .. }
+            | Error::DuplicateOutpoint { .. }
+            | Error::ConflictingOutpoint { .. }
+            | Error::InvalidUtxos(_)
+            | Error::BadSignature { .. }
+            | Error::BadChallenge { .. }
+            | Error::ProofMalformed(_) => StatusCode::BAD_REQUEST,
+            Error::ProofInvalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Error::Forbidden(_) => StatusCode::FORBIDDEN,
+            Error::ProofNotFound(_)
+            | Error::ArtifactNotFound(_)
+            | Error::PublishedProofNotFound(_)
+            | Error::KeyNotFound(_) => StatusCode::NOT_FOUND,
+            Error::ProofFinished { .. } => StatusCode::CONFLICT,
+            Error::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::QueueFull(_) => StatusCode::TOO_MANY_REQUESTS,
+            Error::BackendUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Error::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Short human-readable summary; `details` carries the specifics
+    fn title(&self) -> &'static str {
+        match &self.0 {
+            Error::InvalidRequest(_) => "Invalid request",
+            Error::InvalidUtxo { .. } => "Invalid UTXO",
+            Error::DuplicateOutpoint { .. } => "Duplicate outpoint",
+            Error::ConflictingOutpoint { .. } => "Conflicting outpoint",
+            Error::InvalidUtxos(_) => "Invalid UTXOs",
+            Error::BadSignature { .. } => "Invalid ownership proof",
+            Error::BadChallenge { .. } => "Invalid challenge",
+            Error::ProofMalformed(_) => "Malformed proof",
+            Error::ProofInvalid(_) => "Invalid proof",
+            Error::Unauthorized(_) => "Unauthorized",
+            Error::Forbidden(_) => "Forbidden",
+            Error::KeyNotFound(_) => "API key not found",
+            Error::ProofNotFound(_) => "Proof not found",
+            Error::ArtifactNotFound(_) => "Proof artifact not found",
+            Error::PublishedProofNotFound(_) => "Published proof not found",
+            Error::ProofFinished { .. } => "Proof already finished",
+            Error::PayloadTooLarge { .. } => "Request too large",
+            Error::QueueFull(_) => "Proving queue is full",
+            Error::BackendUnavailable { .. } => "Backend unavailable",
+            Error::Internal(_) => "Internal server error",
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::error!("{}: {:#}", self.title(), self.0);
+        } else {
+            tracing::warn!("{}: {}", self.title(), self.0);
+        }
+
+        let body = ApiError {
+            code: self.0.code().to_string(),
+            error: self.title().to_string(),
+            // Internal errors can name files, queries or panics; they are only logged
+            details: match &self.0 {
+                Error::Internal(_) => None,
+                error => Some(format!("{:#}", error)),
+            },
+            errors: match &self.0 {
+                Error::InvalidUtxos(problems) => problems.iter()
+                    .map(|problem| ItemError {
+                        index: problem.index(),
+                        code: problem.code().to_string(),
+                        details: problem.to_string(),
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            },
+        };
+        let mut response = (status, Json(body)).into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response.headers_mut().insert(header::WWW_AUTHENTICATE, header::HeaderValue::from_static("Bearer"));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use zkpoor_core::queue::QueueFull;
+
+    async fn render(error: Error) -> serde_json::Value {
+        let response = AppError(error).into_response();
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_internal_errors_have_no_details() {
+        let error = anyhow::anyhow!("no such table: proofs").context("Failed to open /var/lib/zkpoor/zkpoor.db");
+        let body = render(Error::Internal(error)).await;
+        assert_eq!(body["code"], "internal");
+        assert_eq!(body["error"], "Internal server error");
+        assert!(body["details"].is_null());
+
+        let body = render(QueueFull { max_depth: 16 }.into()).await;
+        assert_eq!(body["details"], "Proving queue is full (16 jobs waiting)");
+    }
+}
@@ -0,0 +1,138 @@
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Multipart, Query, Request},
+    http::{header, StatusCode},
+    Json,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use zkpoor_core::Error;
+
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Axum's default request body limit, which applies outside `/proof-verify`
+const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// `Json` whose rejections are API errors, so a malformed txid or amount is
+/// an `invalid_request` naming the offending field, e.g. `utxos[0].txid`
+pub struct ApiJson<T>(pub T);
+
+#[async_trait]
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for ApiJson<T> {
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await
+            .map_err(|e| rejected(e.status(), e.body_text(), DEFAULT_BODY_LIMIT))?;
+        Ok(ApiJson(value))
+    }
+}
+
+/// A proof submitted to `/proof-verify`, in any of the accepted forms:
+///
+/// - `application/json`: `{"proof": ..., "public_inputs": ...}`, either as
+///   strings or as inline JSON
+/// - `application/octet-stream`: the raw proof, `public_inputs` in the query
+/// - `multipart/form-data`: a `proof` file and an optional `public_inputs` field
+/// - no body: `proof` and `public_inputs` in the query string
+pub struct VerifyInput {
+    pub proof: String,
+    pub public_inputs: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyQuery {
+    pub proof: Option<String>,
+    pub public_inputs: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyBody {
+    proof: Value,
+    #[serde(default)]
+    public_inputs: Option<Value>,
+}
+
+/// JSON strings are taken as-is, anything else is passed on serialized
+fn json_text(value: Value) -> String {
+    match value {
+        Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
+
+fn utf8_proof(bytes: Bytes) -> Result<String, Error> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| Error::ProofMalformed("Proof is not valid UTF-8".to_string()))
+}
+
+/// Body extraction failures, with oversized bodies reported as such
+fn rejected(status: StatusCode, reason: String, limit: usize) -> Error {
+    if status == StatusCode::PAYLOAD_TOO_LARGE {
+        Error::PayloadTooLarge { limit }
+    } else {
+        Error::InvalidRequest(reason)
+    }
+}
+
+#[async_trait]
+impl FromRequest<AppState> for VerifyInput {
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let limit = state.max_proof_bytes;
+        let Query(query) = Query::<VerifyQuery>::try_from_uri(req.uri())
+            .map_err(|e| Error::InvalidRequest(e.body_text()))?;
+        let mime = req.headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase());
+
+        let (proof, public_inputs) = match mime.as_deref() {
+            None => (query.proof, query.public_inputs),
+            Some("application/json") => {
+                let Json(body) = Json::<VerifyBody>::from_request(req, state).await
+                    .map_err(|e| rejected(e.status(), e.body_text(), limit))?;
+                (Some(json_text(body.proof)), body.public_inputs.map(json_text))
+            }
+            Some("application/octet-stream") => {
+                let bytes = Bytes::from_request(req, state).await
+                    .map_err(|e| rejected(e.status(), e.body_text(), limit))?;
+                (Some(utf8_proof(bytes)?), query.public_inputs)
+            }
+            Some("multipart/form-data") => {
+                let mut multipart = Multipart::from_request(req, state).await
+                    .map_err(|e| rejected(e.status(), e.body_text(), limit))?;
+                let (mut proof, mut public_inputs) = (None, query.public_inputs);
+                while let Some(field) = multipart.next_field().await
+                    .map_err(|e| rejected(e.status(), e.body_text(), limit))?
+                {
+                    match field.name() {
+                        Some("proof") => {
+                            let bytes = field.bytes().await
+                                .map_err(|e| rejected(e.status(), e.body_text(), limit))?;
+                            proof = Some(utf8_proof(bytes)?);
+                        }
+                        Some("public_inputs") => {
+                            public_inputs = Some(field.text().await
+                                .map_err(|e| rejected(e.status(), e.body_text(), limit))?);
+                        }
+                        _ => {}
+                    }
+                }
+                (proof, public_inputs)
+            }
+            Some(other) => {
+                return Err(Error::InvalidRequest(format!(
+                    "Unsupported content type '{}', expected application/json, application/octet-stream or multipart/form-data",
+                    other
+                )).into());
+            }
+        };
+
+        let proof = proof.ok_or_else(|| Error::InvalidRequest("Missing 'proof'".to_string()))?;
+        Ok(Self { proof, public_inputs })
+    }
+}
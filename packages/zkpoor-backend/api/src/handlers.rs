@@ -1,215 +1,331 @@
 use axum::{
-    extract::{Path, Query},
-    http::StatusCode,
-    response::Json,
+    body::Body,
+    extract::{Extension, Path, State},
+    http::header,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
 };
-use serde::Deserialize;
+use futures::{Stream, stream};
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 use chrono::Utc;
+use tokio_util::io::ReaderStream;
+use zkpoor_core::auth::StreamToken;
+use zkpoor_core::challenge::Challenge;
+use zkpoor_core::proof::ProofGenerator;
+use zkpoor_core::published::PublishedProof;
+use zkpoor_core::types::VerificationResult;
+use zkpoor_core::Error;
 
+use crate::auth::Caller;
+use crate::error::AppError;
+use crate::extract::{ApiJson, VerifyInput};
+use crate::state::AppState;
 use crate::types::*;
-use zkpoor_core::proof::{ProofGenerator, ProofVerifier};
 
-#[derive(Debug, Deserialize)]
-pub struct VerifyQuery {
-    pub proof: String,
-    pub public_inputs: String,
+/// POST /challenge - Issue a single-use message for the ownership proofs of one `/prove`
+pub async fn challenge_handler(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<Challenge>, AppError> {
+    caller.require(Role::Manager)?;
+    Ok(Json(state.generator.issue_challenge()))
 }
 
 /// POST /prove - Generate a proof for the given UTXOs
 pub async fn prove_handler(
-    Json(request): Json<ProveRequest>,
-) -> Result<Json<ProveResponse>, (StatusCode, Json<ApiError>)> {
-    tracing::info!("Received proof generation request with {} UTXOs", request.utxos.len());
-    
-    // Validate input
-    if request.utxos.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ApiError {
-                error: "No UTXOs provided".to_string(),
-                details: None,
-            }),
-        ));
-    }
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    ApiJson(request): ApiJson<ProveRequest>,
+) -> Result<Json<ProveResponse>, AppError> {
+    caller.require(Role::Manager)?;
+    tracing::info!(
+        "Received proof generation request with {} UTXOs from key {:?}",
+        request.utxos.len(),
+        caller.key_id()
+    );
 
-    if request.utxos.len() != request.ownership_proofs.len() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ApiError {
-                error: "Mismatch between UTXOs and ownership proofs".to_string(),
-                details: Some("Each UTXO must have a corresponding ownership proof".to_string()),
-            }),
-        ));
-    }
+    let proof_id = state.generator
+        .generate_proof_async(&request.utxos, &request.ownership_proofs, request.priority, caller.key_id())
+        .await?;
 
-    // Generate proof using core library (mocked for now)
-    let proof_generator = ProofGenerator::new();
-    match proof_generator.generate_proof_async(&request.utxos, &request.ownership_proofs).await {
-        Ok(proof_id) => {
-            tracing::info!("Proof generation started with ID: {}", proof_id);
-            Ok(Json(ProveResponse {
-                proof_id,
-                status: ProofStatus::Pending,
-                message: "Proof generation initiated successfully".to_string(),
-            }))
-        }
-        Err(e) => {
-            tracing::error!("Failed to generate proof: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiError {
-                    error: "Failed to initiate proof generation".to_string(),
-                    details: Some(e.to_string()),
-                }),
-            ))
-        }
-    }
+    tracing::info!("Proof generation started with ID: {}", proof_id);
+    let utxos = request.utxos.iter()
+        .map(|utxo| {
+            let template = utxo.script_pubkey.template();
+            UtxoSummary {
+                outpoint: utxo.outpoint(),
+                amount: utxo.amount,
+                script_type: template.name(),
+                address: template.address(state.network),
+            }
+        })
+        .collect();
+    Ok(Json(ProveResponse {
+        proof_id,
+        status: ProofStatus::Pending,
+        utxos,
+        message: "Proof generation initiated successfully".to_string(),
+    }))
 }
 
 /// GET /proof/{proof_id} - Get proof data by ID
 pub async fn get_proof_handler(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Path(proof_id): Path<Uuid>,
-) -> Result<Json<ProofData>, (StatusCode, Json<ApiError>)> {
+) -> Result<Json<ProofData>, AppError> {
     tracing::info!("Fetching proof data for ID: {}", proof_id);
     
-    let proof_generator = ProofGenerator::new();
-    match proof_generator.get_proof_status(proof_id).await {
-        Ok(Some(proof_data)) => {
-            tracing::info!("Found proof data for ID: {} with status: {:?}", proof_id, proof_data.status);
-            Ok(Json(proof_data))
-        }
-        Ok(None) => {
-            tracing::warn!("Proof not found for ID: {}", proof_id);
-            Err((
-                StatusCode::NOT_FOUND,
-                Json(ApiError {
-                    error: "Proof not found".to_string(),
-                    details: Some(format!("No proof found with ID: {}", proof_id)),
-                }),
-            ))
-        }
-        Err(e) => {
-            tracing::error!("Error fetching proof {}: {}", proof_id, e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiError {
-                    error: "Failed to fetch proof data".to_string(),
-                    details: Some(e.to_string()),
-                }),
-            ))
-        }
-    }
+    let proof_data = caller.view(state.generator.get_proof(proof_id).await?)?;
+    tracing::info!("Found proof data for ID: {} with status: {:?}", proof_id, proof_data.status);
+    Ok(Json(proof_data))
 }
 
-/// POST /proof-verify - Verify a proof
-pub async fn verify_proof_handler(
-    Query(params): Query<VerifyQuery>,
-) -> Result<Json<VerifyResponse>, (StatusCode, Json<ApiError>)> {
-    tracing::info!("Received proof verification request");
-    
-    // Validate query parameters
-    if params.proof.is_empty() || params.public_inputs.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ApiError {
-                error: "Missing required parameters".to_string(),
-                details: Some("Both 'proof' and 'public_inputs' parameters are required".to_string()),
-            }),
-        ));
+/// GET /proof/{proof_id}/events - Stream status and stage changes as Server-Sent Events
+pub async fn proof_events_handler(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(proof_id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    tracing::info!("Streaming events for proof {}", proof_id);
+
+    // Subscribe before reading the record so no transition falls in between
+    let events = state.generator.subscribe();
+    let current = state.generator.get_proof(proof_id).await?;
+    caller.view(current.clone())?;
+
+    let stream = proof_event_stream(Arc::clone(&state.generator), caller, current, events);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// POST /proof/{proof_id}/events/token - Token for following the proof's events with `?token=`
+pub async fn stream_token_handler(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(proof_id): Path<Uuid>,
+) -> Result<Json<StreamToken>, AppError> {
+    caller.view(state.generator.get_proof(proof_id).await?)?;
+    Ok(Json(state.keys.issue_stream_token(caller.0.as_ref(), proof_id)))
+}
+
+/// One `proof` event per change of the record, starting with its current
+/// state and ending after a terminal status, as `caller` may see it
+fn proof_event_stream(
+    generator: Arc<ProofGenerator>,
+    caller: Caller,
+    current: ProofData,
+    events: broadcast::Receiver<ProofData>,
+) -> impl Stream<Item = Result<Event, axum::Error>> {
+    struct Cursor {
+        generator: Arc<ProofGenerator>,
+        caller: Caller,
+        proof_id: Uuid,
+        events: broadcast::Receiver<ProofData>,
+        next: Option<ProofData>,
+        last: Option<(u8, usize)>,
     }
 
-    // Verify proof using core library (mocked for now)
-    let proof_verifier = ProofVerifier::new();
-    match proof_verifier.verify_proof(&params.proof, &params.public_inputs).await {
-        Ok((is_valid, total_amount)) => {
-            let message = if is_valid {
-                "Proof verification successful".to_string()
-            } else {
-                "Proof verification failed".to_string()
-            };
-            
-            tracing::info!("Proof verification result: valid={}, amount={:?}", is_valid, total_amount);
-            
-            Ok(Json(VerifyResponse {
-                is_valid,
-                total_amount,
-                verified_at: Utc::now(),
-                message,
-            }))
+    // Records only move forward, so (status, stages entered) orders them and
+    // lets us drop updates already covered by the initial read
+    fn version(proof_data: &ProofData) -> (u8, usize) {
+        let status = match proof_data.status {
+            ProofStatus::Pending => 0,
+            ProofStatus::InProgress => 1,
+            _ => 2,
+        };
+        (status, proof_data.stages.len())
+    }
+
+    let cursor = Cursor {
+        generator,
+        caller,
+        proof_id: current.proof_id,
+        events,
+        next: Some(current),
+        last: None,
+    };
+    stream::unfold(cursor, |mut cursor| async move {
+        if cursor.last.is_some_and(|(status, _)| status == 2) {
+            return None;
         }
-        Err(e) => {
-            tracing::error!("Error during proof verification: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiError {
-                    error: "Proof verification failed".to_string(),
-                    details: Some(e.to_string()),
-                }),
-            ))
+
+        let proof_data = loop {
+            let candidate = match cursor.next.take() {
+                Some(proof_data) => proof_data,
+                None => match cursor.events.recv().await {
+                    Ok(proof_data) => proof_data,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Event stream lagged by {} updates, reloading", skipped);
+                        match cursor.generator.get_proof_status(cursor.proof_id).await {
+                            Ok(Some(proof_data)) => proof_data,
+                            _ => return None,
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+            };
+            if candidate.proof_id == cursor.proof_id && Some(version(&candidate)) > cursor.last {
+                break candidate;
+            }
+        };
+
+        cursor.last = Some(version(&proof_data));
+        let proof_data = cursor.caller.view(proof_data).ok()?;
+        let event = Event::default().event("proof").json_data(&proof_data);
+        Some((event, cursor))
+    })
+}
+
+/// DELETE /proof/{proof_id} - Cancel a queued or running proof
+pub async fn cancel_proof_handler(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(proof_id): Path<Uuid>,
+) -> Result<Json<ProofData>, AppError> {
+    tracing::info!("Cancelling proof {}", proof_id);
+
+    caller.check_cancel(&state.generator.get_proof(proof_id).await?)?;
+    let proof_data = state.generator.cancel_proof(proof_id).await?;
+    Ok(Json(caller.view(proof_data)?))
+}
+
+/// GET /proof/{proof_id}/artifact - Stream the proof artifact
+pub async fn get_proof_artifact_handler(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(proof_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    tracing::info!("Downloading proof artifact for ID: {}", proof_id);
+
+    let proof_data = caller.view(state.generator.get_proof(proof_id).await?)?;
+    let artifact = proof_data.proof_artifact.ok_or_else(|| {
+        Error::ArtifactNotFound(format!("proof {} has no artifact (status: {:?})", proof_id, proof_data.status))
+    })?;
+    let file = state.generator.artifacts().open(&artifact).await?
+        .ok_or_else(|| Error::ArtifactNotFound(format!("{} is missing from storage", artifact.sha256)))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_LENGTH, artifact.size.to_string()),
+            (header::ETAG, format!("\"{}\"", artifact.sha256)),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.json\"", artifact.sha256),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response())
+}
+
+/// POST /proof-verify - Verify a proof sent as JSON, raw bytes, multipart upload or query string
+pub async fn verify_proof_handler(
+    State(state): State<AppState>,
+    input: VerifyInput,
+) -> Result<Json<VerifyResponse>, AppError> {
+    tracing::info!("Received proof verification request ({} bytes)", input.proof.len());
+    // The mock verifier only checks a prefix; never report its verdict as real
+    if state.verifier.is_mock() && !state.allow_mock_proofs {
+        return Err(Error::BackendUnavailable {
+            backend: state.verifier.name().to_string(),
+            reason: "this server has no STARK verifier; build zkpoor-api with the stwo feature to verify proofs".to_string(),
         }
+        .into());
     }
-}
 
-/// GET /verify-hardcoded-proof - Verify a hardcoded proof from JSON file
-pub async fn verify_hardcoded_proof_handler() -> Result<Json<VerifyResponse>, (StatusCode, Json<ApiError>)> {
-    tracing::info!("Received hardcoded proof verification request");
+    let result = state.verifier.verify_proof(&input.proof, input.public_inputs.as_deref()).await?;
+    let message = verification_message(&result);
     
-    // Load and parse the hardcoded proof from the JSON file
-    // Try multiple potential paths since the working directory could vary
-    let possible_paths = [
-        "../../proof.json",      // from packages/zkpoor-backend/
-        "../../../proof.json",   // from packages/zkpoor-backend/api/ 
-        "proof.json",            // if running from root
-        "../proof.json",         // if running from packages/
-    ];
+    tracing::info!("Proof verification result: valid={}, claimed amount={:?}", result.is_valid, result.claimed_total_amount);
     
-    let mut proof_content = String::new();
-    let mut found_path = None;
-    
-    for path in &possible_paths {
-        match std::fs::read_to_string(path) {
-            Ok(content) => {
-                proof_content = content;
-                found_path = Some(path);
-                break;
-            }
-            Err(_) => continue, // Try next path
-        }
+    Ok(Json(VerifyResponse {
+        is_valid: result.is_valid,
+        claimed_total_amount: result.claimed_total_amount,
+        public_output: result.public_output,
+        verified_at: Utc::now(),
+        message,
+    }))
+}
+
+/// GET /published-proofs - List the operator's published proofs
+pub async fn list_published_proofs_handler(
+    State(state): State<AppState>,
+) -> Json<Vec<PublishedProofResponse>> {
+    Json(state.published.iter().map(published_proof_response).collect())
+}
+
+/// GET /published-proofs/{name} - Verification result of a published proof
+pub async fn get_published_proof_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<PublishedProofResponse>, AppError> {
+    let proof = state.published.get(&name).ok_or(Error::PublishedProofNotFound(name))?;
+    Ok(Json(published_proof_response(proof)))
+}
+
+fn published_proof_response(proof: &PublishedProof) -> PublishedProofResponse {
+    PublishedProofResponse {
+        name: proof.name.clone(),
+        sha256: proof.sha256.clone(),
+        size: proof.size,
+        is_valid: proof.result.is_valid,
+        public_output: proof.result.public_output.clone(),
+        verified_at: proof.verified_at,
+        message: verification_message(&proof.result),
     }
-    match found_path {
-        Some(path) => {
-            // For now, we'll just return a fake success verification
-            // without actually parsing the complex proof structure
-            tracing::info!("Successfully loaded proof.json file from {} ({} bytes)", path, proof_content.len());
-            
-            // Return fake success response
-            Ok(Json(VerifyResponse {
-                is_valid: true,
-                total_amount: Some(325906414), // Demo amount in satoshis (~3.26 BTC)
-                verified_at: Utc::now(),
-                message: "Hardcoded proof verification successful - this is a demo response".to_string(),
-            }))
-        }
-        None => {
-            tracing::error!("Failed to find proof.json file at any of the expected paths");
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiError {
-                    error: "Failed to load hardcoded proof".to_string(),
-                    details: Some("Could not find proof.json at any of the expected paths".to_string()),
-                }),
-            ))
-        }
+}
+
+fn verification_message(result: &VerificationResult) -> String {
+    match (result.is_valid, &result.reason) {
+        (true, _) => "Proof verification successful".to_string(),
+        (false, Some(reason)) => format!("Proof verification failed: {}", reason),
+        (false, None) => "Proof verification failed".to_string(),
     }
 }
 
-/// GET /health - Health check endpoint
-pub async fn health_handler() -> Json<HashMap<String, String>> {
+/// POST /admin/keys - Issue an API key; the secret is only returned here
+pub async fn create_key_handler(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    ApiJson(request): ApiJson<CreateKeyRequest>,
+) -> Result<Json<CreateKeyResponse>, AppError> {
+    caller.require(Role::Admin)?;
+
+    let issued = state.keys.create(&request.name, &request.roles).await?;
+    Ok(Json(CreateKeyResponse { key: issued.key, secret: issued.secret }))
+}
+
+/// GET /admin/keys - List API keys, including revoked ones
+pub async fn list_keys_handler(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Json<Vec<ApiKey>>, AppError> {
+    caller.require(Role::Admin)?;
+    Ok(Json(state.keys.list().await?))
+}
+
+/// DELETE /admin/keys/{key_id} - Revoke an API key
+pub async fn revoke_key_handler(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(key_id): Path<Uuid>,
+) -> Result<Json<ApiKey>, AppError> {
+    caller.require(Role::Admin)?;
+    Ok(Json(state.keys.revoke(key_id).await?))
+}
+
+/// GET /health - Health check endpoint, naming the STARK verifier in use
+pub async fn health_handler(State(state): State<AppState>) -> Json<HashMap<String, String>> {
     let mut response = HashMap::new();
     response.insert("status".to_string(), "healthy".to_string());
     response.insert("timestamp".to_string(), Utc::now().to_rfc3339());
     response.insert("service".to_string(), "zkpoor-api".to_string());
+    response.insert("verifier".to_string(), state.verifier.name().to_string());
     Json(response)
 }
\ No newline at end of file
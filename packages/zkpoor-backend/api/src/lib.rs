@@ -1,6 +1,12 @@
+pub mod auth;
+pub mod config;
+pub mod error;
+pub mod extract;
 pub mod handlers;
 pub mod routes;
 pub mod server;
+pub mod state;
 pub mod types;
 
 pub use server::create_app;
+pub use state::AppState;
@@ -1,11 +1,40 @@
-use zkpoor_api::server::run_server;
+use clap::Parser;
+use zkpoor_api::auth::run_key_command;
+use zkpoor_api::config::{Cli, Command, Config};
+use zkpoor_api::server::{init_tracing, run_server};
+use zkpoor_api::AppState;
+use zkpoor_core::proof::ProofGenerator;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "8080".to_string())
-        .parse::<u16>()
-        .unwrap_or(8080);
+    let cli = Cli::parse();
+    let config = Config::load(&cli)?;
+    if let Some(Command::Keys { command }) = &cli.command {
+        return run_key_command(&config, command).await;
+    }
+    init_tracing(&config.log_filter);
+    config.log_warnings();
 
-    run_server(port).await
-}
\ No newline at end of file
+    // stwo with the `stwo` feature. The mock verifier of a default build only
+    // serves the mock prover and no published proofs (see `check_verifier`).
+    let verifier = config.verifier.build();
+    config.check_verifier(&verifier)?;
+    let generator = ProofGenerator::new(config.prover.build()?)
+        .with_store(config.store_config().open()?)
+        .with_self_verify(verifier.clone())
+        .with_artifacts(config.artifact_store())
+        .with_queue(config.queue_config())
+        .with_ownership_verifier(config.ownership.verifier())
+        .with_challenges(config.ownership.challenges());
+
+    let published = config.published_proofs(&verifier).await?;
+    let state = AppState::new(generator, verifier)
+        .with_max_proof_bytes(config.limits.max_proof_bytes)
+        .with_cors(config.cors.clone())
+        .with_auth_required(config.auth.required)
+        .with_mock_proofs(config.verifier.allow_mock)
+        .with_network(config.network)
+        .with_published(published);
+    config.bootstrap_admin(&state.keys).await?;
+    run_server(config.bind, state).await
+}
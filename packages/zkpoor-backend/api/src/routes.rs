@@ -1,33 +1,63 @@
 use axum::{
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
-use tower_http::cors::{CorsLayer, Any};
 
-use crate::handlers::{prove_handler, get_proof_handler, verify_proof_handler, verify_hardcoded_proof_handler, health_handler};
+use crate::handlers::{challenge_handler, prove_handler, get_proof_handler, cancel_proof_handler, proof_events_handler, stream_token_handler, get_proof_artifact_handler, verify_proof_handler, list_published_proofs_handler, get_published_proof_handler, create_key_handler, list_keys_handler, revoke_key_handler, health_handler};
+use crate::auth::{require_api_key, require_stream_auth};
+use crate::state::AppState;
 
-pub fn create_routes() -> Router {
-    Router::new()
+pub fn create_routes(state: AppState) -> Router {
+    // Anyone may check a proof
+    let public = Router::new()
         // Health check
         .route("/health", get(health_handler))
         
+        // Verify proof, accepting uploads up to the configured size
+        .route(
+            "/proof-verify",
+            post(verify_proof_handler).layer(DefaultBodyLimit::max(state.max_proof_bytes)),
+        )
+        
+        // Proofs published by the operator, verified at startup
+        .route("/published-proofs", get(list_published_proofs_handler))
+        .route("/published-proofs/:name", get(get_published_proof_handler))
+        .layer(state.cors.public_layer());
+
+    // Proof jobs carry UTXOs and ownership proofs, so they need an API key
+    // and only the manager origins get them. Handlers check the key's roles.
+    let manager = Router::new()
+        // Message for ownership proofs to sign
+        .route("/challenge", post(challenge_handler))
+
         // Proof generation endpoint
         .route("/prove", post(prove_handler))
         
-        // Get proof by ID
-        .route("/proof/:proof_id", get(get_proof_handler))
+        // Get or cancel proof by ID
+        .route("/proof/:proof_id", get(get_proof_handler).delete(cancel_proof_handler))
         
-        // Verify proof
-        .route("/proof-verify", post(verify_proof_handler))
-        
-        // Verify hardcoded proof from JSON file
-        .route("/verify-hardcoded-proof", get(verify_hardcoded_proof_handler))
+        // Token for following live status updates from a browser
+        .route("/proof/:proof_id/events/token", post(stream_token_handler))
+
+        // Download proof artifact
+        .route("/proof/:proof_id/artifact", get(get_proof_artifact_handler))
         
-        // CORS layer to allow frontend to call API
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
+        // API key administration
+        .route("/admin/keys", get(list_keys_handler).post(create_key_handler))
+        .route("/admin/keys/:key_id", delete(revoke_key_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_api_key))
+        // Live status updates (Server-Sent Events), also authenticated by stream token
+        .merge(
+            Router::new()
+                .route("/proof/:proof_id/events", get(proof_events_handler))
+                .route_layer(middleware::from_fn_with_state(state.clone(), require_stream_auth)),
         )
-}
\ No newline at end of file
+        .layer(state.cors.manager_layer());
+
+    public
+        .merge(manager)
+        // Shared generator and verifier
+        .with_state(state)
+}
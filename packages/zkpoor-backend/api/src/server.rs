@@ -1,32 +1,48 @@
-use axum::Router;
+use axum::{extract::Request, http::Uri, Router};
 use std::net::SocketAddr;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::routes::create_routes;
+use crate::state::AppState;
 
-pub fn create_app() -> Router {
-    create_routes()
-        .layer(TraceLayer::new_for_http())
+pub fn create_app(state: AppState) -> Router {
+    create_routes(state)
+        .layer(TraceLayer::new_for_http().make_span_with(|request: &Request| {
+            tracing::debug_span!(
+                "request",
+                method = %request.method(),
+                uri = %loggable_uri(request.uri()),
+                version = ?request.version(),
+            )
+        }))
 }
 
-pub async fn run_server(port: u16) -> anyhow::Result<()> {
-    // Initialize tracing
+/// `uri` with stream token values replaced, so they never reach the logs
+pub fn loggable_uri(uri: &Uri) -> String {
+    let Some(query) = uri.query() else { return uri.to_string() };
+    let query: Vec<&str> = query
+        .split('&')
+        .map(|pair| if pair.starts_with("token=") { "token=[redacted]" } else { pair })
+        .collect();
+    format!("{}?{}", uri.path(), query.join("&"))
+}
+
+/// Install the global `tracing` subscriber with the configured filter
+pub fn init_tracing(filter: &str) {
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "zkpoor_api=debug,tower_http=debug".into()),
-        )
+        .with(tracing_subscriber::EnvFilter::new(filter))
         .with(tracing_subscriber::fmt::layer())
         .init();
+}
 
-    let app = create_app();
+pub async fn run_server(bind: SocketAddr, state: AppState) -> anyhow::Result<()> {
+    let app = create_app(state);
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    tracing::info!("Starting zkpoor API server on {}", addr);
+    tracing::info!("Starting zkpoor API server on {}", bind);
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let listener = tokio::net::TcpListener::bind(bind).await?;
     axum::serve(listener, app).await?;
 
     Ok(())
-}
\ No newline at end of file
+}
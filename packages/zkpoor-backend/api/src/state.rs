@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use zkpoor_core::auth::KeyManager;
+use zkpoor_core::proof::{ProofGenerator, ProofVerifier};
+use zkpoor_core::published::PublishedProofs;
+use zkpoor_core::script::Network;
+
+use crate::config::CorsSettings;
+
+/// Default limit on proofs uploaded to `/proof-verify`; stwo proofs are a few MB
+pub const DEFAULT_MAX_PROOF_BYTES: usize = 32 * 1024 * 1024;
+
+/// Long-lived services shared by all handlers
+#[derive(Clone)]
+pub struct AppState {
+    pub generator: Arc<ProofGenerator>,
+    pub verifier: ProofVerifier,
+    /// Largest request body accepted by `/proof-verify`
+    pub max_proof_bytes: usize,
+    /// Operator proofs served by `/published-proofs`, verified at startup
+    pub published: Arc<PublishedProofs>,
+    /// Origins allowed to call the API from a browser
+    pub cors: CorsSettings,
+    /// API keys, kept in the generator's store
+    pub keys: KeyManager,
+    /// Reject unauthenticated requests to the proof management routes
+    pub auth_required: bool,
+    /// Network the submitted UTXOs' addresses are shown for
+    pub network: Network,
+    /// Answer `/proof-verify` even when `verifier` is the mock
+    pub allow_mock_proofs: bool,
+}
+
+impl AppState {
+    pub fn new(generator: ProofGenerator, verifier: ProofVerifier) -> Self {
+        Self {
+            keys: KeyManager::new(generator.store()),
+            auth_required: true,
+            network: Network::Mainnet,
+            allow_mock_proofs: false,
+            generator: Arc::new(generator),
+            verifier,
+            max_proof_bytes: DEFAULT_MAX_PROOF_BYTES,
+            published: Arc::new(PublishedProofs::default()),
+            cors: CorsSettings::default(),
+        }
+    }
+
+    pub fn with_max_proof_bytes(mut self, max_proof_bytes: usize) -> Self {
+        self.max_proof_bytes = max_proof_bytes;
+        self
+    }
+
+    pub fn with_cors(mut self, cors: CorsSettings) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Let anonymous clients submit and manage proofs, for local development
+    pub fn with_auth_required(mut self, auth_required: bool) -> Self {
+        self.auth_required = auth_required;
+        self
+    }
+
+    /// Let `/proof-verify` answer with the mock verifier, for local development
+    pub fn with_mock_proofs(mut self, allow: bool) -> Self {
+        self.allow_mock_proofs = allow;
+        self
+    }
+
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn with_published(mut self, published: PublishedProofs) -> Self {
+        self.published = Arc::new(published);
+        self
+    }
+
+    /// State backed by the mock prover and verifier, answering `/proof-verify`
+    pub fn mock() -> Self {
+        Self::new(ProofGenerator::mock(), ProofVerifier::mock()).with_mock_proofs(true)
+    }
+}
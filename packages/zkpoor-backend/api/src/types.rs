@@ -4,30 +4,100 @@ use chrono::{DateTime, Utc};
 
 // Re-export types from core
 pub use zkpoor_core::types::{Utxo, OwnershipProof, ProofStatus, ProofData};
+pub use zkpoor_core::output::PublicOutput;
+pub use zkpoor_core::queue::Priority;
+pub use zkpoor_core::auth::{ApiKey, Role};
+pub use zkpoor_core::bitcoin::{Amount, OutPoint};
 
 #[derive(Debug, Deserialize)]
 pub struct ProveRequest {
     pub utxos: Vec<Utxo>,
     pub ownership_proofs: Vec<OwnershipProof>,
+    /// Scheduling priority; jobs of equal priority are proved in arrival order
+    #[serde(default)]
+    pub priority: Priority,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ProveResponse {
     pub proof_id: Uuid,
     pub status: ProofStatus,
+    /// The UTXOs as understood by the server, in request order
+    pub utxos: Vec<UtxoSummary>,
     pub message: String,
 }
 
+/// A submitted UTXO, with its script decoded
+#[derive(Debug, Serialize)]
+pub struct UtxoSummary {
+    pub outpoint: OutPoint,
+    pub amount: Amount,
+    /// Script template: `p2pkh`, `p2wpkh`, `p2tr`, `multisig`, `nonstandard`, ...
+    pub script_type: &'static str,
+    /// Address on the configured network, if the script has one
+    pub address: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct VerifyResponse {
     pub is_valid: bool,
-    pub total_amount: Option<u64>,
+    /// Amount claimed in the submitted public inputs, not attested by the proof
+    pub claimed_total_amount: Option<u64>,
+    /// What the proof actually attests to, decoded from its public output
+    pub public_output: Option<PublicOutput>,
+    pub verified_at: DateTime<Utc>,
+    pub message: String,
+}
+
+/// A published proof and the result of verifying it at startup
+#[derive(Debug, Serialize)]
+pub struct PublishedProofResponse {
+    pub name: String,
+    /// Hex SHA-256 of the verified proof file
+    pub sha256: String,
+    pub size: u64,
+    pub is_valid: bool,
+    /// What the proof attests to; published proofs carry no claimed amount
+    pub public_output: Option<PublicOutput>,
     pub verified_at: DateTime<Utc>,
     pub message: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CreateKeyRequest {
+    pub name: String,
+    #[serde(default = "default_roles")]
+    pub roles: Vec<Role>,
+}
+
+fn default_roles() -> Vec<Role> {
+    vec![Role::Manager]
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateKeyResponse {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    /// Shown once; only its hash is stored
+    pub secret: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ApiError {
+    /// Stable machine-readable code, see `zkpoor_core::Error::code`
+    pub code: String,
     pub error: String,
     pub details: Option<String>,
+    /// Each problem, when a request had several (e.g. `invalid_utxos`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ItemError>,
+}
+
+/// One of several problems with a request
+#[derive(Debug, Serialize)]
+pub struct ItemError {
+    /// Position of the offending item, e.g. in `utxos`
+    pub index: Option<usize>,
+    pub code: String,
+    pub details: String,
 }
\ No newline at end of file
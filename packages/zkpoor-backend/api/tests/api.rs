@@ -0,0 +1,821 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use zkpoor_api::config::{Config, CorsSettings};
+use zkpoor_api::server::loggable_uri;
+use zkpoor_api::{create_app, AppState};
+use zkpoor_core::auth::{hash_secret, ApiKey, Role};
+use zkpoor_core::ownership::{hash160, signed_message_hash, MockOwnershipVerifier};
+use zkpoor_core::proof::{ProofGenerator, ProofVerifier};
+use zkpoor_core::prover::{ExternalProver, MockProver};
+use zkpoor_core::published::PublishedProofs;
+use zkpoor_core::queue::QueueConfig;
+
+/// Bearer token accepted by every app built with `with_test_key`
+const API_KEY: &str = "zkpoor_test_key";
+
+async fn with_test_key(state: AppState) -> Router {
+    let key = ApiKey {
+        key_id: uuid::Uuid::new_v4(),
+        name: "tests".to_string(),
+        roles: vec![Role::Manager],
+        created_at: chrono::Utc::now(),
+        revoked_at: None,
+    };
+    state.generator.store().save_key(&key, &hash_secret(API_KEY)).await.unwrap();
+    create_app(state)
+}
+
+/// Generator proving with a delay and accepting the mock ownership proofs
+fn generator(delay: Duration) -> ProofGenerator {
+    ProofGenerator::new(Arc::new(MockProver::new().with_delay(delay)))
+        .with_ownership_verifier(Arc::new(MockOwnershipVerifier))
+}
+
+async fn app() -> Router {
+    with_test_key(AppState::new(generator(Duration::from_millis(50)), ProofVerifier::mock()).with_mock_proofs(true)).await
+}
+
+async fn app_with_queue(delay: Duration, queue: QueueConfig) -> Router {
+    with_test_key(AppState::new(generator(delay).with_queue(queue), ProofVerifier::mock()).with_mock_proofs(true)).await
+}
+
+/// Send with the test API key unless the request carries its own credentials
+async fn send(app: &Router, mut request: Request<Body>) -> (StatusCode, Value) {
+    request.headers_mut()
+        .entry("authorization")
+        .or_insert(format!("Bearer {}", API_KEY).parse().unwrap());
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    (status, json)
+}
+
+/// Message for the ownership proofs of one `/prove`, requested with `key` or anonymously
+async fn challenge(app: &Router, key: Option<&str>) -> String {
+    let mut request = Request::post("/challenge").body(Body::empty()).unwrap();
+    if let Some(key) = key {
+        request = with_key(request, key);
+    }
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    body["message"].as_str().unwrap().to_string()
+}
+
+fn prove_request_body(challenge: &str) -> Value {
+    json!({
+        "utxos": [{
+            "txid": "621647c91bcf45f46e2ca3925acfb9681c63c1fdae33138d530ada871dbd8814",
+            "vout": 0,
+            "amount": 315906414,
+            "script_pubkey": "76a914fb37342f6275b13936799def06f2eb4c0f20151588ac"
+        }],
+        "ownership_proofs": [{
+            "signature": "mock_signature",
+            "challenge": challenge
+        }]
+    })
+}
+
+fn post_json(uri: &str, body: &Value) -> Request<Body> {
+    Request::post(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// `/prove` of the test UTXO with the test API key
+async fn prove_request(app: &Router) -> Request<Body> {
+    post_json("/prove", &prove_request_body(&challenge(app, Some(API_KEY)).await))
+}
+
+/// `/prove` of the test UTXO, signing a challenge requested with `key`
+async fn prove_request_as(app: &Router, key: &str) -> Request<Body> {
+    with_key(post_json("/prove", &prove_request_body(&challenge(app, Some(key)).await)), key)
+}
+
+fn with_key(mut request: Request<Body>, key: &str) -> Request<Body> {
+    request.headers_mut().insert("authorization", format!("Bearer {}", key).parse().unwrap());
+    request
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::get(uri)
+        .header("authorization", format!("Bearer {}", API_KEY))
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn wait_for(app: &Router, proof_id: &str, done: impl Fn(&Value) -> bool) -> Value {
+    for _ in 0..100 {
+        let (code, proof) = send(app, get(&format!("/proof/{}", proof_id))).await;
+        assert_eq!(code, StatusCode::OK);
+        if done(&proof) {
+            return proof;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("proof {} never reached the expected state", proof_id);
+}
+
+async fn wait_for_status(app: &Router, proof_id: &str, status: &str) -> Value {
+    wait_for(app, proof_id, |proof| proof["status"] == status).await
+}
+
+#[tokio::test]
+async fn test_prove_poll_fetch_round_trip() {
+    let app = app().await;
+
+    let (status, response) = send(&app, prove_request(&app).await).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["status"], "Pending");
+    assert_eq!(response["utxos"][0]["script_type"], "p2pkh");
+    assert_eq!(response["utxos"][0]["address"], "1PuJjnF476W3zXfVYmJfGnouzFDAXakkL4");
+    assert_eq!(response["utxos"][0]["amount"], 315906414);
+    let proof_id = response["proof_id"].as_str().unwrap().to_string();
+
+    // The job is visible immediately, before it completes
+    let (status, _) = send(&app, get(&format!("/proof/{}", proof_id))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let proof = wait_for_status(&app, &proof_id, "Completed").await;
+    assert_eq!(proof["proof_id"], proof_id);
+    assert_eq!(proof["total_amount"], 315906414);
+    assert!(proof["completed_at"].is_string());
+    assert_eq!(proof["progress_percent"], 100);
+    assert_eq!(proof["stages"][0]["stage"], "ValidatingInputs");
+    assert!(proof["stages"].as_array().unwrap().iter().all(|s| s["finished_at"].is_string()));
+
+    let artifact = fetch_artifact(&app, &proof_id).await;
+    assert!(artifact.starts_with("mock_proof_"));
+    assert_eq!(proof["proof_artifact"]["size"], artifact.len());
+}
+
+#[tokio::test]
+async fn test_generated_proof_verifies() {
+    let app = app().await;
+
+    let (_, response) = send(&app, prove_request(&app).await).await;
+    let proof_id = response["proof_id"].as_str().unwrap().to_string();
+    let proof = wait_for_status(&app, &proof_id, "Completed").await;
+    let artifact = fetch_artifact(&app, &proof_id).await;
+
+    let uri = format!(
+        "/proof-verify?proof={}&public_inputs={}",
+        artifact,
+        urlencode(&proof["public_inputs"].to_string())
+    );
+    let (status, verdict) = send(&app, Request::post(uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["is_valid"], true);
+    assert_eq!(verdict["claimed_total_amount"], 315906414);
+}
+
+#[tokio::test]
+async fn test_proof_verify_refuses_mock_verifier_by_default() {
+    let app = create_app(AppState::new(ProofGenerator::mock(), ProofVerifier::mock()));
+
+    let uri = format!("/proof-verify?proof=mock_proof_abc&public_inputs={}", urlencode("{}"));
+    let (status, body) = send(&app, Request::post(uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "backend_unavailable");
+    assert!(body.get("is_valid").is_none());
+}
+
+#[tokio::test]
+async fn test_proof_verify_accepts_json_raw_and_multipart() {
+    let app = app().await;
+
+    let (_, response) = send(&app, prove_request(&app).await).await;
+    let proof_id = response["proof_id"].as_str().unwrap().to_string();
+    let proof = wait_for_status(&app, &proof_id, "Completed").await;
+    let artifact = fetch_artifact(&app, &proof_id).await;
+    let public_inputs = proof["public_inputs"].to_string();
+
+    let body = json!({ "proof": artifact, "public_inputs": proof["public_inputs"] });
+    let (status, verdict) = send(&app, post_json("/proof-verify", &body)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["is_valid"], true);
+    assert_eq!(verdict["claimed_total_amount"], 315906414);
+
+    let request = Request::post(format!("/proof-verify?public_inputs={}", urlencode(&public_inputs)))
+        .header("content-type", "application/octet-stream")
+        .body(Body::from(artifact.clone()))
+        .unwrap();
+    let (status, verdict) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["is_valid"], true);
+    assert_eq!(verdict["claimed_total_amount"], 315906414);
+
+    let boundary = "zkpoor-boundary";
+    let form = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"proof\"; filename=\"proof.json\"\r\n\
+         Content-Type: application/json\r\n\r\n{proof}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"public_inputs\"\r\n\r\n{inputs}\r\n\
+         --{b}--\r\n",
+        b = boundary,
+        proof = artifact,
+        inputs = public_inputs,
+    );
+    let request = Request::post("/proof-verify")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(form))
+        .unwrap();
+    let (status, verdict) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["is_valid"], true);
+    assert_eq!(verdict["claimed_total_amount"], 315906414);
+
+    // Public inputs are optional; without them no amount is claimed
+    let request = Request::post("/proof-verify")
+        .header("content-type", "application/octet-stream")
+        .body(Body::from(artifact))
+        .unwrap();
+    let (status, verdict) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["is_valid"], true);
+    assert!(verdict["claimed_total_amount"].is_null());
+}
+
+#[tokio::test]
+async fn test_proof_verify_enforces_body_limit() {
+    let app = create_app(
+        AppState::new(ProofGenerator::mock(), ProofVerifier::mock()).with_mock_proofs(true).with_max_proof_bytes(1024),
+    );
+
+    let request = Request::post("/proof-verify")
+        .header("content-type", "application/octet-stream")
+        .body(Body::from(vec![b'a'; 2048]))
+        .unwrap();
+    let (status, error) = send(&app, request).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(error["code"], "payload_too_large");
+
+    let body = json!({ "proof": "a".repeat(2048) });
+    let (status, error) = send(&app, post_json("/proof-verify", &body)).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(error["code"], "payload_too_large");
+
+    let request = Request::post("/proof-verify")
+        .header("content-type", "application/octet-stream")
+        .body(Body::from(vec![0xff, 0xfe]))
+        .unwrap();
+    let (status, error) = send(&app, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "proof_malformed");
+}
+
+#[tokio::test]
+async fn test_prove_returns_429_when_queue_full() {
+    let app = app_with_queue(Duration::from_secs(5), QueueConfig { workers: 1, max_depth: 1 }).await;
+
+    // One job may already be running and one waiting; the third cannot be queued
+    let (first, _) = send(&app, prove_request(&app).await).await;
+    assert_eq!(first, StatusCode::OK);
+    send(&app, prove_request(&app).await).await;
+    let (status, error) = send(&app, prove_request(&app).await).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(error["error"], "Proving queue is full");
+    assert_eq!(error["code"], "queue_full");
+}
+
+#[tokio::test]
+async fn test_cancel_proof() {
+    let app = app_with_queue(Duration::from_secs(5), QueueConfig::default()).await;
+
+    let (_, response) = send(&app, prove_request(&app).await).await;
+    let proof_id = response["proof_id"].as_str().unwrap().to_string();
+    let delete = || Request::delete(format!("/proof/{}", proof_id)).body(Body::empty()).unwrap();
+
+    let proof = wait_for(&app, &proof_id, |proof| proof["stage"] == "Executing").await;
+    assert_eq!(proof["status"], "InProgress");
+    assert_eq!(proof["progress_percent"], 15);
+
+    let (status, proof) = send(&app, delete()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(proof["status"], "Cancelled");
+    assert_eq!(proof["stage"], "Executing");
+
+    // Cancelling twice is harmless, and the job stays cancelled
+    let (status, _) = send(&app, delete()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, proof) = send(&app, get(&format!("/proof/{}", proof_id))).await;
+    assert_eq!(proof["status"], "Cancelled");
+
+    let (status, _) = send(&app, Request::delete(format!("/proof/{}", uuid::Uuid::new_v4())).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_cancel_completed_proof_conflicts() {
+    let app = app().await;
+
+    let (_, response) = send(&app, prove_request(&app).await).await;
+    let proof_id = response["proof_id"].as_str().unwrap().to_string();
+    wait_for_status(&app, &proof_id, "Completed").await;
+
+    let (status, error) = send(&app, Request::delete(format!("/proof/{}", proof_id)).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(error["error"], "Proof already finished");
+    assert_eq!(error["code"], "proof_finished");
+}
+
+#[tokio::test]
+async fn test_proof_events_stream_until_completed() {
+    let app = app().await;
+
+    let (_, response) = send(&app, prove_request(&app).await).await;
+    let proof_id = response["proof_id"].as_str().unwrap().to_string();
+
+    let response = app.clone().oneshot(get(&format!("/proof/{}/events", proof_id))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    // The stream closes by itself once the job is finished
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let events: Vec<Value> = String::from_utf8(body.to_vec())
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect();
+    assert!(events.len() >= 2);
+    assert!(events.iter().all(|event| event["proof_id"] == proof_id));
+    assert_eq!(events.last().unwrap()["status"], "Completed");
+    let statuses: Vec<_> = events.iter().map(|event| event["status"].as_str().unwrap()).collect();
+    assert!(statuses.contains(&"InProgress"));
+
+    let (status, _) = send(&app, get(&format!("/proof/{}/events", uuid::Uuid::new_v4()))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_proof_events_accept_stream_token() {
+    let state = AppState::new(generator(Duration::from_millis(50)), ProofVerifier::mock());
+    let manager = state.keys.create("treasury", &[Role::Manager]).await.unwrap().secret;
+    let other_manager = state.keys.create("subsidiary", &[Role::Manager]).await.unwrap().secret;
+    let app = create_app(state);
+
+    let (_, response) = send(&app, prove_request_as(&app, &manager).await).await;
+    let proof_id = response["proof_id"].as_str().unwrap().to_string();
+    let token_request = |key: &str| with_key(Request::post(format!("/proof/{}/events/token", proof_id)).body(Body::empty()).unwrap(), key);
+    let (status, _) = send(&app, token_request(&other_manager)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, token) = send(&app, token_request(&manager)).await;
+    assert_eq!(status, StatusCode::OK);
+    let token = token["token"].as_str().unwrap().to_string();
+
+    // A browser EventSource sends no headers, only the URL
+    let events = |proof_id: &str, token: &str| {
+        Request::get(format!("/proof/{}/events?token={}", proof_id, token)).body(Body::empty()).unwrap()
+    };
+    let response = app.clone().oneshot(events(&proof_id, &token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let body = String::from_utf8(to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap();
+    assert!(body.contains("\"status\":\"Completed\""));
+
+    let other_proof = uuid::Uuid::new_v4().to_string();
+    let response = app.clone().oneshot(events(&other_proof, &token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(events(&proof_id, &manager)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let request = Request::get(format!("/proof/{}/events", proof_id)).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // The token never reaches the request logs
+    let uri = format!("/proof/{}/events?token={}", proof_id, token).parse().unwrap();
+    assert_eq!(loggable_uri(&uri), format!("/proof/{}/events?token=[redacted]", proof_id));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_failed_proof_reports_failure() {
+    let work_dir = std::env::temp_dir().join(format!("zkpoor-api-failing-{}", uuid::Uuid::new_v4()));
+    let prover = ExternalProver::new("sh", &work_dir).with_args(["-c", "echo 'out of memory' >&2; exit 137"]);
+    let generator = ProofGenerator::new(Arc::new(prover)).with_ownership_verifier(Arc::new(MockOwnershipVerifier));
+    let app = with_test_key(AppState::new(generator, ProofVerifier::mock())).await;
+
+    let (status, response) = send(&app, prove_request(&app).await).await;
+    assert_eq!(status, StatusCode::OK);
+    let proof_id = response["proof_id"].as_str().unwrap().to_string();
+
+    let proof = wait_for_status(&app, &proof_id, "Failed").await;
+    assert_eq!(proof["failure"]["category"], "ProverError");
+    assert_eq!(proof["failure"]["stage"], "Proving");
+    assert_eq!(proof["failure"]["stderr_tail"], "out of memory");
+
+    std::fs::remove_dir_all(&work_dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_prove_returns_503_when_prover_missing() {
+    let prover = ExternalProver::new("/nonexistent/prover", std::env::temp_dir());
+    let generator = ProofGenerator::new(Arc::new(prover)).with_ownership_verifier(Arc::new(MockOwnershipVerifier));
+    let app = with_test_key(AppState::new(generator, ProofVerifier::mock())).await;
+
+    let (status, error) = send(&app, prove_request(&app).await).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(error["code"], "backend_unavailable");
+    assert!(error["details"].as_str().unwrap().contains("/nonexistent/prover"));
+}
+
+#[tokio::test]
+async fn test_invalid_requests_have_stable_codes() {
+    let app = app().await;
+
+    let mut request = prove_request_body(&challenge(&app, Some(API_KEY)).await);
+    request["utxos"] = json!([]);
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_request");
+
+    let mut request = prove_request_body(&challenge(&app, Some(API_KEY)).await);
+    request["ownership_proofs"] = json!([]);
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_request");
+    assert!(error["details"].as_str().unwrap().contains("ownership proof"));
+
+    // Malformed Bitcoin values are rejected before a job is created
+    let mut request = prove_request_body(&challenge(&app, Some(API_KEY)).await);
+    request["utxos"][0]["txid"] = json!("mock_txid_1");
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_request");
+    assert!(error["details"].as_str().unwrap().contains("utxos[0].txid"));
+
+    let mut request = prove_request_body(&challenge(&app, Some(API_KEY)).await);
+    request["utxos"][0]["amount"] = json!(2_100_000_000_000_001u64);
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["details"].as_str().unwrap().contains("21,000,000 BTC"));
+
+    let mut request = prove_request_body(&challenge(&app, Some(API_KEY)).await);
+    let utxo = |vout: u32| json!({"txid": request["utxos"][0]["txid"], "vout": vout, "amount": 2_000_000_000_000_000u64, "script_pubkey": request["utxos"][0]["script_pubkey"]});
+    request["utxos"] = json!([utxo(1), utxo(2)]);
+    request["ownership_proofs"] = json!([request["ownership_proofs"][0], request["ownership_proofs"][0]]);
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["details"].as_str().unwrap().contains("Total UTXO amount"));
+
+    let mut request = prove_request_body(&challenge(&app, Some(API_KEY)).await);
+    request["utxos"][0]["script_pubkey"] = json!("6a0b68656c6c6f20776f726c64");
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_utxos");
+    assert_eq!(error["errors"][0]["index"], 0);
+    assert_eq!(error["errors"][0]["code"], "invalid_utxo");
+
+    // Zero amounts are reported with the other per-UTXO problems, before a job exists
+    let mut request = prove_request_body(&challenge(&app, Some(API_KEY)).await);
+    request["utxos"][0]["amount"] = json!(0);
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_utxos");
+    assert_eq!(error["errors"], json!([
+        {"index": 0, "code": "invalid_utxo", "details": "Invalid UTXO at index 0: amount cannot be zero"}
+    ]));
+
+    // Listing an outpoint twice cannot inflate the total
+    let mut request = prove_request_body(&challenge(&app, Some(API_KEY)).await);
+    let mut conflicting = request["utxos"][0].clone();
+    conflicting["amount"] = json!(1);
+    request["utxos"] = json!([request["utxos"][0], request["utxos"][0], conflicting]);
+    request["ownership_proofs"] = json!([request["ownership_proofs"][0], request["ownership_proofs"][0], request["ownership_proofs"][0]]);
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_utxos");
+    assert_eq!(error["errors"], json!([
+        {
+            "index": 1,
+            "code": "duplicate_outpoint",
+            "details": "Duplicate outpoint 621647c91bcf45f46e2ca3925acfb9681c63c1fdae33138d530ada871dbd8814:0 at index 1, already listed at index 0"
+        },
+        {
+            "index": 2,
+            "code": "conflicting_outpoint",
+            "details": "Outpoint 621647c91bcf45f46e2ca3925acfb9681c63c1fdae33138d530ada871dbd8814:0 at index 2 conflicts with index 0: amount 1 sat differs from 315906414 sat"
+        }
+    ]));
+
+    let uri = "/proof-verify?proof=mock_proof_00&public_inputs=not-json";
+    let (status, error) = send(&app, Request::post(uri).body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_request");
+}
+
+#[tokio::test]
+async fn test_ownership_signatures_are_verified() {
+    let prover = MockProver::new().with_delay(Duration::from_millis(50));
+    let app = with_test_key(AppState::new(ProofGenerator::new(Arc::new(prover)), ProofVerifier::mock())).await;
+
+    let (status, error) = send(&app, prove_request(&app).await).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["code"], "invalid_utxos");
+    assert_eq!(error["errors"][0]["index"], 0);
+    assert_eq!(error["errors"][0]["code"], "bad_signature");
+
+    // signmessage signature by the key of 1HZwkjkeaoZfTSaJxDw6aKkxp45agDiEzN, published with its
+    // message: genuine, but it proves nothing about this job and cannot be replayed
+    let mut request = prove_request_body("This is an example of a signed message.");
+    request["utxos"][0]["script_pubkey"] = json!("76a914b5bd079c4d57cc7fc28ecf8213a6b791625b818388ac");
+    request["ownership_proofs"][0]["signature"] = json!("G9L5yLFjti0QTHhPyFrZCT1V/MMnBtXKmoiKDZ78NDBjERki6ZTQZdSMCtkgoNmp17By9ItJr8o7ChX0XxY91nk=");
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["errors"][0]["code"], "bad_challenge");
+
+    // The same kind of signature over a challenge issued by the server is accepted, once
+    let secp = Secp256k1::new();
+    let secret = SecretKey::from_slice(&[7; 32]).unwrap();
+    let pubkey_hash = hash160(&PublicKey::from_secret_key(&secp, &secret).serialize());
+    let issued = challenge(&app, Some(API_KEY)).await;
+    let digest = Message::from_digest(signed_message_hash(&issued));
+    let (recovery_id, compact) = secp.sign_ecdsa_recoverable(&digest, &secret).serialize_compact();
+    let signature = [&[31 + recovery_id.to_i32() as u8][..], &compact].concat();
+    let mut request = prove_request_body(&issued);
+    request["utxos"][0]["script_pubkey"] = json!(format!("76a914{}88ac", hex::encode(pubkey_hash)));
+    request["ownership_proofs"][0]["signature"] = json!(BASE64.encode(signature));
+    let (status, response) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(response["utxos"][0]["script_type"], "p2pkh");
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["errors"][0]["code"], "bad_challenge");
+    assert!(error["errors"][0]["details"].as_str().unwrap().contains("already been used"));
+
+    // Each challenge is signed by its own key
+    request["ownership_proofs"][0]["challenge"] = json!(challenge(&app, Some(API_KEY)).await);
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["errors"][0]["details"], "Invalid ownership proof at index 0: signature is not by the key of this output");
+
+    // BIP-322 simple signature of "Hello World", taproot key path: replayed too
+    request["utxos"][0]["script_pubkey"] = json!("51200b34f2cc6f60d54e3fdc2d1dd053fcc393bd2db9acc8de4a7c3cc28a83d4d8e9");
+    request["ownership_proofs"][0] = json!({
+        "signature": "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==",
+        "challenge": "Hello World",
+        "format": "bip322_simple"
+    });
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["errors"][0]["code"], "bad_challenge");
+
+    request["ownership_proofs"][0]["challenge"] = json!(challenge(&app, Some(API_KEY)).await);
+    request["ownership_proofs"][0]["format"] = json!("legacy");
+    let (status, error) = send(&app, post_json("/prove", &request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["errors"][0]["details"].as_str().unwrap().contains("use a BIP-322 signature"));
+}
+
+#[tokio::test]
+async fn test_published_proofs_are_verified() {
+    let dir = std::env::temp_dir().join(format!("zkpoor-published-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("reserves.json"), "mock_proof_reserves").unwrap();
+    std::fs::write(dir.join("forged.json"), "not_a_proof").unwrap();
+
+    let verifier = ProofVerifier::mock();
+    let published = PublishedProofs::load(&dir, &verifier).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let app = create_app(AppState::new(ProofGenerator::mock(), verifier).with_published(published));
+
+    let (status, list) = send(&app, get("/published-proofs")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list.as_array().unwrap().len(), 2);
+
+    let (status, proof) = send(&app, get("/published-proofs/reserves")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(proof["is_valid"], true);
+    // Nothing unproven is served next to a valid verdict
+    assert!(proof.get("claimed_total_amount").is_none());
+    assert!(proof.get("total_amount").is_none());
+
+    let (status, proof) = send(&app, get("/published-proofs/forged")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(proof["is_valid"], false);
+    assert!(proof["message"].as_str().unwrap().contains("not a mock proof"));
+
+    let (status, error) = send(&app, get("/published-proofs/missing")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["code"], "published_proof_not_found");
+}
+
+#[tokio::test]
+async fn test_cors_policy_per_route() {
+    let cors = CorsSettings {
+        manager_origins: vec!["https://manager.example".to_string()],
+        ..CorsSettings::default()
+    };
+    let app = create_app(AppState::mock().with_cors(cors));
+
+    async fn allowed_origin(app: &Router, uri: &str, origin: &str) -> Option<String> {
+        let request = Request::options(uri)
+            .header("origin", origin)
+            .header("access-control-request-method", "POST")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        response.headers()
+            .get("access-control-allow-origin")
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+
+    assert_eq!(allowed_origin(&app, "/proof-verify", "https://anyone.example").await.as_deref(), Some("*"));
+    assert_eq!(allowed_origin(&app, "/prove", "https://anyone.example").await, None);
+    assert_eq!(
+        allowed_origin(&app, "/prove", "https://manager.example").await.as_deref(),
+        Some("https://manager.example")
+    );
+    let uri = format!("/proof/{}", uuid::Uuid::new_v4());
+    assert_eq!(allowed_origin(&app, &uri, "https://anyone.example").await, None);
+}
+
+#[tokio::test]
+async fn test_manager_routes_require_api_key() {
+    let state = AppState::new(generator(Duration::from_millis(50)), ProofVerifier::mock()).with_mock_proofs(true);
+    let issued = state.keys.create("treasury", &[Role::Manager]).await.unwrap();
+    let app = create_app(state.clone());
+
+    let unsigned = || post_json("/prove", &prove_request_body("mock_challenge"));
+    let response = app.clone().oneshot(unsigned()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+    let (status, error) = send(&app, with_key(unsigned(), "zkpoor_wrong")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(error["code"], "unauthorized");
+
+    // Jobs are attributed to the key that submitted them
+    let (status, response) = send(&app, prove_request_as(&app, &issued.secret).await).await;
+    assert_eq!(status, StatusCode::OK);
+    let proof_id = response["proof_id"].as_str().unwrap();
+    let (status, proof) = send(&app, with_key(get(&format!("/proof/{}", proof_id)), &issued.secret)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(proof["created_by"], issued.key.key_id.to_string());
+
+    state.keys.revoke(issued.key.key_id).await.unwrap();
+    let (status, _) = send(&app, with_key(unsigned(), &issued.secret)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Verification stays anonymous
+    let request = Request::post("/proof-verify?proof=mock_proof_00").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_roles_are_enforced_per_handler() {
+    let state = AppState::new(generator(Duration::from_millis(50)), ProofVerifier::mock()).with_mock_proofs(true);
+    let manager = state.keys.create("treasury", &[Role::Manager]).await.unwrap().secret;
+    let other_manager = state.keys.create("subsidiary", &[Role::Manager]).await.unwrap().secret;
+    let auditor = state.keys.create("auditor", &[Role::Auditor]).await.unwrap().secret;
+    let admin = state.keys.create("ops", &[Role::Admin]).await.unwrap().secret;
+    let app = create_app(state);
+
+    // Only managers request challenges and submit proofs
+    let (status, error) = send(&app, with_key(Request::post("/challenge").body(Body::empty()).unwrap(), &auditor)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(error["code"], "forbidden");
+    let (status, error) = send(&app, with_key(prove_request_as(&app, &manager).await, &auditor)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(error["code"], "forbidden");
+    let body = prove_request_body(&challenge(&app, Some(&manager)).await);
+    let (status, response) = send(&app, with_key(post_json("/prove", &body), &manager)).await;
+    assert_eq!(status, StatusCode::OK);
+    let proof_id = response["proof_id"].as_str().unwrap().to_string();
+    let uri = format!("/proof/{}", proof_id);
+
+    // Other managers cannot tell the job exists, nor cancel it
+    let (status, _) = send(&app, with_key(get(&uri), &other_manager)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, with_key(Request::delete(&uri).body(Body::empty()).unwrap(), &other_manager)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, with_key(Request::delete(&uri).body(Body::empty()).unwrap(), &auditor)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Private inputs are only shown to auditors, once the job has completed
+    let mut proof = Value::Null;
+    for _ in 0..100 {
+        (_, proof) = send(&app, with_key(get(&uri), &auditor)).await;
+        if proof["status"] == "Completed" {
+            break;
+        }
+        assert!(proof.get("inputs").is_none());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(proof["status"], "Completed");
+    assert_eq!(proof["inputs"]["utxos"], body["utxos"]);
+    let mut ownership_proofs = body["ownership_proofs"].clone();
+    ownership_proofs[0]["format"] = json!("legacy");
+    assert_eq!(proof["inputs"]["ownership_proofs"], ownership_proofs);
+    for key in [&manager, &admin] {
+        let (status, proof) = send(&app, with_key(get(&uri), key)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(proof.get("inputs").is_none());
+    }
+
+    // Key administration is for admins
+    let (status, _) = send(&app, with_key(get("/admin/keys"), &manager)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, created) = send(&app, with_key(post_json("/admin/keys", &json!({"name": "portal"})), &admin)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(created["roles"], json!(["manager"]));
+    let (status, keys) = send(&app, with_key(get("/admin/keys"), &admin)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(keys.as_array().unwrap().len(), 5);
+    assert!(keys[4].get("secret").is_none());
+
+    let secret = created["secret"].as_str().unwrap();
+    let (status, _) = send(&app, prove_request_as(&app, secret).await).await;
+    assert_eq!(status, StatusCode::OK);
+    let revoke = Request::delete(format!("/admin/keys/{}", created["key_id"].as_str().unwrap())).body(Body::empty()).unwrap();
+    let (status, revoked) = send(&app, with_key(revoke, &admin)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(revoked["revoked_at"].is_string());
+    let (status, _) = send(&app, with_key(prove_request_as(&app, &manager).await, secret)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Everyone can verify
+    let request = Request::post("/proof-verify?proof=mock_proof_00").body(Body::empty()).unwrap();
+    let (status, _) = send(&app, with_key(request, &auditor)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_auth_can_be_disabled() {
+    let app = create_app(AppState::mock().with_auth_required(false));
+    let request = post_json("/prove", &prove_request_body(&challenge(&app, None).await));
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A key that is given must still be valid
+    let mut request = post_json("/prove", &prove_request_body(&challenge(&app, None).await));
+    request.headers_mut().insert("authorization", "Bearer zkpoor_wrong".parse().unwrap());
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_bootstrap_admin_key_manages_memory_store() {
+    let mut config = Config::default();
+    config.auth.admin_key_hash = Some(hash_secret("zkpoor_bootstrap").to_uppercase());
+    let state = AppState::mock();
+    config.bootstrap_admin(&state.keys).await.unwrap();
+    let app = create_app(state);
+
+    let (status, created) = send(&app, with_key(post_json("/admin/keys", &json!({"name": "treasury"})), "zkpoor_bootstrap")).await;
+    assert_eq!(status, StatusCode::OK);
+    let manager = created["secret"].as_str().unwrap();
+    let response = app.clone().oneshot(prove_request_as(&app, manager).await).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+async fn fetch_artifact(app: &Router, proof_id: &str) -> String {
+    let response = app.clone().oneshot(get(&format!("/proof/{}/artifact", proof_id))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_health_reports_verifier() {
+    let app = app().await;
+    let (status, health) = send(&app, Request::get("/health").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(health["status"], "healthy");
+    assert_eq!(health["verifier"], "mock");
+}
+
+#[tokio::test]
+async fn test_unknown_proof_is_not_found() {
+    let app = app().await;
+    let (status, error) = send(&app, get(&format!("/proof/{}", uuid::Uuid::new_v4()))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["error"], "Proof not found");
+    assert_eq!(error["code"], "proof_not_found");
+
+    let (status, _) = send(&app, get(&format!("/proof/{}/artifact", uuid::Uuid::new_v4()))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+fn urlencode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,120 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+/// Reference to a stored proof artifact
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArtifactRef {
+    /// Hex SHA-256 of the artifact bytes, also its file name
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Content-addressed directory of proof artifacts.
+///
+/// Files are named by the SHA-256 of their contents and never modified, so the
+/// directory can be rsynced or backed up independently of the proof store.
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    root: PathBuf,
+}
+
+impl ArtifactStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Store bytes, returning their reference. Storing the same bytes twice is a no-op.
+    pub async fn put(&self, bytes: &[u8]) -> Result<ArtifactRef> {
+        let artifact = ArtifactRef {
+            sha256: hex::encode(Sha256::digest(bytes)),
+            size: bytes.len() as u64,
+        };
+
+        let path = self.path(&artifact.sha256)?;
+        if tokio::fs::try_exists(&path).await? {
+            return Ok(artifact);
+        }
+
+        // Write to a temporary name first so readers never see a partial file
+        tokio::fs::create_dir_all(&self.root).await?;
+        let tmp = self.root.join(format!(".{}.tmp", Uuid::new_v4()));
+        tokio::fs::write(&tmp, bytes).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+
+        Ok(artifact)
+    }
+
+    /// Location of an artifact; rejects anything that is not a SHA-256 hex digest
+    pub fn path(&self, sha256: &str) -> Result<PathBuf> {
+        if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+            return Err(anyhow!("Invalid artifact digest: {}", sha256));
+        }
+        Ok(self.root.join(sha256))
+    }
+
+    /// Open an artifact for streaming, or `None` if it is missing
+    pub async fn open(&self, artifact: &ArtifactRef) -> Result<Option<tokio::fs::File>> {
+        match tokio::fs::File::open(self.path(&artifact.sha256)?).await {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Read a whole artifact, checking it still matches its digest
+    pub async fn read(&self, artifact: &ArtifactRef) -> Result<Vec<u8>> {
+        let bytes = tokio::fs::read(self.path(&artifact.sha256)?).await?;
+        if hex::encode(Sha256::digest(&bytes)) != artifact.sha256 {
+            return Err(anyhow!("Artifact {} is corrupted", artifact.sha256));
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> ArtifactStore {
+        ArtifactStore::new(std::env::temp_dir().join(format!("zkpoor-artifacts-{}", Uuid::new_v4())))
+    }
+
+    #[tokio::test]
+    async fn test_put_and_read() {
+        let store = store();
+        let artifact = store.put(b"proof bytes").await.unwrap();
+        assert_eq!(artifact.size, 11);
+        assert_eq!(artifact.sha256, hex::encode(Sha256::digest(b"proof bytes")));
+        assert_eq!(store.read(&artifact).await.unwrap(), b"proof bytes");
+
+        // Same content, same artifact
+        assert_eq!(store.put(b"proof bytes").await.unwrap(), artifact);
+
+        std::fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_detects_corruption() {
+        let store = store();
+        let artifact = store.put(b"proof bytes").await.unwrap();
+        std::fs::write(store.path(&artifact.sha256).unwrap(), b"tampered").unwrap();
+        assert!(store.read(&artifact).await.is_err());
+
+        std::fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_digest() {
+        let store = store();
+        assert!(store.path("../../etc/passwd").is_err());
+        let missing = ArtifactRef { sha256: "0".repeat(64), size: 0 };
+        assert!(store.open(&missing).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::Error;
+use crate::store::ProofStore;
+
+/// Prefix of every API key secret, so leaked keys are easy to spot
+pub const KEY_PREFIX: &str = "zkpoor_";
+
+/// Name of the admin key created from a configured secret hash
+pub const BOOTSTRAP_ADMIN: &str = "bootstrap admin";
+
+/// Prefix of stream tokens, see `KeyManager::issue_stream_token`
+pub const STREAM_TOKEN_PREFIX: &str = "zkpoor_stream_";
+
+/// How long a stream token can be used to open or reopen an event stream
+pub const STREAM_TOKEN_TTL: Duration = Duration::minutes(1);
+
+/// What an API key may do. Roles do not imply one another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Treasury manager: submits UTXOs and follows their own jobs
+    Manager,
+    /// External auditor: reads every job, including the private inputs of completed ones
+    Auditor,
+    /// Manages API keys and can cancel any job
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Manager => "manager",
+            Role::Auditor => "auditor",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manager" => Ok(Role::Manager),
+            "auditor" => Ok(Role::Auditor),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("unknown role '{}', expected manager, auditor or admin", other)),
+        }
+    }
+}
+
+/// An API key as stored; the secret itself is never kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub key_id: Uuid,
+    /// Who or what the key was issued to
+    pub name: String,
+    pub roles: Vec<Role>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.contains(&role)
+    }
+}
+
+/// A freshly created key. `secret` is only available here.
+#[derive(Debug, Clone)]
+pub struct IssuedKey {
+    pub key: ApiKey,
+    pub secret: String,
+}
+
+/// Stands in for an API key on one proof's event stream, for clients that
+/// cannot send an `Authorization` header, such as a browser `EventSource`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// What a stream token was issued for
+struct StreamGrant {
+    key_id: Option<Uuid>,
+    proof_id: Uuid,
+    expires_at: DateTime<Utc>,
+}
+
+/// Lookup hash of a secret. Secrets are 256 random bits, so a plain digest
+/// is enough; there is nothing for a password hash's work factor to protect.
+pub fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Issues, checks and revokes the API keys held in a `ProofStore`, and the
+/// stream tokens derived from them, which are kept in memory only
+#[derive(Clone)]
+pub struct KeyManager {
+    store: Arc<dyn ProofStore>,
+    /// Outstanding stream tokens by secret hash
+    streams: Arc<Mutex<HashMap<String, StreamGrant>>>,
+}
+
+impl KeyManager {
+    pub fn new(store: Arc<dyn ProofStore>) -> Self {
+        Self { store, streams: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    pub async fn create(&self, name: &str, roles: &[Role]) -> Result<IssuedKey, Error> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::InvalidRequest("API key name cannot be empty".to_string()));
+        }
+        if roles.is_empty() {
+            return Err(Error::InvalidRequest("API key needs at least one role".to_string()));
+        }
+        let mut roles = roles.to_vec();
+        roles.sort();
+        roles.dedup();
+
+        let mut bytes = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        let secret = format!("{}{}", KEY_PREFIX, hex::encode(bytes));
+        let key = ApiKey {
+            key_id: Uuid::new_v4(),
+            name: name.to_string(),
+            roles,
+            created_at: Utc::now(),
+            revoked_at: None,
+        };
+
+        self.store.save_key(&key, &hash_secret(&secret)).await?;
+        tracing::info!("Created API key {} ({}) with roles {:?}", key.key_id, key.name, key.roles);
+        Ok(IssuedKey { key, secret })
+    }
+
+    /// Make sure an admin key with the given secret hash exists, so a server
+    /// can be administered without the `keys` command, e.g. on the memory
+    /// store. An existing key is returned as is, including a revoked one.
+    pub async fn bootstrap_admin(&self, secret_hash: &str) -> Result<ApiKey, Error> {
+        if let Some(key) = self.store.find_key(secret_hash).await? {
+            return Ok(key);
+        }
+        let key = ApiKey {
+            key_id: Uuid::new_v4(),
+            name: BOOTSTRAP_ADMIN.to_string(),
+            roles: vec![Role::Admin],
+            created_at: Utc::now(),
+            revoked_at: None,
+        };
+        self.store.save_key(&key, secret_hash).await?;
+        tracing::info!("Created bootstrap admin key {}", key.key_id);
+        Ok(key)
+    }
+
+    /// The live key matching `secret`
+    pub async fn authenticate(&self, secret: &str) -> Result<ApiKey, Error> {
+        match self.store.find_key(&hash_secret(secret)).await? {
+            Some(key) if key.is_revoked() => Err(Error::Unauthorized(format!("API key {} has been revoked", key.key_id))),
+            Some(key) => Ok(key),
+            None => Err(Error::Unauthorized("Unknown API key".to_string())),
+        }
+    }
+
+    /// A token letting the holder of `key` (`None` when authentication is off)
+    /// follow the events of `proof_id` until it expires. It may be used more
+    /// than once, so clients can reconnect.
+    pub fn issue_stream_token(&self, key: Option<&ApiKey>, proof_id: Uuid) -> StreamToken {
+        let mut bytes = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        let token = format!("{}{}", STREAM_TOKEN_PREFIX, hex::encode(bytes));
+        let now = Utc::now();
+        let expires_at = now + STREAM_TOKEN_TTL;
+
+        let mut streams = self.streams();
+        streams.retain(|_, grant| grant.expires_at > now);
+        streams.insert(hash_secret(&token), StreamGrant { key_id: key.map(|key| key.key_id), proof_id, expires_at });
+        StreamToken { token, expires_at }
+    }
+
+    /// The key a stream token for `proof_id` was issued to, if the token is
+    /// live and the key has not been revoked since
+    pub async fn authenticate_stream_token(&self, token: &str, proof_id: Uuid) -> Result<Option<ApiKey>, Error> {
+        let key_id = match self.streams().get(&hash_secret(token)) {
+            Some(grant) if grant.expires_at <= Utc::now() => {
+                return Err(Error::Unauthorized("Stream token has expired".to_string()));
+            }
+            Some(grant) if grant.proof_id != proof_id => {
+                return Err(Error::Unauthorized(format!("Stream token is not valid for proof {}", proof_id)));
+            }
+            Some(grant) => grant.key_id,
+            None => return Err(Error::Unauthorized("Unknown stream token".to_string())),
+        };
+        let Some(key_id) = key_id else { return Ok(None) };
+
+        match self.store.get_key(key_id).await? {
+            Some(key) if !key.is_revoked() => Ok(Some(key)),
+            _ => Err(Error::Unauthorized(format!("API key {} has been revoked", key_id))),
+        }
+    }
+
+    pub async fn list(&self) -> Result<Vec<ApiKey>, Error> {
+        Ok(self.store.list_keys().await?)
+    }
+
+    /// Revoke a key; revoking it again keeps the original revocation time
+    pub async fn revoke(&self, key_id: Uuid) -> Result<ApiKey, Error> {
+        let key = self.store.revoke_key(key_id, Utc::now()).await?
+            .ok_or(Error::KeyNotFound(key_id))?;
+        tracing::info!("Revoked API key {} ({})", key.key_id, key.name);
+        Ok(key)
+    }
+
+    fn streams(&self) -> MutexGuard<'_, HashMap<String, StreamGrant>> {
+        self.streams.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{MemoryProofStore, SqliteProofStore};
+
+    async fn lifecycle(store: Arc<dyn ProofStore>) {
+        let keys = KeyManager::new(store);
+        let issued = keys.create("treasury", &[Role::Manager, Role::Auditor, Role::Manager]).await.unwrap();
+        assert!(issued.secret.starts_with(KEY_PREFIX));
+        assert_eq!(issued.key.roles, [Role::Manager, Role::Auditor]);
+        assert_eq!(keys.authenticate(&issued.secret).await.unwrap(), issued.key);
+        assert!(matches!(keys.authenticate("zkpoor_nope").await, Err(Error::Unauthorized(_))));
+
+        let other = keys.create("auditor", &[Role::Auditor]).await.unwrap();
+        let listed: Vec<_> = keys.list().await.unwrap().into_iter().map(|key| key.name).collect();
+        assert_eq!(listed, ["treasury", "auditor"]);
+
+        let revoked = keys.revoke(issued.key.key_id).await.unwrap();
+        assert!(revoked.is_revoked());
+        assert_eq!(keys.revoke(issued.key.key_id).await.unwrap().revoked_at, revoked.revoked_at);
+        assert!(matches!(keys.authenticate(&issued.secret).await, Err(Error::Unauthorized(_))));
+        assert!(keys.authenticate(&other.secret).await.is_ok());
+
+        // Stream tokens look their key up again, so revocation applies to them too
+        let proof_id = Uuid::new_v4();
+        let revoked_stream = keys.issue_stream_token(Some(&issued.key), proof_id);
+        let live_stream = keys.issue_stream_token(Some(&other.key), proof_id);
+        assert!(matches!(keys.authenticate_stream_token(&revoked_stream.token, proof_id).await, Err(Error::Unauthorized(_))));
+        assert_eq!(keys.authenticate_stream_token(&live_stream.token, proof_id).await.unwrap(), Some(other.key.clone()));
+
+        assert!(matches!(keys.revoke(Uuid::new_v4()).await, Err(Error::KeyNotFound(_))));
+        assert!(matches!(keys.create("  ", &[Role::Admin]).await, Err(Error::InvalidRequest(_))));
+        assert!(matches!(keys.create("nobody", &[]).await, Err(Error::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_admin_is_created_once() {
+        let keys = KeyManager::new(Arc::new(SqliteProofStore::open_in_memory().unwrap()));
+        let secret = "zkpoor_bootstrap";
+        let key = keys.bootstrap_admin(&hash_secret(secret)).await.unwrap();
+        assert_eq!(key.name, BOOTSTRAP_ADMIN);
+        assert_eq!(key.roles, [Role::Admin]);
+        assert_eq!(keys.authenticate(secret).await.unwrap(), key);
+        assert_eq!(keys.bootstrap_admin(&hash_secret(secret)).await.unwrap(), key);
+
+        // A revoked bootstrap key stays revoked across restarts
+        keys.revoke(key.key_id).await.unwrap();
+        assert!(keys.bootstrap_admin(&hash_secret(secret)).await.unwrap().is_revoked());
+        assert_eq!(keys.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_tokens_are_scoped_to_one_proof() {
+        let keys = KeyManager::new(Arc::new(MemoryProofStore::new()));
+        let issued = keys.create("treasury", &[Role::Manager]).await.unwrap();
+        let proof_id = Uuid::new_v4();
+        let stream = keys.issue_stream_token(Some(&issued.key), proof_id);
+        assert!(stream.token.starts_with(STREAM_TOKEN_PREFIX));
+
+        // Reusable until it expires, so clients can reconnect
+        for _ in 0..2 {
+            assert_eq!(keys.authenticate_stream_token(&stream.token, proof_id).await.unwrap(), Some(issued.key.clone()));
+        }
+        assert!(matches!(keys.authenticate_stream_token(&stream.token, Uuid::new_v4()).await, Err(Error::Unauthorized(_))));
+        assert!(matches!(keys.authenticate_stream_token(&issued.secret, proof_id).await, Err(Error::Unauthorized(_))));
+
+        // Anonymous when authentication is off
+        let anonymous = keys.issue_stream_token(None, proof_id);
+        assert_eq!(keys.authenticate_stream_token(&anonymous.token, proof_id).await.unwrap(), None);
+
+        keys.revoke(issued.key.key_id).await.unwrap();
+        assert!(matches!(keys.authenticate_stream_token(&stream.token, proof_id).await, Err(Error::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_memory_key_lifecycle() {
+        lifecycle(Arc::new(MemoryProofStore::new())).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_key_lifecycle() {
+        lifecycle(Arc::new(SqliteProofStore::open_in_memory().unwrap())).await;
+    }
+}
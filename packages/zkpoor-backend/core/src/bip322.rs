@@ -0,0 +1,764 @@
+use anyhow::{Result, anyhow};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use secp256k1::{ecdsa, schnorr, Message, PublicKey, Secp256k1, Verification, XOnlyPublicKey};
+use sha2::{Digest, Sha256};
+
+use crate::bitcoin::ScriptPubKey;
+use crate::ownership::{hash160, write_compact_size};
+use crate::script::ScriptTemplate;
+
+/// Taproot only: sign everything, without a trailing hash type byte
+const SIGHASH_DEFAULT: u8 = 0x00;
+const SIGHASH_ALL: u8 = 0x01;
+
+/// First byte of a taproot annex, an optional last witness item
+const ANNEX_TAG: u8 = 0x50;
+
+const OP_RETURN: u8 = 0x6a;
+
+/// Tagged hash of the message, committed to by `to_spend`'s scriptSig. Any
+/// message verifies; `validate_utxos` only lets through challenges issued by
+/// `ChallengeStore`, so published BIP-322 proofs cannot be replayed.
+pub fn message_hash(message: &str) -> [u8; 32] {
+    tagged_hash("BIP0322-signed-message", message.as_bytes())
+}
+
+/// Check a base64 BIP-322 simple signature of `message` for `script`: the
+/// witness stack of the virtual `to_sign` transaction. Simple signatures
+/// only cover native segwit outputs.
+pub fn verify_simple<C: Verification>(
+    secp: &Secp256k1<C>,
+    script: &ScriptPubKey,
+    message: &str,
+    signature: &str,
+) -> Result<()> {
+    let template = script.template();
+    if !matches!(template, ScriptTemplate::P2wpkh { .. } | ScriptTemplate::P2wsh { .. } | ScriptTemplate::P2tr { .. }) {
+        return Err(anyhow!("BIP-322 simple signatures cannot prove ownership of {} outputs; use a full signature", template));
+    }
+
+    let bytes = decode(signature)?;
+    let mut reader = Reader::new(&bytes);
+    let witness = reader.witness().map_err(|e| anyhow!("malformed witness stack: {}", e))?;
+    reader.finish().map_err(|e| anyhow!("malformed witness stack: {}", e))?;
+
+    let to_spend = to_spend(script, message);
+    verify_spend(secp, script, &to_sign(&to_spend, witness))
+}
+
+/// Check a base64 BIP-322 full signature of `message` for `script`: the
+/// serialized `to_sign` transaction. Proof-of-funds inputs are not supported.
+pub fn verify_full<C: Verification>(
+    secp: &Secp256k1<C>,
+    script: &ScriptPubKey,
+    message: &str,
+    signature: &str,
+) -> Result<()> {
+    let bytes = decode(signature)?;
+    let to_sign = Transaction::decode(&bytes).map_err(|e| anyhow!("malformed to_sign transaction: {}", e))?;
+
+    let to_spend_txid = to_spend(script, message).txid();
+    match &to_sign.inputs[..] {
+        [input] if input.prev_txid == to_spend_txid && input.prev_vout == 0 => {}
+        [_] => return Err(anyhow!("to_sign does not spend the to_spend transaction of this message and output")),
+        _ => return Err(anyhow!("to_sign must have exactly one input; proof-of-funds inputs are not supported")),
+    }
+    match &to_sign.outputs[..] {
+        [output] if output.value == 0 && output.script_pubkey == [OP_RETURN] => {}
+        _ => return Err(anyhow!("to_sign must have a single empty OP_RETURN output")),
+    }
+    verify_spend(secp, script, &to_sign)
+}
+
+fn decode(signature: &str) -> Result<Vec<u8>> {
+    BASE64.decode(signature.trim()).map_err(|e| anyhow!("signature is not base64: {}", e))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TxIn {
+    /// Internal byte order, reversed relative to the displayed txid
+    prev_txid: [u8; 32],
+    prev_vout: u32,
+    script_sig: Vec<u8>,
+    sequence: u32,
+    witness: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TxOut {
+    value: u64,
+    script_pubkey: Vec<u8>,
+}
+
+impl TxOut {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.value.to_le_bytes());
+        write_bytes(out, &self.script_pubkey);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Transaction {
+    version: i32,
+    inputs: Vec<TxIn>,
+    outputs: Vec<TxOut>,
+    lock_time: u32,
+}
+
+impl Transaction {
+    /// Consensus serialization, with the segwit marker and witnesses only
+    /// when `witness` is set and any input has one
+    fn encode(&self, witness: bool) -> Vec<u8> {
+        let witness = witness && self.inputs.iter().any(|input| !input.witness.is_empty());
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.version.to_le_bytes());
+        if witness {
+            out.extend_from_slice(&[0x00, 0x01]);
+        }
+        write_compact_size(&mut out, self.inputs.len() as u64);
+        for input in &self.inputs {
+            out.extend_from_slice(&input.prev_txid);
+            out.extend_from_slice(&input.prev_vout.to_le_bytes());
+            write_bytes(&mut out, &input.script_sig);
+            out.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        write_compact_size(&mut out, self.outputs.len() as u64);
+        for output in &self.outputs {
+            output.encode(&mut out);
+        }
+        if witness {
+            for input in &self.inputs {
+                write_witness(&mut out, &input.witness);
+            }
+        }
+        out.extend_from_slice(&self.lock_time.to_le_bytes());
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let version = reader.u32()? as i32;
+        let segwit = reader.data.starts_with(&[0x00, 0x01]);
+        if segwit {
+            reader.take(2)?;
+        }
+
+        let mut inputs = Vec::new();
+        for _ in 0..reader.compact_size()? {
+            inputs.push(TxIn {
+                prev_txid: reader.take(32)?.try_into().unwrap(),
+                prev_vout: reader.u32()?,
+                script_sig: reader.bytes()?.to_vec(),
+                sequence: reader.u32()?,
+                witness: Vec::new(),
+            });
+        }
+        if inputs.is_empty() {
+            return Err(anyhow!("no inputs"));
+        }
+        let mut outputs = Vec::new();
+        for _ in 0..reader.compact_size()? {
+            outputs.push(TxOut { value: reader.u64()?, script_pubkey: reader.bytes()?.to_vec() });
+        }
+        if segwit {
+            for input in &mut inputs {
+                input.witness = reader.witness()?;
+            }
+        }
+        let lock_time = reader.u32()?;
+        reader.finish()?;
+
+        Ok(Transaction { version, inputs, outputs, lock_time })
+    }
+
+    /// In internal byte order
+    fn txid(&self) -> [u8; 32] {
+        sha256d(&self.encode(false))
+    }
+}
+
+/// The virtual transaction whose only output, locked by `script`, is spent
+/// by the signature
+fn to_spend(script: &ScriptPubKey, message: &str) -> Transaction {
+    let mut script_sig = vec![0x00, 0x20];
+    script_sig.extend_from_slice(&message_hash(message));
+    Transaction {
+        version: 0,
+        inputs: vec![TxIn { prev_txid: [0; 32], prev_vout: 0xffff_ffff, script_sig, sequence: 0, witness: Vec::new() }],
+        outputs: vec![TxOut { value: 0, script_pubkey: script.as_bytes().to_vec() }],
+        lock_time: 0,
+    }
+}
+
+/// The virtual transaction a simple signature is the witness of
+fn to_sign(to_spend: &Transaction, witness: Vec<Vec<u8>>) -> Transaction {
+    Transaction {
+        version: 0,
+        inputs: vec![TxIn { prev_txid: to_spend.txid(), prev_vout: 0, script_sig: Vec::new(), sequence: 0, witness }],
+        outputs: vec![TxOut { value: 0, script_pubkey: vec![OP_RETURN] }],
+        lock_time: 0,
+    }
+}
+
+/// Check that the first input of `to_sign` satisfies `script`, for the
+/// standard single-key and multisig templates
+fn verify_spend<C: Verification>(secp: &Secp256k1<C>, script: &ScriptPubKey, to_sign: &Transaction) -> Result<()> {
+    let input = &to_sign.inputs[0];
+    let template = script.template();
+    let native_segwit = matches!(template, ScriptTemplate::P2wpkh { .. } | ScriptTemplate::P2wsh { .. } | ScriptTemplate::P2tr { .. });
+    if native_segwit && !input.script_sig.is_empty() {
+        return Err(anyhow!("{} spends must have an empty scriptSig", template));
+    }
+
+    match template {
+        ScriptTemplate::P2pkh { pubkey_hash } => {
+            if !input.witness.is_empty() {
+                return Err(anyhow!("p2pkh spends must have an empty witness"));
+            }
+            let [signature, pubkey] = pushes(&input.script_sig)?[..] else {
+                return Err(anyhow!("p2pkh scriptSig must push a signature and a public key"));
+            };
+            if hash160(pubkey) != pubkey_hash {
+                return Err(anyhow!("public key does not match this output"));
+            }
+            let (signature, hash_type) = ecdsa_signature(signature)?;
+            verify_ecdsa(secp, legacy_sighash(to_sign, script.as_bytes(), hash_type), &signature, pubkey)
+        }
+        ScriptTemplate::P2sh { script_hash } => {
+            let [redeem_script] = pushes(&input.script_sig)?[..] else {
+                return Err(anyhow!("p2sh scriptSig must push only the redeem script"));
+            };
+            if hash160(redeem_script) != script_hash {
+                return Err(anyhow!("redeem script does not match this output"));
+            }
+            match ScriptPubKey::from_bytes(redeem_script.to_vec()).template() {
+                ScriptTemplate::P2wpkh { pubkey_hash } => verify_p2wpkh(secp, to_sign, pubkey_hash),
+                _ => Err(anyhow!("only P2SH-wrapped P2WPKH outputs are supported")),
+            }
+        }
+        ScriptTemplate::P2wpkh { pubkey_hash } => verify_p2wpkh(secp, to_sign, pubkey_hash),
+        ScriptTemplate::P2wsh { script_hash } => verify_p2wsh(secp, to_sign, script_hash),
+        ScriptTemplate::P2tr { output_key } => verify_p2tr(secp, to_sign, script, output_key),
+        template => Err(anyhow!("BIP-322 signatures cannot prove ownership of {} outputs", template)),
+    }
+}
+
+fn verify_p2wpkh<C: Verification>(secp: &Secp256k1<C>, to_sign: &Transaction, pubkey_hash: [u8; 20]) -> Result<()> {
+    let [signature, pubkey] = &to_sign.inputs[0].witness[..] else {
+        return Err(anyhow!("p2wpkh witness must hold a signature and a public key"));
+    };
+    if hash160(pubkey) != pubkey_hash {
+        return Err(anyhow!("public key does not match this output"));
+    }
+    let mut script_code = vec![0x76, 0xa9, 0x14];
+    script_code.extend_from_slice(&pubkey_hash);
+    script_code.extend_from_slice(&[0x88, 0xac]);
+    verify_segwit_v0(secp, to_sign, &script_code, signature, pubkey)
+}
+
+/// Witness scripts of a single key (`<pubkey> OP_CHECKSIG`) or a multisig
+fn verify_p2wsh<C: Verification>(secp: &Secp256k1<C>, to_sign: &Transaction, script_hash: [u8; 32]) -> Result<()> {
+    let Some((witness_script, stack)) = to_sign.inputs[0].witness.split_last() else {
+        return Err(anyhow!("p2wsh witness is empty"));
+    };
+    if <[u8; 32]>::from(Sha256::digest(witness_script)) != script_hash {
+        return Err(anyhow!("witness script does not match this output"));
+    }
+
+    match ScriptPubKey::from_bytes(witness_script.clone()).template() {
+        ScriptTemplate::P2pk { pubkey } => {
+            let [signature] = stack else {
+                return Err(anyhow!("p2wsh witness must hold one signature for a single-key script"));
+            };
+            verify_segwit_v0(secp, to_sign, witness_script, signature, &pubkey)
+        }
+        ScriptTemplate::Multisig { required, pubkeys } => {
+            let [dummy, signatures @ ..] = stack else {
+                return Err(anyhow!("p2wsh multisig witness is empty"));
+            };
+            if !dummy.is_empty() || signatures.len() != required as usize {
+                return Err(anyhow!("p2wsh multisig witness must hold an empty item and {} signatures", required));
+            }
+            // Like OP_CHECKMULTISIG, signatures must follow the order of their keys
+            let mut pubkeys = pubkeys.iter();
+            for signature in signatures {
+                pubkeys
+                    .find(|pubkey| verify_segwit_v0(secp, to_sign, witness_script, signature, pubkey).is_ok())
+                    .ok_or_else(|| anyhow!("multisig signatures are not by the keys of this output"))?;
+            }
+            Ok(())
+        }
+        _ => Err(anyhow!("only single-key and multisig witness scripts are supported")),
+    }
+}
+
+/// Key-path spends only
+fn verify_p2tr<C: Verification>(
+    secp: &Secp256k1<C>,
+    to_sign: &Transaction,
+    script: &ScriptPubKey,
+    output_key: [u8; 32],
+) -> Result<()> {
+    let witness = &to_sign.inputs[0].witness;
+    if witness.len() > 1 && witness.last().is_some_and(|item| item.first() == Some(&ANNEX_TAG)) {
+        return Err(anyhow!("taproot annexes are not supported"));
+    }
+    let [signature] = &witness[..] else {
+        return Err(anyhow!("only taproot key-path spends, a single signature, are supported"));
+    };
+    let (signature, hash_type) = match signature.len() {
+        64 => (&signature[..], SIGHASH_DEFAULT),
+        65 if signature[64] == SIGHASH_ALL => (&signature[..64], SIGHASH_ALL),
+        65 => return Err(anyhow!("sighash type {:#04x} is not supported, sign with SIGHASH_DEFAULT or SIGHASH_ALL", signature[64])),
+        len => return Err(anyhow!("taproot signatures are 64 or 65 bytes, got {}", len)),
+    };
+
+    let signature = schnorr::Signature::from_slice(signature).map_err(|e| anyhow!("malformed signature: {}", e))?;
+    let output_key = XOnlyPublicKey::from_slice(&output_key).map_err(|_| anyhow!("output key is not a valid public key"))?;
+    let digest = taproot_sighash(to_sign, script.as_bytes(), hash_type);
+    secp.verify_schnorr(&signature, &Message::from_digest(digest), &output_key)
+        .map_err(|_| anyhow!("signature is not by the key of this output"))
+}
+
+fn verify_segwit_v0<C: Verification>(
+    secp: &Secp256k1<C>,
+    to_sign: &Transaction,
+    script_code: &[u8],
+    signature: &[u8],
+    pubkey: &[u8],
+) -> Result<()> {
+    if pubkey.len() != 33 {
+        return Err(anyhow!("segwit public keys must be compressed"));
+    }
+    let (signature, hash_type) = ecdsa_signature(signature)?;
+    verify_ecdsa(secp, segwit_v0_sighash(to_sign, script_code, hash_type), &signature, pubkey)
+}
+
+/// DER signature followed by its hash type, which must be `SIGHASH_ALL`
+fn ecdsa_signature(bytes: &[u8]) -> Result<(ecdsa::Signature, u8)> {
+    let Some((&hash_type, der)) = bytes.split_last() else {
+        return Err(anyhow!("signature is empty"));
+    };
+    if hash_type != SIGHASH_ALL {
+        return Err(anyhow!("sighash type {:#04x} is not supported, sign with SIGHASH_ALL", hash_type));
+    }
+    let signature = ecdsa::Signature::from_der(der).map_err(|e| anyhow!("malformed signature: {}", e))?;
+    Ok((signature, hash_type))
+}
+
+fn verify_ecdsa<C: Verification>(secp: &Secp256k1<C>, digest: [u8; 32], signature: &ecdsa::Signature, pubkey: &[u8]) -> Result<()> {
+    let pubkey = PublicKey::from_slice(pubkey).map_err(|_| anyhow!("malformed public key"))?;
+    secp.verify_ecdsa(&Message::from_digest(digest), signature, &pubkey)
+        .map_err(|_| anyhow!("signature is not by the key of this output"))
+}
+
+// The sighashes below sign the first input, which spends `to_spend`'s
+// zero-value output.
+
+/// Pre-segwit digest: the transaction with the signed input's scriptSig
+/// replaced by `script_code`
+fn legacy_sighash(tx: &Transaction, script_code: &[u8], hash_type: u8) -> [u8; 32] {
+    let mut tx = tx.clone();
+    for (index, input) in tx.inputs.iter_mut().enumerate() {
+        input.script_sig = if index == 0 { script_code.to_vec() } else { Vec::new() };
+        input.witness.clear();
+    }
+    let mut data = tx.encode(false);
+    data.extend_from_slice(&(hash_type as u32).to_le_bytes());
+    sha256d(&data)
+}
+
+/// BIP 143 digest, for `SIGHASH_ALL`
+fn segwit_v0_sighash(tx: &Transaction, script_code: &[u8], hash_type: u8) -> [u8; 32] {
+    let (prevouts, sequences, outputs) = tx_parts(tx);
+    let input = &tx.inputs[0];
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&tx.version.to_le_bytes());
+    data.extend_from_slice(&sha256d(&prevouts));
+    data.extend_from_slice(&sha256d(&sequences));
+    data.extend_from_slice(&input.prev_txid);
+    data.extend_from_slice(&input.prev_vout.to_le_bytes());
+    write_bytes(&mut data, script_code);
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&input.sequence.to_le_bytes());
+    data.extend_from_slice(&sha256d(&outputs));
+    data.extend_from_slice(&tx.lock_time.to_le_bytes());
+    data.extend_from_slice(&(hash_type as u32).to_le_bytes());
+    sha256d(&data)
+}
+
+/// BIP 341 key-path digest, for `SIGHASH_DEFAULT` and `SIGHASH_ALL`
+fn taproot_sighash(tx: &Transaction, spent_script: &[u8], hash_type: u8) -> [u8; 32] {
+    let (prevouts, sequences, outputs) = tx_parts(tx);
+    let mut spent_scripts = Vec::new();
+    write_bytes(&mut spent_scripts, spent_script);
+
+    // Epoch, then the signature message
+    let mut data = vec![0x00, hash_type];
+    data.extend_from_slice(&tx.version.to_le_bytes());
+    data.extend_from_slice(&tx.lock_time.to_le_bytes());
+    data.extend_from_slice(&Sha256::digest(&prevouts));
+    data.extend_from_slice(&Sha256::digest(0u64.to_le_bytes()));
+    data.extend_from_slice(&Sha256::digest(&spent_scripts));
+    data.extend_from_slice(&Sha256::digest(&sequences));
+    data.extend_from_slice(&Sha256::digest(&outputs));
+    // Key path without annex, input 0
+    data.push(0x00);
+    data.extend_from_slice(&0u32.to_le_bytes());
+    tagged_hash("TapSighash", &data)
+}
+
+/// Serialized outpoints, sequences and outputs of `tx`
+fn tx_parts(tx: &Transaction) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    let (mut prevouts, mut sequences, mut outputs) = (Vec::new(), Vec::new(), Vec::new());
+    for input in &tx.inputs {
+        prevouts.extend_from_slice(&input.prev_txid);
+        prevouts.extend_from_slice(&input.prev_vout.to_le_bytes());
+        sequences.extend_from_slice(&input.sequence.to_le_bytes());
+    }
+    for output in &tx.outputs {
+        output.encode(&mut outputs);
+    }
+    (prevouts, sequences, outputs)
+}
+
+/// Data pushed by a push-only script
+fn pushes(script: &[u8]) -> Result<Vec<&[u8]>> {
+    let mut reader = Reader::new(script);
+    let mut items = Vec::new();
+    while !reader.data.is_empty() {
+        let len = match reader.take(1)?[0] {
+            op @ 0x00..=0x4b => op as usize,
+            0x4c => reader.take(1)?[0] as usize,
+            0x4d => u16::from_le_bytes(reader.take(2)?.try_into().unwrap()) as usize,
+            0x4e => reader.u32()? as usize,
+            op => return Err(anyhow!("scriptSig must only push data, found opcode {:#04x}", op)),
+        };
+        items.push(reader.take(len)?);
+    }
+    Ok(items)
+}
+
+fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag = Sha256::digest(tag.as_bytes());
+    Sha256::new().chain_update(tag).chain_update(tag).chain_update(data).finalize().into()
+}
+
+fn sha256d(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_witness(out: &mut Vec<u8>, witness: &[Vec<u8>]) {
+    write_compact_size(out, witness.len() as u64);
+    for item in witness {
+        write_bytes(out, item);
+    }
+}
+
+/// Reads consensus-encoded values, failing on truncated data
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.data.len() {
+            return Err(anyhow!("unexpected end of data"));
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn compact_size(&mut self) -> Result<u64> {
+        Ok(match self.take(1)?[0] {
+            0xfd => u16::from_le_bytes(self.take(2)?.try_into().unwrap()) as u64,
+            0xfe => self.u32()? as u64,
+            0xff => self.u64()?,
+            n => n as u64,
+        })
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = usize::try_from(self.compact_size()?)?;
+        self.take(len)
+    }
+
+    fn witness(&mut self) -> Result<Vec<Vec<u8>>> {
+        (0..self.compact_size()?).map(|_| self.bytes().map(<[u8]>::to_vec)).collect()
+    }
+
+    fn finish(&self) -> Result<()> {
+        if !self.data.is_empty() {
+            return Err(anyhow!("{} trailing bytes", self.data.len()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::{Keypair, Scalar, SecretKey};
+    use crate::bitcoin::{Amount, Txid};
+    use crate::challenge::ChallengeStore;
+    use crate::error::Error;
+    use crate::ownership::SignatureVerifier;
+    use crate::types::{OwnershipProof, SignatureFormat, Utxo};
+    use crate::validation::validate_utxos;
+
+    /// bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l, from the BIP-322 test vectors
+    const P2WPKH: &str = "00142b05d564e6a7a33c087f16e0f730d1440123799d";
+    /// bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3, same key
+    const P2TR: &str = "51200b34f2cc6f60d54e3fdc2d1dd053fcc393bd2db9acc8de4a7c3cc28a83d4d8e9";
+    const CHALLENGE: &str = "zkpoor proof of reserves 2024-06-01";
+
+    fn script(hex: &str) -> ScriptPubKey {
+        hex.parse().unwrap()
+    }
+
+    /// Displayed (reversed) txid
+    fn display(txid: [u8; 32]) -> String {
+        let mut txid = txid;
+        txid.reverse();
+        hex::encode(txid)
+    }
+
+    fn key(byte: u8) -> (SecretKey, PublicKey) {
+        let secret = SecretKey::from_slice(&[byte; 32]).unwrap();
+        (secret, PublicKey::from_secret_key(&Secp256k1::new(), &secret))
+    }
+
+    fn ecdsa_sign(secret: &SecretKey, digest: [u8; 32]) -> Vec<u8> {
+        let mut signature = Secp256k1::new().sign_ecdsa(&Message::from_digest(digest), secret).serialize_der().to_vec();
+        signature.push(SIGHASH_ALL);
+        signature
+    }
+
+    fn simple(witness: &[Vec<u8>]) -> String {
+        let mut out = Vec::new();
+        write_witness(&mut out, witness);
+        BASE64.encode(out)
+    }
+
+    fn p2wsh(witness_script: &[u8]) -> ScriptPubKey {
+        let mut script = vec![0x00, 0x20];
+        script.extend_from_slice(&Sha256::digest(witness_script));
+        ScriptPubKey::from_bytes(script)
+    }
+
+    #[test]
+    fn test_message_hash_and_virtual_transactions() {
+        assert_eq!(hex::encode(message_hash("")), "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1");
+        assert_eq!(hex::encode(message_hash("Hello World")), "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a");
+
+        let script = script(P2WPKH);
+        let to_spend_empty = to_spend(&script, "");
+        let to_spend_hello = to_spend(&script, "Hello World");
+        assert_eq!(display(to_spend_empty.txid()), "c5680aa69bb8d860bf82d4e9cd3504b55dde018de765a91bb566283c545a99a7");
+        assert_eq!(display(to_spend_hello.txid()), "b79d196740ad5217771c1098fc4a4b51e0535c32236c71f1ea4d61a2d603352b");
+        assert_eq!(display(to_sign(&to_spend_empty, Vec::new()).txid()), "1e9654e951a5ba44c8604c4de6c67fd78a27e81dcadcfe1edf638ba3aaebaed6");
+        assert_eq!(display(to_sign(&to_spend_hello, Vec::new()).txid()), "88737ae86f2077145f93cc4b153ae9a1cb8d56afa511988c149c5c8c9d93bddf");
+    }
+
+    #[test]
+    fn test_known_simple_signatures() {
+        let secp = Secp256k1::verification_only();
+        let p2wpkh = script(P2WPKH);
+        let empty = "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+        let hello = "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+        verify_simple(&secp, &p2wpkh, "", empty).unwrap();
+        verify_simple(&secp, &p2wpkh, "Hello World", hello).unwrap();
+        assert!(verify_simple(&secp, &p2wpkh, "Hello World", empty).is_err());
+
+        let p2tr = script(P2TR);
+        let hello = "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+        verify_simple(&secp, &p2tr, "Hello World", hello).unwrap();
+        let error = verify_simple(&secp, &p2tr, "Hello World!", hello).unwrap_err();
+        assert_eq!(error.to_string(), "signature is not by the key of this output");
+    }
+
+    #[test]
+    fn test_taproot_key_path() {
+        let secp = Secp256k1::new();
+        let (secret, _) = key(3);
+        let keypair = Keypair::from_secret_key(&secp, &secret);
+        let (internal, _) = keypair.x_only_public_key();
+        let tweak = Scalar::from_be_bytes(tagged_hash("TapTweak", &internal.serialize())).unwrap();
+        let tweaked = keypair.add_xonly_tweak(&secp, &tweak).unwrap();
+        let mut script = vec![0x51, 0x20];
+        script.extend_from_slice(&tweaked.x_only_public_key().0.serialize());
+        let script = ScriptPubKey::from_bytes(script);
+
+        let tx = to_sign(&to_spend(&script, CHALLENGE), Vec::new());
+        let sign = |hash_type| {
+            let digest = Message::from_digest(taproot_sighash(&tx, script.as_bytes(), hash_type));
+            secp.sign_schnorr_no_aux_rand(&digest, &tweaked).as_ref().to_vec()
+        };
+        verify_simple(&secp, &script, CHALLENGE, &simple(&[sign(SIGHASH_DEFAULT)])).unwrap();
+        let mut signature = sign(SIGHASH_ALL);
+        signature.push(SIGHASH_ALL);
+        verify_simple(&secp, &script, CHALLENGE, &simple(&[signature.clone()])).unwrap();
+
+        // The hash type is committed to
+        assert!(verify_simple(&secp, &script, CHALLENGE, &simple(&[signature[..64].to_vec()])).is_err());
+        let error = verify_simple(&secp, &script, CHALLENGE, &simple(&[signature, vec![0xc0]])).unwrap_err();
+        assert!(error.to_string().contains("key-path"));
+    }
+
+    #[test]
+    fn test_p2wsh_single_key_and_multisig() {
+        let secp = Secp256k1::new();
+        let keys: Vec<_> = (1..=3).map(key).collect();
+
+        let mut single = vec![0x21];
+        single.extend_from_slice(&keys[0].1.serialize());
+        single.push(0xac);
+        let script = p2wsh(&single);
+        let tx = to_sign(&to_spend(&script, CHALLENGE), Vec::new());
+        let signature = ecdsa_sign(&keys[0].0, segwit_v0_sighash(&tx, &single, SIGHASH_ALL));
+        verify_simple(&secp, &script, CHALLENGE, &simple(&[signature, single.clone()])).unwrap();
+
+        // 2-of-3
+        let mut multisig = vec![0x52];
+        for (_, public) in &keys {
+            multisig.push(0x21);
+            multisig.extend_from_slice(&public.serialize());
+        }
+        multisig.extend_from_slice(&[0x53, 0xae]);
+        let script = p2wsh(&multisig);
+        let tx = to_sign(&to_spend(&script, CHALLENGE), Vec::new());
+        let digest = segwit_v0_sighash(&tx, &multisig, SIGHASH_ALL);
+        let (first, third) = (ecdsa_sign(&keys[0].0, digest), ecdsa_sign(&keys[2].0, digest));
+        verify_simple(&secp, &script, CHALLENGE, &simple(&[vec![], first.clone(), third.clone(), multisig.clone()])).unwrap();
+
+        assert!(verify_simple(&secp, &script, CHALLENGE, &simple(&[vec![], third.clone(), first.clone(), multisig.clone()])).is_err());
+        assert!(verify_simple(&secp, &script, CHALLENGE, &simple(&[vec![], first.clone(), multisig.clone()])).is_err());
+        assert!(verify_simple(&secp, &script, CHALLENGE, &simple(&[vec![], first, third, single])).is_err());
+    }
+
+    #[test]
+    fn test_full_signatures() {
+        let secp = Secp256k1::new();
+        let (secret, public) = key(5);
+        let pubkey = public.serialize();
+
+        // P2PKH, in a scriptSig
+        let mut script = vec![0x76, 0xa9, 0x14];
+        script.extend_from_slice(&hash160(&pubkey));
+        script.extend_from_slice(&[0x88, 0xac]);
+        let script = ScriptPubKey::from_bytes(script);
+        let mut tx = to_sign(&to_spend(&script, CHALLENGE), Vec::new());
+        let signature = ecdsa_sign(&secret, legacy_sighash(&tx, script.as_bytes(), SIGHASH_ALL));
+        tx.inputs[0].script_sig = [vec![signature.len() as u8], signature, vec![0x21], pubkey.to_vec()].concat();
+        let full = BASE64.encode(tx.encode(true));
+        verify_full(&secp, &script, CHALLENGE, &full).unwrap();
+        assert!(verify_full(&secp, &script, "another challenge", &full).unwrap_err().to_string().contains("to_spend"));
+        let error = verify_simple(&secp, &script, CHALLENGE, &full).unwrap_err();
+        assert!(error.to_string().contains("use a full signature"));
+
+        // P2SH-P2WPKH, with the redeem script in the scriptSig
+        let mut redeem_script = vec![0x00, 0x14];
+        redeem_script.extend_from_slice(&hash160(&pubkey));
+        let mut script = vec![0xa9, 0x14];
+        script.extend_from_slice(&hash160(&redeem_script));
+        script.push(0x87);
+        let script = ScriptPubKey::from_bytes(script);
+        let mut tx = to_sign(&to_spend(&script, CHALLENGE), Vec::new());
+        tx.version = 2;
+        tx.inputs[0].script_sig = [vec![0x16], redeem_script.clone()].concat();
+        let script_code = [&[0x76, 0xa9, 0x14], &redeem_script[2..], &[0x88, 0xac]].concat();
+        tx.inputs[0].witness = vec![ecdsa_sign(&secret, segwit_v0_sighash(&tx, &script_code, SIGHASH_ALL)), pubkey.to_vec()];
+        verify_full(&secp, &script, CHALLENGE, &BASE64.encode(tx.encode(true))).unwrap();
+
+        // The spend must be of to_spend, to a lone empty OP_RETURN
+        tx.outputs[0].value = 1;
+        assert!(verify_full(&secp, &script, CHALLENGE, &BASE64.encode(tx.encode(true))).is_err());
+        tx.outputs[0].value = 0;
+        tx.inputs.push(tx.inputs[0].clone());
+        let error = verify_full(&secp, &script, CHALLENGE, &BASE64.encode(tx.encode(true))).unwrap_err();
+        assert!(error.to_string().contains("proof-of-funds"));
+    }
+
+    #[test]
+    fn test_transaction_round_trip() {
+        let mut tx = to_sign(&to_spend(&script(P2WPKH), ""), vec![vec![1, 2, 3], vec![]]);
+        tx.inputs[0].script_sig = vec![0x51];
+        let bytes = tx.encode(true);
+        assert_eq!(Transaction::decode(&bytes).unwrap(), tx);
+        assert!(Transaction::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Transaction::decode(&[bytes.as_slice(), &[0]].concat()).is_err());
+        assert_eq!(pushes(&[0x00, 0x4c, 0x01, 0xaa]).unwrap(), [&[][..], &[0xaa][..]]);
+        assert!(pushes(&[0x76]).is_err());
+    }
+
+    #[test]
+    fn test_replayed_signatures_need_an_issued_challenge() {
+        let secp = Secp256k1::new();
+        let challenges = ChallengeStore::default();
+        let verifier = SignatureVerifier::new();
+        let utxo = |hex: &str| Utxo {
+            txid: Txid::from_bytes([1; 32]),
+            vout: 0,
+            amount: Amount::from_sat(1000).unwrap(),
+            script_pubkey: script(hex),
+        };
+        let proof = |challenge: &str, signature: String, format| OwnershipProof {
+            signature,
+            challenge: challenge.to_string(),
+            format,
+        };
+        let codes = |utxos: &[Utxo], proofs: &[OwnershipProof]| match validate_utxos(utxos, proofs, &verifier, &challenges) {
+            Ok(_) => Vec::new(),
+            Err(Error::InvalidUtxos(problems)) => problems.iter().map(Error::code).collect(),
+            Err(e) => panic!("unexpected error {}", e),
+        };
+
+        // Published test vectors: valid signatures, over someone else's message
+        let p2tr = [utxo(P2TR)];
+        let hello = "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+        verify_simple(&secp, &p2tr[0].script_pubkey, "Hello World", hello).unwrap();
+        assert_eq!(codes(&p2tr, &[proof("Hello World", hello.to_string(), SignatureFormat::Bip322Simple)]), ["bad_challenge"]);
+        let p2wpkh = [utxo(P2WPKH)];
+        let empty = "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+        assert_eq!(codes(&p2wpkh, &[proof("", empty.to_string(), SignatureFormat::Bip322Simple)]), ["bad_challenge"]);
+
+        // Simple and full signatures over an issued challenge, each usable once
+        let (secret, public) = key(4);
+        let mut single = vec![0x21];
+        single.extend_from_slice(&public.serialize());
+        single.push(0xac);
+        let p2wsh = [Utxo { script_pubkey: p2wsh(&single), ..utxo(P2WPKH) }];
+        let message = challenges.issue().message;
+        let tx = to_sign(&to_spend(&p2wsh[0].script_pubkey, &message), Vec::new());
+        let signature = ecdsa_sign(&secret, segwit_v0_sighash(&tx, &single, SIGHASH_ALL));
+        let simple_proof = [proof(&message, simple(&[signature.clone(), single.clone()]), SignatureFormat::Bip322Simple)];
+        assert!(codes(&p2wsh, &simple_proof).is_empty());
+
+        let mut tx = tx;
+        tx.inputs[0].witness = vec![signature, single];
+        let full_proof = [proof(&message, BASE64.encode(tx.encode(true)), SignatureFormat::Bip322Full)];
+        assert!(codes(&p2wsh, &full_proof).is_empty());
+
+        challenges.redeem(&simple_proof).unwrap();
+        assert_eq!(codes(&p2wsh, &full_proof), ["bad_challenge"]);
+    }
+}
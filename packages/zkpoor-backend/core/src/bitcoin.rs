@@ -0,0 +1,257 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Satoshis in one bitcoin
+pub const COIN: u64 = 100_000_000;
+
+/// The 21M BTC supply cap. No single amount, nor any sum of them, can exceed it.
+pub const MAX_MONEY: u64 = 21_000_000 * COIN;
+
+/// Why a txid, outpoint, amount or script was rejected
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    #[error("txid must be 64 hex characters, got '{0}'")]
+    InvalidTxid(String),
+
+    #[error("outpoint must be '<txid>:<vout>', got '{0}'")]
+    InvalidOutPoint(String),
+
+    #[error("amount of {0} sat exceeds the 21,000,000 BTC supply")]
+    AmountTooLarge(u64),
+
+    #[error("script_pubkey is not valid hex: {0}")]
+    InvalidScript(String),
+}
+
+/// Transaction ID, in the usual RPC display order
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Txid([u8; 32]);
+
+impl Txid {
+    /// From bytes in display order
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Txid(bytes)
+    }
+
+    /// Bytes in display order, reversed relative to the transaction hash
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
+
+impl FromStr for Txid {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(s, &mut bytes).map_err(|_| ParseError::InvalidTxid(s.to_string()))?;
+        Ok(Txid(bytes))
+    }
+}
+
+impl fmt::Display for Txid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::Debug for Txid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// A transaction output, `<txid>:<vout>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct OutPoint {
+    pub txid: Txid,
+    pub vout: u32,
+}
+
+impl FromStr for OutPoint {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::InvalidOutPoint(s.to_string());
+        let (txid, vout) = s.split_once(':').ok_or_else(invalid)?;
+        Ok(OutPoint {
+            txid: txid.parse().map_err(|_| invalid())?,
+            vout: vout.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl fmt::Display for OutPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.vout)
+    }
+}
+
+/// An amount of satoshis, never above `MAX_MONEY`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+    pub const MAX: Amount = Amount(MAX_MONEY);
+
+    pub fn from_sat(sat: u64) -> Result<Self, ParseError> {
+        if sat > MAX_MONEY {
+            return Err(ParseError::AmountTooLarge(sat));
+        }
+        Ok(Amount(sat))
+    }
+
+    pub fn to_sat(self) -> u64 {
+        self.0
+    }
+
+    /// `None` if the result would exceed `MAX_MONEY`
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        Amount::from_sat(self.0.checked_add(other.0)?).ok()
+    }
+
+    /// Total of `amounts`, `None` if it would exceed `MAX_MONEY`
+    pub fn checked_sum(amounts: impl IntoIterator<Item = Amount>) -> Option<Amount> {
+        amounts.into_iter().try_fold(Amount::ZERO, Amount::checked_add)
+    }
+}
+
+/// Satoshis, as in the API
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Amount::from_sat(u64::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Locking script of an output, hex-encoded in the API
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct ScriptPubKey(Vec<u8>);
+
+impl ScriptPubKey {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        ScriptPubKey(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.0)
+    }
+}
+
+impl FromStr for ScriptPubKey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        hex::decode(s)
+            .map(ScriptPubKey)
+            .map_err(|e| ParseError::InvalidScript(e.to_string()))
+    }
+}
+
+impl fmt::Display for ScriptPubKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::Debug for ScriptPubKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ScriptPubKey({})", self.to_hex())
+    }
+}
+
+/// Hex-string serde for the types above
+macro_rules! serde_via_str {
+    ($($ty:ty),*) => {$(
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    )*};
+}
+
+serde_via_str!(Txid, ScriptPubKey);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TXID: &str = "92902693c34c80da75da19f97bfb3719013883d8d307e88011a648363dd2f334";
+
+    #[test]
+    fn test_txid_round_trip() {
+        let txid: Txid = TXID.parse().unwrap();
+        assert_eq!(txid.to_string(), TXID);
+        assert_eq!(TXID.to_uppercase().parse::<Txid>().unwrap(), txid);
+        assert_eq!(serde_json::to_value(txid).unwrap(), TXID);
+
+        assert!("mock_txid_1".parse::<Txid>().is_err());
+        assert!(TXID[2..].parse::<Txid>().is_err());
+        assert!(format!("{}00", TXID).parse::<Txid>().is_err());
+        assert!(serde_json::from_str::<Txid>("\"abcd\"").is_err());
+    }
+
+    #[test]
+    fn test_outpoint_parse() {
+        let outpoint: OutPoint = format!("{}:1", TXID).parse().unwrap();
+        assert_eq!(outpoint.vout, 1);
+        assert_eq!(outpoint.to_string(), format!("{}:1", TXID));
+        assert!(TXID.parse::<OutPoint>().is_err());
+        assert!(format!("{}:-1", TXID).parse::<OutPoint>().is_err());
+    }
+
+    #[test]
+    fn test_amount_is_bounded_by_supply() {
+        assert_eq!(Amount::from_sat(MAX_MONEY).unwrap(), Amount::MAX);
+        assert_eq!(Amount::from_sat(MAX_MONEY + 1), Err(ParseError::AmountTooLarge(MAX_MONEY + 1)));
+        assert!(serde_json::from_str::<Amount>("2100000000000001").is_err());
+        assert!(serde_json::from_str::<Amount>("-1").is_err());
+        assert_eq!(serde_json::from_str::<Amount>("5000").unwrap().to_sat(), 5000);
+
+        let half = Amount::from_sat(MAX_MONEY / 2 + 1).unwrap();
+        assert_eq!(half.checked_add(half), None);
+        assert_eq!(Amount::checked_sum([half, half]), None);
+        assert_eq!(Amount::checked_sum([Amount::from_sat(1).unwrap(); 3]).unwrap().to_sat(), 3);
+        assert_eq!(Amount::checked_sum([]), Some(Amount::ZERO));
+    }
+
+    #[test]
+    fn test_script_pubkey_hex() {
+        let script: ScriptPubKey = "76a914044f70e664eb08b6efe9c8daebb85364a16dd59b88ac".parse().unwrap();
+        assert_eq!(script.len(), 25);
+        assert_eq!(script.as_bytes()[0], 0x76);
+        assert_eq!(serde_json::to_value(&script).unwrap(), "76a914044f70e664eb08b6efe9c8daebb85364a16dd59b88ac");
+        assert!("mock_script".parse::<ScriptPubKey>().is_err());
+        assert!("abc".parse::<ScriptPubKey>().is_err());
+    }
+}
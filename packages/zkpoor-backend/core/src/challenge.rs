@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rand::RngCore;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::types::OwnershipProof;
+
+/// Operator named in challenges unless one is configured
+pub const DEFAULT_OPERATOR: &str = "zkpoor";
+
+/// How long an issued challenge can be redeemed
+pub const DEFAULT_CHALLENGE_TTL: Duration = Duration::minutes(10);
+
+/// A message for ownership proofs to sign, issued by `ChallengeStore`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Challenge {
+    /// The exact text to sign
+    pub message: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Single-use challenges issued by this server. Ownership proofs must sign
+/// one, so a signature the key holder made for anything else (an earlier
+/// attestation, another operator, a forum post) cannot be replayed in a new
+/// job. Challenges are kept in memory and do not survive a restart.
+pub struct ChallengeStore {
+    operator: String,
+    ttl: Duration,
+    /// Outstanding challenges by message, with their expiry
+    issued: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl ChallengeStore {
+    pub fn new(operator: impl Into<String>, ttl: Duration) -> Self {
+        Self { operator: operator.into(), ttl, issued: Mutex::new(HashMap::new()) }
+    }
+
+    /// A fresh challenge naming the operator, valid for the configured TTL
+    pub fn issue(&self) -> Challenge {
+        let mut nonce = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let now = Utc::now();
+        let expires_at = now + self.ttl;
+        let message = format!(
+            "zkpoor ownership proof\noperator: {}\nnonce: {}\nexpires: {}",
+            self.operator,
+            hex::encode(nonce),
+            expires_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        );
+
+        let mut issued = self.issued();
+        issued.retain(|_, expiry| *expiry > now);
+        issued.insert(message.clone(), expires_at);
+        Challenge { message, expires_at }
+    }
+
+    /// Why `message` cannot be redeemed, if it cannot
+    pub fn check(&self, message: &str) -> Result<(), String> {
+        check(&self.issued(), message, Utc::now())
+    }
+
+    /// Use up the challenges signed by `proofs`. Proofs of one request may
+    /// share a challenge. Fails without redeeming any of them, as
+    /// `Error::InvalidUtxos`, if one is unknown, expired or already used.
+    pub fn redeem(&self, proofs: &[OwnershipProof]) -> Result<(), Error> {
+        let now = Utc::now();
+        let mut issued = self.issued();
+        let problems: Vec<Error> = proofs.iter()
+            .enumerate()
+            .filter_map(|(index, proof)| {
+                check(&issued, &proof.challenge, now).err().map(|reason| Error::BadChallenge { index, reason })
+            })
+            .collect();
+        if !problems.is_empty() {
+            return Err(Error::InvalidUtxos(problems));
+        }
+
+        let used: HashSet<&str> = proofs.iter().map(|proof| proof.challenge.as_str()).collect();
+        for message in used {
+            issued.remove(message);
+        }
+        Ok(())
+    }
+
+    fn issued(&self) -> MutexGuard<'_, HashMap<String, DateTime<Utc>>> {
+        self.issued.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for ChallengeStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_OPERATOR, DEFAULT_CHALLENGE_TTL)
+    }
+}
+
+fn check(issued: &HashMap<String, DateTime<Utc>>, message: &str, now: DateTime<Utc>) -> Result<(), String> {
+    match issued.get(message) {
+        Some(expires_at) if *expires_at > now => Ok(()),
+        Some(_) => Err("challenge has expired; request a new one from POST /challenge".to_string()),
+        None => Err("challenge was not issued by this server or has already been used; request one from POST /challenge".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SignatureFormat;
+
+    fn proof(challenge: &str) -> OwnershipProof {
+        OwnershipProof {
+            signature: "mock_signature".to_string(),
+            challenge: challenge.to_string(),
+            format: SignatureFormat::Legacy,
+        }
+    }
+
+    #[test]
+    fn test_challenges_are_single_use() {
+        let store = ChallengeStore::new("Acme Treasury", Duration::minutes(5));
+        let challenge = store.issue();
+        assert!(challenge.message.starts_with("zkpoor ownership proof\noperator: Acme Treasury\nnonce: "));
+        assert_ne!(store.issue().message, challenge.message);
+
+        store.check(&challenge.message).unwrap();
+        // One challenge covers every UTXO of a request
+        store.redeem(&[proof(&challenge.message), proof(&challenge.message)]).unwrap();
+        assert!(store.check(&challenge.message).unwrap_err().contains("already been used"));
+        assert!(store.redeem(&[proof(&challenge.message)]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_expired_challenges() {
+        let store = ChallengeStore::new(DEFAULT_OPERATOR, Duration::zero());
+        let expired = store.issue();
+        assert!(store.check(&expired.message).unwrap_err().contains("expired"));
+        assert!(store.check("This is an example of a signed message.").unwrap_err().contains("not issued"));
+
+        // Nothing is redeemed when one of the proofs is rejected
+        let store = ChallengeStore::default();
+        let valid = store.issue();
+        let Err(Error::InvalidUtxos(problems)) = store.redeem(&[proof(&valid.message), proof("old attestation")]) else {
+            panic!("expected InvalidUtxos");
+        };
+        assert_eq!(problems.iter().map(Error::index).collect::<Vec<_>>(), [Some(1)]);
+        store.check(&valid.message).unwrap();
+    }
+}
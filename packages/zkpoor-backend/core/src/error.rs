@@ -0,0 +1,111 @@
+use uuid::Uuid;
+
+use crate::queue::QueueFull;
+use crate::types::ProofStatus;
+
+/// Errors `zkpoor-core` reports to its callers. Anything unexpected is wrapped
+/// in `Internal`; the other variants describe a problem with the request.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Invalid UTXO at index {index}: {reason}")]
+    InvalidUtxo { index: usize, reason: String },
+
+    #[error("Duplicate outpoint {outpoint} at index {index}, already listed at index {first_index}")]
+    DuplicateOutpoint { index: usize, outpoint: String, first_index: usize },
+
+    #[error("Outpoint {outpoint} at index {index} conflicts with index {first_index}: {reason}")]
+    ConflictingOutpoint { index: usize, outpoint: String, first_index: usize, reason: String },
+
+    /// Every problem found with a request's UTXOs, each carrying its index
+    #[error("{} problem(s) with the submitted UTXOs", .0.len())]
+    InvalidUtxos(Vec<Error>),
+
+    #[error("Invalid ownership proof at index {index}: {reason}")]
+    BadSignature { index: usize, reason: String },
+
+    /// The ownership proof does not sign a challenge issued by this server
+    #[error("Invalid challenge at index {index}: {reason}")]
+    BadChallenge { index: usize, reason: String },
+
+    #[error("Malformed proof: {0}")]
+    ProofMalformed(String),
+
+    #[error("Invalid proof: {0}")]
+    ProofInvalid(String),
+
+    #[error("No proof found with ID: {0}")]
+    ProofNotFound(Uuid),
+
+    #[error("Proof artifact not found: {0}")]
+    ArtifactNotFound(String),
+
+    #[error("No published proof named '{0}'")]
+    PublishedProofNotFound(String),
+
+    #[error("Proof {proof_id} is {status:?} and can no longer be changed")]
+    ProofFinished { proof_id: Uuid, status: ProofStatus },
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("No API key with ID: {0}")]
+    KeyNotFound(Uuid),
+
+    #[error("Request body exceeds the {limit} byte limit")]
+    PayloadTooLarge { limit: usize },
+
+    #[error(transparent)]
+    QueueFull(#[from] QueueFull),
+
+    #[error("{backend} backend unavailable: {reason}")]
+    BackendUnavailable { backend: String, reason: String },
+
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl Error {
+    /// Stable machine-readable code, safe for clients to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::InvalidRequest(_) => "invalid_request",
+            Error::InvalidUtxo { .. } => "invalid_utxo",
+            Error::DuplicateOutpoint { .. } => "duplicate_outpoint",
+            Error::ConflictingOutpoint { .. } => "conflicting_outpoint",
+            Error::InvalidUtxos(_) => "invalid_utxos",
+            Error::BadSignature { .. } => "bad_signature",
+            Error::BadChallenge { .. } => "bad_challenge",
+            Error::ProofMalformed(_) => "proof_malformed",
+            Error::ProofInvalid(_) => "proof_invalid",
+            Error::ProofNotFound(_) => "proof_not_found",
+            Error::ArtifactNotFound(_) => "artifact_not_found",
+            Error::PublishedProofNotFound(_) => "published_proof_not_found",
+            Error::ProofFinished { .. } => "proof_finished",
+            Error::Unauthorized(_) => "unauthorized",
+            Error::Forbidden(_) => "forbidden",
+            Error::KeyNotFound(_) => "key_not_found",
+            Error::PayloadTooLarge { .. } => "payload_too_large",
+            Error::QueueFull(_) => "queue_full",
+            Error::BackendUnavailable { .. } => "backend_unavailable",
+            Error::Internal(_) => "internal",
+        }
+    }
+
+    /// Position in the request of the UTXO or ownership proof at fault
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            Error::InvalidUtxo { index, .. }
+            | Error::DuplicateOutpoint { index, .. }
+            | Error::ConflictingOutpoint { index, .. }
+            | Error::BadSignature { index, .. }
+            | Error::BadChallenge { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+}
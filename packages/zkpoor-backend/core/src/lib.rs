@@ -1,2 +1,23 @@
+pub mod artifacts;
+pub mod auth;
+pub mod bip322;
+pub mod bitcoin;
+pub mod challenge;
+pub mod error;
+pub mod output;
+pub mod ownership;
+mod process;
+pub mod program;
 pub mod proof;
+pub mod prover;
+pub mod published;
+pub mod queue;
+pub mod script;
+pub mod stark;
+pub mod store;
+#[cfg(feature = "stwo")]
+pub mod stwo;
+pub mod toolchain;
 pub mod types;
+pub mod validation;
+pub use error::Error;
@@ -0,0 +1,211 @@
+use serde::Serialize;
+use anyhow::{Result, anyhow};
+
+use crate::program::{Felt, U256};
+
+/// Felts in the bootloader output: n_tasks, task_output_size, task_program_hash
+/// followed by the 6-felt `Result`
+const BOOTLOADER_OUTPUT_LEN: usize = 9;
+
+/// Felts in the `Result` itself, the whole output of a run without the bootloader
+const RESULT_LEN: usize = 6;
+
+/// Expected `task_output_size`: program hash, 6 felts of `Result`, and the size itself
+const TASK_OUTPUT_SIZE: u64 = 8;
+
+/// What a zkpoor proof actually attests to: the `Result` returned by the Cairo
+/// program's `main`, usually as wrapped by the simple bootloader
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PublicOutput {
+    /// Hash of the zkpoor program that was run by the bootloader; `None` when
+    /// the program ran without it (the in-process stwo prover)
+    pub task_program_hash: Option<Felt>,
+    /// Hash of the chain state after the blocks have been applied
+    pub chain_state_hash: U256,
+    /// Hash of the roots of the Merkle Mountain Range of the block hashes
+    pub block_mmr_hash: U256,
+    /// Hash of the bootloader that ran the recursively verified proof
+    pub bootloader_hash: Felt,
+    /// Hash of the recursively verified program
+    pub program_hash: Felt,
+}
+
+impl PublicOutput {
+    /// Decode the proof's output segment: the `BootloaderOutput` layout, or
+    /// the bare `Result` of a run without the bootloader
+    pub fn decode(output: &[Felt]) -> Result<Self> {
+        if output.len() == RESULT_LEN {
+            return Self::decode_result(None, output);
+        }
+        if output.len() != BOOTLOADER_OUTPUT_LEN {
+            return Err(anyhow!(
+                "Unexpected output length: expected {} or {} felts, got {}",
+                BOOTLOADER_OUTPUT_LEN,
+                RESULT_LEN,
+                output.len()
+            ));
+        }
+
+        let n_tasks = felt_to_u128(&output[0])?;
+        if n_tasks != 1 {
+            return Err(anyhow!("Unexpected number of tasks: {}", n_tasks));
+        }
+        let task_output_size = felt_to_u128(&output[1])?;
+        if task_output_size != TASK_OUTPUT_SIZE as u128 {
+            return Err(anyhow!("Unexpected task output size: {}", task_output_size));
+        }
+
+        Self::decode_result(Some(output[2]), &output[3..])
+    }
+
+    fn decode_result(task_program_hash: Option<Felt>, result: &[Felt]) -> Result<Self> {
+        Ok(PublicOutput {
+            task_program_hash,
+            chain_state_hash: u256_from_felts(&result[0], &result[1])?,
+            block_mmr_hash: u256_from_felts(&result[2], &result[3])?,
+            bootloader_hash: result[4],
+            program_hash: result[5],
+        })
+    }
+}
+
+/// Program hashes a proof's output must carry to be accepted as a zkpoor
+/// proof. A STARK proof only shows that *some* program ran; these pin which
+/// one. Hashes left unset are not checked.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExpectedPrograms {
+    /// Hash of the zkpoor program, as reported by the bootloader
+    pub task_program_hash: Option<Felt>,
+    /// Hash of the bootloader that ran the recursively verified proof
+    pub bootloader_hash: Option<Felt>,
+    /// Hash of the recursively verified program
+    pub program_hash: Option<Felt>,
+}
+
+impl ExpectedPrograms {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl PublicOutput {
+    /// Why the output was not produced by the `expected` programs, if it was not
+    pub fn check(&self, expected: &ExpectedPrograms) -> Result<(), String> {
+        let mut mismatches = Vec::new();
+        if let Some(hash) = expected.task_program_hash {
+            match self.task_program_hash {
+                Some(actual) if actual == hash => {}
+                Some(actual) => mismatches.push(format!("task_program_hash is {}, expected {}", actual, hash)),
+                None => mismatches.push(format!("task_program_hash is missing, expected {}", hash)),
+            }
+        }
+        if let Some(hash) = expected.bootloader_hash.filter(|hash| *hash != self.bootloader_hash) {
+            mismatches.push(format!("bootloader_hash is {}, expected {}", self.bootloader_hash, hash));
+        }
+        if let Some(hash) = expected.program_hash.filter(|hash| *hash != self.program_hash) {
+            mismatches.push(format!("program_hash is {}, expected {}", self.program_hash, hash));
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Proof is not of the expected program: {}", mismatches.join(", ")))
+        }
+    }
+}
+
+fn felt_to_u128(felt: &Felt) -> Result<u128> {
+    let bytes = felt.to_be_bytes();
+    if bytes[..16].iter().any(|b| *b != 0) {
+        return Err(anyhow!("Felt {} does not fit in u128", felt));
+    }
+    let mut low = [0u8; 16];
+    low.copy_from_slice(&bytes[16..]);
+    Ok(u128::from_be_bytes(low))
+}
+
+fn u256_from_felts(low: &Felt, high: &Felt) -> Result<U256> {
+    Ok(U256 {
+        low: felt_to_u128(low)?,
+        high: felt_to_u128(high)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output() -> Vec<Felt> {
+        vec![
+            Felt::from(1u32),
+            Felt::from(8u32),
+            Felt::from(0xaaaau32),
+            Felt::from(0x11u32),
+            Felt::from(0x22u32),
+            Felt::from(0x33u32),
+            Felt::from(0u32),
+            Felt::from(0xbbbbu32),
+            Felt::from(0xccccu32),
+        ]
+    }
+
+    #[test]
+    fn test_decode_public_output() {
+        let decoded = PublicOutput::decode(&output()).unwrap();
+        assert_eq!(decoded.task_program_hash, Some(Felt::from(0xaaaau32)));
+        assert_eq!(decoded.chain_state_hash, U256 { low: 0x11, high: 0x22 });
+        assert_eq!(decoded.block_mmr_hash, U256 { low: 0x33, high: 0 });
+        assert_eq!(decoded.bootloader_hash, Felt::from(0xbbbbu32));
+        assert_eq!(decoded.program_hash, Felt::from(0xccccu32));
+
+        let json = serde_json::to_value(&decoded).unwrap();
+        assert_eq!(
+            json["chain_state_hash"],
+            "0x0000000000000000000000000000002200000000000000000000000000000011"
+        );
+
+        // Without the bootloader the output is just the `Result`
+        let bare = PublicOutput::decode(&output()[3..]).unwrap();
+        assert_eq!(bare, PublicOutput { task_program_hash: None, ..decoded });
+    }
+
+    #[test]
+    fn test_check_expected_programs() {
+        let decoded = PublicOutput::decode(&output()).unwrap();
+        decoded.check(&ExpectedPrograms::default()).unwrap();
+        let expected = ExpectedPrograms {
+            task_program_hash: Some(Felt::from(0xaaaau32)),
+            bootloader_hash: Some(Felt::from(0xbbbbu32)),
+            program_hash: Some(Felt::from(0xccccu32)),
+        };
+        decoded.check(&expected).unwrap();
+
+        let wrong = ExpectedPrograms { program_hash: Some(Felt::from(0xddddu32)), ..expected.clone() };
+        assert_eq!(
+            decoded.check(&wrong).unwrap_err(),
+            "Proof is not of the expected program: program_hash is 0xcccc, expected 0xdddd"
+        );
+
+        // A run without the bootloader has no task program hash to match
+        let bare = PublicOutput::decode(&output()[3..]).unwrap();
+        assert!(bare.check(&expected).unwrap_err().contains("task_program_hash is missing"));
+        bare.check(&ExpectedPrograms { task_program_hash: None, ..expected }).unwrap();
+    }
+
+    #[test]
+    fn test_decode_rejects_unexpected_layout() {
+        let mut two_tasks = output();
+        two_tasks[0] = Felt::from(2u32);
+        assert!(PublicOutput::decode(&two_tasks).is_err());
+
+        let mut bad_size = output();
+        bad_size[1] = Felt::from(7u32);
+        assert!(PublicOutput::decode(&bad_size).is_err());
+
+        assert!(PublicOutput::decode(&output()[..8]).is_err());
+
+        let mut oversized = output();
+        oversized[3] = Felt::from_hex("0x100000000000000000000000000000000").unwrap();
+        assert!(PublicOutput::decode(&oversized).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::{Result, anyhow};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ripemd::Ripemd160;
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, Secp256k1, VerifyOnly};
+use sha2::{Digest, Sha256};
+
+use crate::bip322;
+use crate::bitcoin::ScriptPubKey;
+use crate::script::ScriptTemplate;
+use crate::types::{OwnershipProof, SignatureFormat, Utxo};
+
+/// Prefix Bitcoin Core's `signmessage` commits to before the message
+const MESSAGE_MAGIC: &str = "Bitcoin Signed Message:\n";
+
+/// Checks that the submitter of a UTXO controls it
+pub trait OwnershipVerifier: Send + Sync {
+    /// `Err` explains why `proof` does not show control of `utxo`
+    fn verify(&self, utxo: &Utxo, proof: &OwnershipProof) -> Result<()>;
+}
+
+/// Accepts any ownership proof. Only meant for tests and local development.
+#[derive(Debug, Default, Clone)]
+pub struct MockOwnershipVerifier;
+
+impl OwnershipVerifier for MockOwnershipVerifier {
+    fn verify(&self, _utxo: &Utxo, _proof: &OwnershipProof) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Checks each proof's `signature` over its `challenge` against the paired
+/// UTXO's script, in the proof's format: Bitcoin Core `signmessage`
+/// signatures, including the BIP 137 headers, for P2PKH and P2WPKH outputs,
+/// or BIP-322 signatures, see [`bip322`].
+pub struct SignatureVerifier {
+    secp: Secp256k1<VerifyOnly>,
+}
+
+impl SignatureVerifier {
+    pub fn new() -> Self {
+        Self { secp: Secp256k1::verification_only() }
+    }
+
+    /// Check a base64 `signmessage` signature of `message` by the key `script` pays to
+    pub fn verify_message(&self, script: &ScriptPubKey, message: &str, signature: &str) -> Result<()> {
+        let template = script.template();
+        if !matches!(template, ScriptTemplate::P2pkh { .. } | ScriptTemplate::P2wpkh { .. }) {
+            return Err(anyhow!("signmessage signatures cannot prove ownership of {} outputs; use a BIP-322 signature", template));
+        }
+
+        let bytes = BASE64.decode(signature.trim()).map_err(|e| anyhow!("signature is not base64: {}", e))?;
+        if bytes.len() != 65 {
+            return Err(anyhow!("expected a 65-byte compact signature, got {} bytes", bytes.len()));
+        }
+
+        // 27-30: uncompressed key, 31-34: compressed key, and from BIP 137
+        // 35-38: P2SH-P2WPKH, 39-42: P2WPKH; plus the recovery ID
+        let header = bytes[0];
+        if !(27..=42).contains(&header) {
+            return Err(anyhow!("invalid signature header byte {}", header));
+        }
+        let compressed = header >= 31;
+        let recovery_id = RecoveryId::from_i32(((header - 27) % 4) as i32)?;
+        let signature = RecoverableSignature::from_compact(&bytes[1..], recovery_id)
+            .map_err(|e| anyhow!("malformed signature: {}", e))?;
+        let pubkey = self.secp
+            .recover_ecdsa(&Message::from_digest(signed_message_hash(message)), &signature)
+            .map_err(|_| anyhow!("signature does not recover a public key"))?;
+        let key_hash = if compressed {
+            hash160(&pubkey.serialize())
+        } else {
+            hash160(&pubkey.serialize_uncompressed())
+        };
+
+        let expected = match template {
+            ScriptTemplate::P2pkh { pubkey_hash } if header <= 34 => pubkey_hash,
+            ScriptTemplate::P2wpkh { pubkey_hash } if compressed && !(35..=38).contains(&header) => pubkey_hash,
+            template => return Err(anyhow!("signature header {} does not match a {} output", header, template)),
+        };
+        if key_hash != expected {
+            return Err(anyhow!("signature is not by the key of this output"));
+        }
+        Ok(())
+    }
+}
+
+impl Default for SignatureVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OwnershipVerifier for SignatureVerifier {
+    fn verify(&self, utxo: &Utxo, proof: &OwnershipProof) -> Result<()> {
+        let script = &utxo.script_pubkey;
+        match proof.format {
+            SignatureFormat::Legacy => self.verify_message(script, &proof.challenge, &proof.signature),
+            SignatureFormat::Bip322Simple => bip322::verify_simple(&self.secp, script, &proof.challenge, &proof.signature),
+            SignatureFormat::Bip322Full => bip322::verify_full(&self.secp, script, &proof.challenge, &proof.signature),
+        }
+    }
+}
+
+/// Digest `signmessage` signs: double SHA-256 of the magic prefix and the
+/// message, each prefixed with its length
+pub fn signed_message_hash(message: &str) -> [u8; 32] {
+    let mut data = Vec::with_capacity(MESSAGE_MAGIC.len() + message.len() + 10);
+    for part in [MESSAGE_MAGIC, message] {
+        write_compact_size(&mut data, part.len() as u64);
+        data.extend_from_slice(part.as_bytes());
+    }
+    Sha256::digest(Sha256::digest(&data)).into()
+}
+
+/// Bitcoin's variable-length integer encoding
+pub(crate) fn write_compact_size(out: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => out.push(n as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+/// RIPEMD-160 of SHA-256, as committed to by P2PKH and P2WPKH scripts
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::{PublicKey, SecretKey};
+
+    const CHALLENGE: &str = "zkpoor proof of reserves 2024-06-01";
+
+    /// `signmessage` signature with the given header base (27, 31, 35 or 39)
+    fn sign(secret: &SecretKey, message: &str, base: u8) -> String {
+        let secp = Secp256k1::new();
+        let digest = Message::from_digest(signed_message_hash(message));
+        let (recovery_id, compact) = secp.sign_ecdsa_recoverable(&digest, secret).serialize_compact();
+        let mut bytes = vec![base + recovery_id.to_i32() as u8];
+        bytes.extend_from_slice(&compact);
+        BASE64.encode(bytes)
+    }
+
+    fn key(byte: u8) -> (SecretKey, PublicKey) {
+        let secret = SecretKey::from_slice(&[byte; 32]).unwrap();
+        (secret, PublicKey::from_secret_key(&Secp256k1::new(), &secret))
+    }
+
+    fn p2pkh(hash: [u8; 20]) -> ScriptPubKey {
+        format!("76a914{}88ac", hex::encode(hash)).parse().unwrap()
+    }
+
+    fn p2wpkh(hash: [u8; 20]) -> ScriptPubKey {
+        format!("0014{}", hex::encode(hash)).parse().unwrap()
+    }
+
+    #[test]
+    fn test_known_signmessage_signature() {
+        // bitcoinjs-message example, key 5KYZdUEo39z3FPrtuX2QbbwGnNP5zTd7yyr2SC1j299sBCnWjss (uncompressed)
+        let address = bs58::decode("1HZwkjkeaoZfTSaJxDw6aKkxp45agDiEzN").with_check(None).into_vec().unwrap();
+        let script = p2pkh(address[1..].try_into().unwrap());
+        let signature = "G9L5yLFjti0QTHhPyFrZCT1V/MMnBtXKmoiKDZ78NDBjERki6ZTQZdSMCtkgoNmp17By9ItJr8o7ChX0XxY91nk=";
+        let verifier = SignatureVerifier::new();
+        verifier.verify_message(&script, "This is an example of a signed message.", signature).unwrap();
+        assert!(verifier.verify_message(&script, "This is an example of a signed message!", signature).is_err());
+    }
+
+    #[test]
+    fn test_p2pkh_compressed_and_uncompressed() {
+        let verifier = SignatureVerifier::new();
+        let (secret, public) = key(7);
+
+        let compressed = p2pkh(hash160(&public.serialize()));
+        verifier.verify_message(&compressed, CHALLENGE, &sign(&secret, CHALLENGE, 31)).unwrap();
+        let uncompressed = p2pkh(hash160(&public.serialize_uncompressed()));
+        verifier.verify_message(&uncompressed, CHALLENGE, &sign(&secret, CHALLENGE, 27)).unwrap();
+
+        // The header decides which serialization of the key is hashed
+        assert!(verifier.verify_message(&compressed, CHALLENGE, &sign(&secret, CHALLENGE, 27)).is_err());
+        let error = verifier.verify_message(&compressed, "another challenge", &sign(&secret, CHALLENGE, 31)).unwrap_err();
+        assert_eq!(error.to_string(), "signature is not by the key of this output");
+    }
+
+    #[test]
+    fn test_p2wpkh_requires_compressed_key() {
+        let verifier = SignatureVerifier::new();
+        let (secret, public) = key(9);
+        let script = p2wpkh(hash160(&public.serialize()));
+
+        verifier.verify_message(&script, CHALLENGE, &sign(&secret, CHALLENGE, 31)).unwrap();
+        verifier.verify_message(&script, CHALLENGE, &sign(&secret, CHALLENGE, 39)).unwrap();
+        assert!(verifier.verify_message(&script, CHALLENGE, &sign(&secret, CHALLENGE, 27)).is_err());
+        assert!(verifier.verify_message(&script, CHALLENGE, &sign(&secret, CHALLENGE, 35)).is_err());
+    }
+
+    #[test]
+    fn test_rejects_other_keys_and_garbage() {
+        let verifier = SignatureVerifier::new();
+        let (_, public) = key(7);
+        let (other, _) = key(8);
+        let script = p2pkh(hash160(&public.serialize()));
+
+        assert!(verifier.verify_message(&script, CHALLENGE, &sign(&other, CHALLENGE, 31)).is_err());
+        assert!(verifier.verify_message(&script, CHALLENGE, "mock_signature").is_err());
+        assert!(verifier.verify_message(&script, CHALLENGE, &BASE64.encode([31u8; 64])).is_err());
+        assert!(verifier.verify_message(&script, CHALLENGE, &BASE64.encode([0u8; 65])).is_err());
+
+        let p2tr: ScriptPubKey = "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".parse().unwrap();
+        let error = verifier.verify_message(&p2tr, CHALLENGE, &sign(&other, CHALLENGE, 31)).unwrap_err();
+        assert!(error.to_string().contains("p2tr"));
+    }
+
+    #[test]
+    fn test_compact_size() {
+        let mut out = Vec::new();
+        write_compact_size(&mut out, 0xfc);
+        write_compact_size(&mut out, 0xfd);
+        write_compact_size(&mut out, 0x1_0000);
+        assert_eq!(out, [0xfc, 0xfd, 0xfd, 0x00, 0xfe, 0x00, 0x00, 0x01, 0x00]);
+    }
+}
@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+use tokio::process::{Child, Command};
+use uuid::Uuid;
+
+/// Whether `program` can be spawned: a path is checked as is, a bare name is
+/// looked up in `PATH` the way `Command` does
+pub(crate) fn find_program(program: &Path) -> bool {
+    if program.components().count() > 1 {
+        return program.is_file();
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// Spawn `command` as the leader of a new process group.
+///
+/// `kill_on_drop` only reaches the direct child, which for us is often a wrapper
+/// (`/usr/bin/time`, a prover script). Dropping the returned guard kills the
+/// whole group, so a timed out or cancelled job cannot leave a prover running.
+pub(crate) fn spawn_group(command: &mut Command) -> std::io::Result<(Child, GroupGuard)> {
+    #[cfg(unix)]
+    command.process_group(0);
+    let child = command.kill_on_drop(true).spawn()?;
+    let guard = GroupGuard { pgid: child.id() };
+    Ok((child, guard))
+}
+
+/// A job's own directory under a prover's `work_dir`. Dropping it removes the
+/// directory and everything in it, so intermediate files do not outlive the
+/// job whether it succeeded, failed or was cancelled.
+pub(crate) struct JobDir {
+    path: PathBuf,
+}
+
+impl JobDir {
+    pub(crate) async fn create(work_dir: &Path) -> std::io::Result<Self> {
+        let path = work_dir.join(Uuid::new_v4().to_string());
+        tokio::fs::create_dir_all(&path).await?;
+        Ok(Self { path })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for JobDir {
+    fn drop(&mut self) {
+        // Drop cannot await; unlinking is quick even for large traces
+        if let Err(e) = std::fs::remove_dir_all(&self.path) {
+            tracing::warn!("Failed to remove job directory {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Kills a process group when dropped, unless disarmed
+pub(crate) struct GroupGuard {
+    pgid: Option<u32>,
+}
+
+impl GroupGuard {
+    /// Call once the leader has exited normally; its pid may be reused afterwards
+    pub(crate) fn disarm(mut self) {
+        self.pgid = None;
+    }
+}
+
+impl Drop for GroupGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid.and_then(|pgid| i32::try_from(pgid).ok()) {
+            // SAFETY: killpg has no memory-safety preconditions; ESRCH is expected
+            // if the group already exited
+            unsafe {
+                libc::killpg(pgid, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn is_running(pid: &str) -> bool {
+        // Killed children of the shell are reparented and reaped by init, but may
+        // linger as zombies for a moment
+        match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => !stat.contains(") Z"),
+            Err(_) => false,
+        }
+    }
+
+    #[test]
+    fn test_find_program() {
+        assert!(find_program(Path::new("sh")));
+        assert!(find_program(Path::new("/bin/sh")));
+        assert!(!find_program(Path::new("zkpoor-no-such-program")));
+        assert!(!find_program(Path::new("/nonexistent/sh")));
+    }
+
+    #[tokio::test]
+    async fn test_drop_kills_grandchildren() {
+        let pid_file = std::env::temp_dir().join(format!("zkpoor-group-{}", uuid::Uuid::new_v4()));
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("sleep 30 & echo $! > {}; wait", pid_file.display()));
+        let (child, guard) = spawn_group(&mut command).unwrap();
+
+        let mut pid = String::new();
+        for _ in 0..100 {
+            pid = std::fs::read_to_string(&pid_file).unwrap_or_default().trim().to_string();
+            if !pid.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(is_running(&pid));
+
+        drop(guard);
+        drop(child);
+        for _ in 0..100 {
+            if !is_running(&pid) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!is_running(&pid));
+        std::fs::remove_file(&pid_file).unwrap();
+    }
+}
@@ -0,0 +1,706 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use anyhow::{Result, anyhow};
+
+use crate::types;
+
+// Rust mirror of the arguments accepted by the zkpoor Cairo executable
+// (`program/src/lib.cairo`) and the Raito consensus types it embeds.
+// The serde (JSON) representation matches what `program/scripts/generate_data.py`
+// writes to `batch.json`; `CairoSerialize` produces the felt252 array that
+// `format_args.py` would.
+
+/// Field element modulus P = 2^251 + 17 * 2^192 + 1, big-endian
+const FELT_MODULUS: [u8; 32] = [
+    0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// A felt252 value, stored big-endian
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Felt([u8; 32]);
+
+impl Felt {
+    pub const ZERO: Felt = Felt([0; 32]);
+
+    /// Build a felt from at most 32 big-endian bytes; the value must be below P
+    pub fn from_be_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() > 32 {
+            return Err(anyhow!("Felt cannot hold {} bytes", bytes.len()));
+        }
+        let mut buf = [0u8; 32];
+        buf[32 - bytes.len()..].copy_from_slice(bytes);
+        if buf >= FELT_MODULUS {
+            return Err(anyhow!("Value exceeds felt252 modulus"));
+        }
+        Ok(Felt(buf))
+    }
+
+    /// Parse a `0x`-prefixed hex string
+    pub fn from_hex(s: &str) -> Result<Self> {
+        let digits = s
+            .strip_prefix("0x")
+            .ok_or_else(|| anyhow!("Felt must be 0x-prefixed: {}", s))?;
+        let padded = if digits.len() % 2 == 1 { format!("0{}", digits) } else { digits.to_string() };
+        let bytes = hex::decode(&padded).map_err(|e| anyhow!("Invalid felt {}: {}", s, e))?;
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        Self::from_be_bytes(&bytes[start..])
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Minimal lowercase hex, e.g. `0x0` or `0x1f` (same as Python's `hex()`)
+    pub fn to_hex(&self) -> String {
+        let digits = hex::encode(self.0);
+        let trimmed = digits.trim_start_matches('0');
+        if trimmed.is_empty() { "0x0".to_string() } else { format!("0x{}", trimmed) }
+    }
+}
+
+impl From<u128> for Felt {
+    fn from(value: u128) -> Self {
+        let mut buf = [0u8; 32];
+        buf[16..].copy_from_slice(&value.to_be_bytes());
+        Felt(buf)
+    }
+}
+
+impl From<u64> for Felt {
+    fn from(value: u64) -> Self {
+        Felt::from(value as u128)
+    }
+}
+
+impl From<u32> for Felt {
+    fn from(value: u32) -> Self {
+        Felt::from(value as u128)
+    }
+}
+
+impl From<bool> for Felt {
+    fn from(value: bool) -> Self {
+        Felt::from(value as u128)
+    }
+}
+
+impl fmt::Debug for Felt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl fmt::Display for Felt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Serialize for Felt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Felt {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Felt::from_hex(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Cairo `Serde` encoding into a flat felt252 array
+pub trait CairoSerialize {
+    fn serialize_to(&self, out: &mut Vec<Felt>);
+
+    fn to_felts(&self) -> Vec<Felt> {
+        let mut out = Vec::new();
+        self.serialize_to(&mut out);
+        out
+    }
+}
+
+impl CairoSerialize for u32 {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        out.push(Felt::from(*self));
+    }
+}
+
+impl CairoSerialize for u64 {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        out.push(Felt::from(*self));
+    }
+}
+
+impl CairoSerialize for bool {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        out.push(Felt::from(*self));
+    }
+}
+
+/// `Array<T>` / `Span<T>`: length followed by the elements
+impl<T: CairoSerialize> CairoSerialize for Vec<T> {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        out.push(Felt::from(self.len() as u64));
+        for item in self {
+            item.serialize_to(out);
+        }
+    }
+}
+
+/// SHA-256 digest as eight u32 words, in Cairo's (internal byte order) layout
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct Digest(pub [u32; 8]);
+
+impl Digest {
+    /// Parse a hash in the usual RPC display order (e.g. a txid), which is
+    /// reversed relative to the bytes Cairo hashes over
+    pub fn from_hex(s: &str) -> Result<Self> {
+        let mut bytes = hex::decode(s).map_err(|e| anyhow!("Invalid hash {}: {}", s, e))?;
+        if bytes.len() != 32 {
+            return Err(anyhow!("Expected 32-byte hash, got {} bytes: {}", bytes.len(), s));
+        }
+        bytes.reverse();
+        let mut words = [0u32; 8];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        Ok(Digest(words))
+    }
+
+    pub fn to_hex(&self) -> String {
+        let mut bytes: Vec<u8> = self.0.iter().flat_map(|w| w.to_be_bytes()).collect();
+        bytes.reverse();
+        hex::encode(bytes)
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Serialize for Digest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Digest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Digest::from_hex(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl CairoSerialize for Digest {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        for word in self.0 {
+            out.push(Felt::from(word));
+        }
+    }
+}
+
+/// Cairo `u256`, serialized as `{ low: u128, high: u128 }`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct U256 {
+    pub low: u128,
+    pub high: u128,
+}
+
+impl U256 {
+    /// Parse a decimal string (how `batch.json` carries `total_work` and `current_target`)
+    pub fn from_dec_str(s: &str) -> Result<Self> {
+        if s.is_empty() {
+            return Err(anyhow!("Empty u256"));
+        }
+        // Little-endian 64-bit limbs
+        let mut limbs = [0u64; 4];
+        for c in s.chars() {
+            let digit = c.to_digit(10).ok_or_else(|| anyhow!("Invalid u256 digit in {}", s))?;
+            let mut carry = digit as u128;
+            for limb in limbs.iter_mut() {
+                let v = (*limb as u128) * 10 + carry;
+                *limb = v as u64;
+                carry = v >> 64;
+            }
+            if carry != 0 {
+                return Err(anyhow!("u256 overflow: {}", s));
+            }
+        }
+        Ok(U256 {
+            low: (limbs[0] as u128) | ((limbs[1] as u128) << 64),
+            high: (limbs[2] as u128) | ((limbs[3] as u128) << 64),
+        })
+    }
+
+    /// Parse a `0x`-prefixed hex string of up to 64 digits, as `Serialize` writes
+    pub fn from_hex(s: &str) -> Result<Self> {
+        let digits = s
+            .strip_prefix("0x")
+            .ok_or_else(|| anyhow!("u256 hex must be 0x-prefixed: {}", s))?;
+        if digits.is_empty() || digits.len() > 64 {
+            return Err(anyhow!("u256 hex must have 1 to 64 digits: {}", s));
+        }
+        let padded = format!("{:0>64}", digits);
+        let bytes = hex::decode(&padded).map_err(|e| anyhow!("Invalid u256 {}: {}", s, e))?;
+        Ok(U256::from_be_bytes(bytes.try_into().expect("64 hex digits are 32 bytes")))
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        let mut high = [0u8; 16];
+        let mut low = [0u8; 16];
+        high.copy_from_slice(&bytes[..16]);
+        low.copy_from_slice(&bytes[16..]);
+        U256 {
+            low: u128::from_be_bytes(low),
+            high: u128::from_be_bytes(high),
+        }
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes[..16].copy_from_slice(&self.high.to_be_bytes());
+        bytes[16..].copy_from_slice(&self.low.to_be_bytes());
+        bytes
+    }
+}
+
+/// Serialized as 0x-prefixed, zero-padded 64-digit hex
+impl Serialize for U256 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(self.to_be_bytes())))
+    }
+}
+
+/// Accepts the decimal strings of `batch.json` and the hex `Serialize` writes
+impl<'de> Deserialize<'de> for U256 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        if s.starts_with("0x") { U256::from_hex(&s) } else { U256::from_dec_str(&s) }
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl CairoSerialize for U256 {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        out.push(Felt::from(self.low));
+        out.push(Felt::from(self.high));
+    }
+}
+
+/// Cairo `ByteArray`: full 31-byte words, then the pending word and its length
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ByteArray(pub Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteArray {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let digits = s
+            .strip_prefix("0x")
+            .ok_or_else(|| serde::de::Error::custom(format!("ByteArray must be 0x-prefixed: {}", s)))?;
+        hex::decode(digits).map(ByteArray).map_err(serde::de::Error::custom)
+    }
+}
+
+impl CairoSerialize for ByteArray {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        let chunks = self.0.chunks_exact(31);
+        let pending = chunks.remainder();
+        out.push(Felt::from((self.0.len() / 31) as u64));
+        for chunk in chunks {
+            out.push(Felt::from_be_bytes(chunk).expect("31 bytes fit in a felt"));
+        }
+        out.push(Felt::from_be_bytes(pending).expect("31 bytes fit in a felt"));
+        out.push(Felt::from(pending.len() as u64));
+    }
+}
+
+/// Chain state after applying a block (Raito `ChainState`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChainState {
+    pub block_height: u32,
+    pub total_work: U256,
+    pub best_block_hash: Digest,
+    pub current_target: U256,
+    pub epoch_start_time: u32,
+    pub prev_timestamps: Vec<u32>,
+}
+
+impl CairoSerialize for ChainState {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        self.block_height.serialize_to(out);
+        self.total_work.serialize_to(out);
+        self.best_block_hash.serialize_to(out);
+        self.current_target.serialize_to(out);
+        self.epoch_start_time.serialize_to(out);
+        self.prev_timestamps.serialize_to(out);
+    }
+}
+
+/// Block header fields that are not derived from the chain state
+#[derive(Debug, Clone, Deserialize)]
+pub struct Header {
+    pub version: u32,
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: u32,
+}
+
+impl CairoSerialize for Header {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        self.version.serialize_to(out);
+        self.time.serialize_to(out);
+        self.bits.serialize_to(out);
+        self.nonce.serialize_to(out);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TxOut {
+    pub value: u64,
+    pub pk_script: ByteArray,
+    pub cached: bool,
+}
+
+impl CairoSerialize for TxOut {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        self.value.serialize_to(out);
+        self.pk_script.serialize_to(out);
+        self.cached.serialize_to(out);
+    }
+}
+
+/// Output being spent, with the context needed for validation
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutPoint {
+    pub txid: Digest,
+    pub vout: u32,
+    pub data: TxOut,
+    pub block_height: u32,
+    pub median_time_past: u32,
+    pub is_coinbase: bool,
+}
+
+impl CairoSerialize for OutPoint {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        self.txid.serialize_to(out);
+        self.vout.serialize_to(out);
+        self.data.serialize_to(out);
+        self.block_height.serialize_to(out);
+        self.median_time_past.serialize_to(out);
+        self.is_coinbase.serialize_to(out);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TxIn {
+    pub script: ByteArray,
+    pub sequence: u32,
+    pub previous_output: OutPoint,
+    pub witness: Vec<ByteArray>,
+}
+
+impl CairoSerialize for TxIn {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        self.script.serialize_to(out);
+        self.sequence.serialize_to(out);
+        self.previous_output.serialize_to(out);
+        self.witness.serialize_to(out);
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transaction {
+    pub version: u32,
+    pub is_segwit: bool,
+    pub inputs: Vec<TxIn>,
+    pub outputs: Vec<TxOut>,
+    pub lock_time: u32,
+}
+
+impl CairoSerialize for Transaction {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        self.version.serialize_to(out);
+        self.is_segwit.serialize_to(out);
+        self.inputs.serialize_to(out);
+        self.outputs.serialize_to(out);
+        self.lock_time.serialize_to(out);
+    }
+}
+
+/// Block body: either just the Merkle root or the full transaction list.
+/// The zkpoor program requires full transactions.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawTransactionData")]
+pub enum TransactionData {
+    MerkleRoot(Digest),
+    Transactions(Vec<Transaction>),
+}
+
+/// `batch.json` encodes the enum as `{ "variant_id": n, <payload field> }`
+#[derive(Deserialize)]
+struct RawTransactionData {
+    variant_id: u32,
+    merkle_root: Option<Digest>,
+    transactions: Option<Vec<Transaction>>,
+}
+
+impl TryFrom<RawTransactionData> for TransactionData {
+    type Error = String;
+
+    fn try_from(raw: RawTransactionData) -> std::result::Result<Self, Self::Error> {
+        match (raw.variant_id, raw.merkle_root, raw.transactions) {
+            (0, Some(root), _) => Ok(TransactionData::MerkleRoot(root)),
+            (1, _, Some(txs)) => Ok(TransactionData::Transactions(txs)),
+            (id, _, _) => Err(format!("Invalid transaction data variant {}", id)),
+        }
+    }
+}
+
+impl CairoSerialize for TransactionData {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        match self {
+            TransactionData::MerkleRoot(root) => {
+                out.push(Felt::from(0u32));
+                root.serialize_to(out);
+            }
+            TransactionData::Transactions(txs) => {
+                out.push(Felt::from(1u32));
+                txs.serialize_to(out);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Block {
+    pub header: Header,
+    pub data: TransactionData,
+}
+
+impl CairoSerialize for Block {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        self.header.serialize_to(out);
+        self.data.serialize_to(out);
+    }
+}
+
+/// UTXO that the program checks is created and left unspent
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Utxo {
+    pub txid: Digest,
+    pub vout: u32,
+}
+
+impl CairoSerialize for Utxo {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        self.txid.serialize_to(out);
+        self.vout.serialize_to(out);
+    }
+}
+
+impl TryFrom<&types::Utxo> for Utxo {
+    type Error = anyhow::Error;
+
+    fn try_from(utxo: &types::Utxo) -> Result<Self> {
+        Ok(Utxo {
+            txid: Digest::from_hex(&utxo.txid.to_hex())?,
+            vout: utxo.vout,
+        })
+    }
+}
+
+/// Chain state, blocks and chain state proof shared by every job proving
+/// against the same tip; only the target UTXO changes between jobs
+#[derive(Debug, Clone)]
+pub struct ChainData {
+    pub chain_state: ChainState,
+    pub blocks: Vec<Block>,
+    /// Previous chain state proof, already in Cairo serde form
+    pub chain_state_proof: Vec<Felt>,
+}
+
+/// The parts of `batch.json` that do not depend on the target UTXO
+#[derive(Deserialize)]
+struct Batch {
+    chain_state: ChainState,
+    blocks: Vec<Block>,
+}
+
+impl ChainData {
+    /// Load the `batch.json` written by `program/scripts/prove.py` (its
+    /// `target_utxo` is ignored) and the Cairo serde chain state proof
+    /// (`proof_serde.json`)
+    pub async fn load(batch: &Path, chain_state_proof: &Path) -> Result<Self> {
+        let raw = tokio::fs::read_to_string(batch)
+            .await
+            .map_err(|e| anyhow!("Failed to read {}: {}", batch.display(), e))?;
+        let Batch { chain_state, blocks } = serde_json::from_str(&raw)
+            .map_err(|e| anyhow!("Invalid chain data in {}: {}", batch.display(), e))?;
+        let raw = tokio::fs::read_to_string(chain_state_proof)
+            .await
+            .map_err(|e| anyhow!("Failed to read {}: {}", chain_state_proof.display(), e))?;
+        let chain_state_proof = parse_felts(&raw)?;
+        Ok(Self { chain_state, blocks, chain_state_proof })
+    }
+}
+
+/// Where provers load `ChainData` from for each job, so that the operator
+/// can refresh the files as the chain moves on without a restart
+#[derive(Debug, Clone)]
+pub struct ChainDataFiles {
+    /// `batch.json` from `program/scripts/prove.py`
+    pub batch: PathBuf,
+    /// Cairo serde chain state proof (`proof_serde.json`)
+    pub chain_state_proof: PathBuf,
+}
+
+impl ChainDataFiles {
+    pub fn new(batch: impl Into<PathBuf>, chain_state_proof: impl Into<PathBuf>) -> Self {
+        Self { batch: batch.into(), chain_state_proof: chain_state_proof.into() }
+    }
+
+    pub async fn load(&self) -> Result<ChainData> {
+        ChainData::load(&self.batch, &self.chain_state_proof).await
+    }
+}
+
+/// `Args` of the zkpoor executable's `main`
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub chain_state: ChainState,
+    pub blocks: Vec<Block>,
+    pub target_utxo: Utxo,
+    /// Previous chain state proof, already in Cairo serde form
+    /// (the output of `convert_proof_format --hash blake2s`)
+    pub chain_state_proof: Vec<Felt>,
+}
+
+impl CairoSerialize for Args {
+    fn serialize_to(&self, out: &mut Vec<Felt>) {
+        self.chain_state.serialize_to(out);
+        self.blocks.serialize_to(out);
+        self.target_utxo.serialize_to(out);
+        // The proof is a struct, so its fields are inlined without a length prefix
+        out.extend_from_slice(&self.chain_state_proof);
+    }
+}
+
+impl Args {
+    /// Arguments proving that `utxo` is unspent at the tip of `chain`
+    pub fn new(chain: &ChainData, utxo: &types::Utxo) -> Result<Self> {
+        Ok(Self {
+            chain_state: chain.chain_state.clone(),
+            blocks: chain.blocks.clone(),
+            target_utxo: Utxo::try_from(utxo)?,
+            chain_state_proof: chain.chain_state_proof.clone(),
+        })
+    }
+
+    /// Arguments file contents for `scarb execute` / `cairo_program_runner`
+    pub fn to_args_json(&self) -> String {
+        let hex: Vec<String> = self.to_felts().iter().map(Felt::to_hex).collect();
+        serde_json::to_string(&hex).expect("string array serializes")
+    }
+
+    pub async fn write_args_file(&self, path: &Path) -> Result<()> {
+        tokio::fs::write(path, self.to_args_json()).await?;
+        Ok(())
+    }
+}
+
+/// Load a Cairo serde proof file (a JSON array of hex felts)
+pub fn parse_felts(json: &str) -> Result<Vec<Felt>> {
+    serde_json::from_str(json).map_err(|e| anyhow!("Invalid felt array: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::Amount;
+
+    /// Same shape as `batch.json` written by `program/scripts/prove.py`
+    const BATCH: &str = r#"{"chain_state": {"block_height": 913139, "total_work": "66511388658477985281784852688", "best_block_hash": "00000000000000000000a8e6a3a2a0e0ac2a0a37e1b18c8d4a04bc2d4aa5e9f1", "current_target": "186638143592068295363365164192367490459121553535664128", "epoch_start_time": 1756000000, "prev_timestamps": [1, 2, 3]}, "blocks": [{"header": {"version": 536870912, "time": 1756900000, "bits": 386021236, "nonce": 12345}, "data": {"variant_id": 1, "transactions": [{"version": 2, "is_segwit": true, "inputs": [{"script": "0x0102", "sequence": 4294967295, "previous_output": {"txid": "0000000000000000000000000000000000000000000000000000000000000000", "vout": 4294967295, "data": {"value": 0, "pk_script": "0x", "cached": false}, "block_height": 0, "median_time_past": 0, "is_coinbase": false}, "witness": ["0x0000000000000000000000000000000000000000000000000000000000000000"]}], "outputs": [{"value": 312500000, "pk_script": "0x76a914044f70e664eb08b6efe9c8daebb85364a16dd59b88ac", "cached": false}], "lock_time": 0}]}}], "target_utxo": {"txid": "92902693c34c80da75da19f97bfb3719013883d8d307e88011a648363dd2f334", "vout": 1}}"#;
+
+    /// `format_args.serialize` output for `BATCH`
+    const EXPECTED: &str = r#"["0xdeef3", "0xd6e8ee63ed6f3cc6a92eb0d0", "0x0", "0xf1e9a54a", "0x2dbc044a", "0x8d8cb1e1", "0x370a2aac", "0xe0a0a2a3", "0xe6a80000", "0x0", "0x0", "0x0", "0x1f2d700000000", "0x68aa6f00", "0x3", "0x1", "0x2", "0x3", "0x1", "0x20000000", "0x68b82aa0", "0x17023774", "0x3039", "0x1", "0x1", "0x2", "0x1", "0x1", "0x0", "0x102", "0x2", "0xffffffff", "0x0", "0x0", "0x0", "0x0", "0x0", "0x0", "0x0", "0x0", "0xffffffff", "0x0", "0x0", "0x0", "0x0", "0x0", "0x0", "0x0", "0x0", "0x1", "0x1", "0x0", "0x0", "0x1", "0x1", "0x12a05f20", "0x0", "0x76a914044f70e664eb08b6efe9c8daebb85364a16dd59b88ac", "0x19", "0x0", "0x0", "0x34f3d23d", "0x3648a611", "0x80e807d3", "0xd8833801", "0x1937fb7b", "0xf919da75", "0xda804cc3", "0x93269092", "0x1"]"#;
+
+    fn target_utxo() -> types::Utxo {
+        types::Utxo {
+            txid: "92902693c34c80da75da19f97bfb3719013883d8d307e88011a648363dd2f334".parse().unwrap(),
+            vout: 1,
+            amount: Amount::from_sat(1000).unwrap(),
+            script_pubkey: "76a914044f70e664eb08b6efe9c8daebb85364a16dd59b88ac".parse().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_args_match_python_encoder() {
+        let dir = std::env::temp_dir().join(format!("zkpoor-program-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("batch.json"), BATCH).unwrap();
+        std::fs::write(dir.join("proof_serde.json"), r#"["0x1", "0x2"]"#).unwrap();
+
+        let chain = ChainData::load(&dir.join("batch.json"), &dir.join("proof_serde.json")).await.unwrap();
+        let args = Args::new(&chain, &target_utxo()).unwrap();
+        args.write_args_file(&dir.join("args.json")).await.unwrap();
+
+        let mut expected: Vec<String> = serde_json::from_str(EXPECTED).unwrap();
+        expected.extend(["0x1".to_string(), "0x2".to_string()]);
+        let actual: Vec<String> = serde_json::from_str(&std::fs::read_to_string(dir.join("args.json")).unwrap()).unwrap();
+        assert_eq!(actual, expected);
+
+        assert!(ChainData::load(&dir.join("missing.json"), &dir.join("proof_serde.json")).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_digest_round_trip() {
+        let txid = "92902693c34c80da75da19f97bfb3719013883d8d307e88011a648363dd2f334";
+        let digest = Digest::from_hex(txid).unwrap();
+        assert_eq!(digest.0[0], 0x34f3d23d);
+        assert_eq!(digest.to_hex(), txid);
+        assert!(Digest::from_hex("abcd").is_err());
+    }
+
+    #[test]
+    fn test_byte_array_chunks() {
+        let bytes: Vec<u8> = (1..=40).collect();
+        let felts = ByteArray(bytes.clone()).to_felts();
+        assert_eq!(felts.len(), 4);
+        assert_eq!(felts[0], Felt::from(1u32));
+        assert_eq!(felts[1], Felt::from_be_bytes(&bytes[..31]).unwrap());
+        assert_eq!(felts[2], Felt::from_be_bytes(&bytes[31..]).unwrap());
+        assert_eq!(felts[3], Felt::from(9u32));
+    }
+
+    #[test]
+    fn test_u256_and_felt_bounds() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(U256::from_dec_str(max).unwrap(), U256 { low: u128::MAX, high: u128::MAX });
+        assert!(U256::from_dec_str("115792089237316195423570985008687907853269984665640564039457584007913129639936").is_err());
+
+        let value = U256::from_dec_str("186638143592068295363365164192367490459121553535664128").unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, "\"0x00000000000000000001f2d70000000000000000000000000000000000000000\"");
+        assert_eq!(serde_json::from_str::<U256>(&json).unwrap(), value);
+        assert_eq!(serde_json::from_str::<U256>("\"0x1f\"").unwrap(), U256 { low: 31, high: 0 });
+        assert!(serde_json::from_str::<U256>(&format!("\"0x1{}\"", "0".repeat(64))).is_err());
+        assert!(serde_json::from_str::<U256>("\"0x\"").is_err());
+
+        assert!(Felt::from_hex("0x800000000000011000000000000000000000000000000000000000000000000").is_ok());
+        assert!(Felt::from_hex("0x800000000000011000000000000000000000000000000000000000000000001").is_err());
+        assert_eq!(Felt::from_hex("0x00ff").unwrap().to_hex(), "0xff");
+    }
+
+    #[test]
+    fn test_utxo_from_api_type() {
+        let cairo_utxo = Utxo::try_from(&target_utxo()).unwrap();
+        assert_eq!(cairo_utxo.vout, 1);
+        assert_eq!(cairo_utxo.to_felts().len(), 9);
+    }
+}
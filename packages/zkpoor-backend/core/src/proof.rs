@@ -1,32 +1,186 @@
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 use chrono::Utc;
 use anyhow::{Result, anyhow};
 
+use crate::artifacts::ArtifactStore;
+use crate::challenge::{Challenge, ChallengeStore};
+use crate::error::Error;
+use crate::output::{ExpectedPrograms, PublicOutput};
+use crate::ownership::{MockOwnershipVerifier, OwnershipVerifier, SignatureVerifier};
+use crate::prover::{hold_slot, MockProver, ProgressReporter, ProverBackend, ProverExit, ProvingRequest};
+use crate::queue::{JobQueue, Priority, QueueConfig};
+use crate::stark::{MockStarkVerifier, StarkVerdict, StarkVerifier};
+use crate::store::{MemoryProofStore, ProofStore};
+use crate::toolchain::ToolchainError;
 use crate::types::*;
+use crate::validation::{total_amount, validate_utxos};
 
-/// In-memory storage for proof data (in production, this would be a database)
-type ProofStorage = Arc<Mutex<HashMap<Uuid, ProofData>>>;
+/// Buffered record updates per subscriber before it starts lagging
+const EVENT_CAPACITY: usize = 256;
+
+/// A queued proving job
+struct ProvingJob {
+    proof_id: Uuid,
+    utxos: Vec<Utxo>,
+    cancel: CancellationToken,
+}
+
+/// State shared between the generator and its workers
+#[derive(Clone)]
+struct JobContext {
+    store: Arc<dyn ProofStore>,
+    artifacts: ArtifactStore,
+    prover: Arc<dyn ProverBackend>,
+    /// Cancellation handles of queued and running jobs
+    jobs: Arc<Mutex<HashMap<Uuid, CancellationToken>>>,
+    /// Serializes record updates between workers and `cancel_proof`
+    records: Arc<tokio::sync::Mutex<()>>,
+    /// Checks each new proof before it is published
+    self_verifier: Option<ProofVerifier>,
+    /// Every saved change to a proof record
+    events: broadcast::Sender<ProofData>,
+}
 
 pub struct ProofGenerator {
-    storage: ProofStorage,
+    ctx: JobContext,
+    /// Checks ownership proofs before a job is queued
+    ownership: Arc<dyn OwnershipVerifier>,
+    /// Messages ownership proofs must sign
+    challenges: Arc<ChallengeStore>,
+    queue_config: QueueConfig,
+    queue: Arc<JobQueue<ProvingJob>>,
+    /// Workers are spawned on the first job, once the builder is done
+    workers: OnceLock<()>,
 }
 
 impl ProofGenerator {
-    pub fn new() -> Self {
+    /// Generator keeping proof records in memory and artifacts in the system
+    /// temp directory; see `with_store` and `with_artifacts` for persistence
+    pub fn new(prover: Arc<dyn ProverBackend>) -> Self {
+        let queue_config = QueueConfig::default();
         Self {
-            storage: Arc::new(Mutex::new(HashMap::new())),
+            ctx: JobContext {
+                store: Arc::new(MemoryProofStore::new()),
+                artifacts: ArtifactStore::new(std::env::temp_dir().join("zkpoor-artifacts")),
+                prover,
+                jobs: Arc::new(Mutex::new(HashMap::new())),
+                records: Arc::new(tokio::sync::Mutex::new(())),
+                self_verifier: None,
+                events: broadcast::channel(EVENT_CAPACITY).0,
+            },
+            ownership: Arc::new(SignatureVerifier::new()),
+            challenges: Arc::new(ChallengeStore::default()),
+            queue_config,
+            queue: Arc::new(JobQueue::new(queue_config.max_depth)),
+            workers: OnceLock::new(),
         }
     }
 
-    /// Generate a STARK proof for the given UTXOs (mocked implementation)
+    /// Generator backed by the mock prover and accepting any ownership proof,
+    /// for tests and local development
+    pub fn mock() -> Self {
+        Self::new(Arc::new(MockProver::new()))
+            .with_self_verify(ProofVerifier::mock())
+            .with_ownership_verifier(Arc::new(MockOwnershipVerifier))
+    }
+
+    pub fn with_store(mut self, store: Arc<dyn ProofStore>) -> Self {
+        self.ctx.store = store;
+        self
+    }
+
+    pub fn with_artifacts(mut self, artifacts: ArtifactStore) -> Self {
+        self.ctx.artifacts = artifacts;
+        self
+    }
+
+    /// How ownership proofs are checked; signatures by default
+    pub fn with_ownership_verifier(mut self, ownership: Arc<dyn OwnershipVerifier>) -> Self {
+        self.ownership = ownership;
+        self
+    }
+
+    /// Operator and lifetime of the challenges ownership proofs sign
+    pub fn with_challenges(mut self, challenges: ChallengeStore) -> Self {
+        self.challenges = Arc::new(challenges);
+        self
+    }
+
+    /// Verify every proof after it is generated and fail the job if it does not verify
+    pub fn with_self_verify(mut self, verifier: ProofVerifier) -> Self {
+        self.ctx.self_verifier = Some(verifier);
+        self
+    }
+
+    pub fn with_queue(mut self, config: QueueConfig) -> Self {
+        self.queue_config = config;
+        self.queue = Arc::new(JobQueue::new(config.max_depth));
+        self
+    }
+
+    pub fn store(&self) -> Arc<dyn ProofStore> {
+        Arc::clone(&self.ctx.store)
+    }
+
+    pub fn artifacts(&self) -> &ArtifactStore {
+        &self.ctx.artifacts
+    }
+
+    /// Receive every status and stage change of every job, as the updated
+    /// record. Slow receivers may lag and miss updates.
+    pub fn subscribe(&self) -> broadcast::Receiver<ProofData> {
+        self.ctx.events.subscribe()
+    }
+
+    /// A single-use challenge for the ownership proofs of one prove request
+    pub fn issue_challenge(&self) -> Challenge {
+        self.challenges.issue()
+    }
+
+    /// Jobs waiting for a proving worker
+    pub fn queue_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Queue a STARK proof for the given UTXOs with the configured backend.
+    /// Fails with `Error::QueueFull` when too many jobs are already waiting.
+    /// The ownership proofs must sign a challenge from `issue_challenge`,
+    /// which the job uses up. `created_by` is the API key the job is attributed to.
     pub async fn generate_proof_async(
         &self,
         utxos: &[Utxo],
         ownership_proofs: &[OwnershipProof],
-    ) -> Result<Uuid> {
+        priority: Priority,
+        created_by: Option<Uuid>,
+    ) -> Result<Uuid, Error> {
+        if utxos.is_empty() {
+            return Err(Error::InvalidRequest("No UTXOs provided".to_string()));
+        }
+        if utxos.len() != ownership_proofs.len() {
+            return Err(Error::InvalidRequest(
+                "Each UTXO must have a corresponding ownership proof".to_string(),
+            ));
+        }
+        self.ctx.prover.check_available()?;
+        if let Some(max) = self.ctx.prover.max_utxos() {
+            if utxos.len() > max {
+                return Err(Error::InvalidRequest(format!(
+                    "The {} prover proves at most {} UTXO(s) per request, got {}",
+                    self.ctx.prover.name(), max, utxos.len()
+                )));
+            }
+        }
+        validate_utxos(utxos, ownership_proofs, self.ownership.as_ref(), &self.challenges)?;
+
+        // Reject before recording anything if there is no room, and only then
+        // use up the challenge, so a full queue does not cost the client one
+        let reservation = self.queue.reserve()?;
+        self.challenges.redeem(ownership_proofs)?;
         let proof_id = Uuid::new_v4();
         
         // Create initial proof data with pending status
@@ -34,233 +188,798 @@ impl ProofGenerator {
             proof_id,
             status: ProofStatus::Pending,
             total_amount: None,
-            proof: None,
+            proof_artifact: None,
             public_inputs: None,
             created_at: Utc::now(),
             completed_at: None,
+            stage: None,
+            stages: Vec::new(),
+            progress_percent: None,
+            failure: None,
+            created_by,
+            inputs: Some(ProofInputs {
+                utxos: utxos.to_vec(),
+                ownership_proofs: ownership_proofs.to_vec(),
+            }),
         };
 
         // Store initial proof data
-        {
-            let mut storage = self.storage.lock().await;
-            storage.insert(proof_id, proof_data);
+        self.ctx.save(&proof_data).await?;
+
+        let cancel = CancellationToken::new();
+        self.ctx.jobs().insert(proof_id, cancel.clone());
+
+        self.start_workers();
+        reservation.push(
+            ProvingJob {
+                proof_id,
+                utxos: utxos.to_vec(),
+                cancel,
+            },
+            priority,
+        );
+
+        Ok(proof_id)
+    }
+
+    /// Cancel a queued or running job and return its updated record.
+    /// Cancelling an already cancelled job is a no-op.
+    pub async fn cancel_proof(&self, proof_id: Uuid) -> Result<ProofData, Error> {
+        let cancel = self.ctx.jobs().get(&proof_id).cloned();
+        if let Some(cancel) = cancel {
+            // A running job stops at its next await point, killing any prover processes
+            cancel.cancel();
+            self.queue.remove(|job| job.proof_id == proof_id);
+            self.ctx.jobs().remove(&proof_id);
         }
 
-        // Spawn background task to simulate proof generation
-        let storage_clone = Arc::clone(&self.storage);
-        let utxos_clone = utxos.to_vec();
-        let ownership_proofs_clone = ownership_proofs.to_vec();
-        
-        tokio::spawn(async move {
-            let storage_for_error = Arc::clone(&storage_clone);
-            if let Err(e) = Self::generate_proof_background(
-                storage_clone,
+        let _records = self.ctx.records.lock().await;
+        let mut proof_data = self.ctx.store.get(proof_id).await?
+            .ok_or(Error::ProofNotFound(proof_id))?;
+        if matches!(proof_data.status, ProofStatus::Completed | ProofStatus::Failed) {
+            return Err(Error::ProofFinished {
                 proof_id,
-                utxos_clone,
-                ownership_proofs_clone,
-            ).await {
-                eprintln!("Background proof generation failed: {}", e);
-                
-                // Mark as failed
-                let mut storage = storage_for_error.lock().await;
-                if let Some(proof_data) = storage.get_mut(&proof_id) {
-                    proof_data.status = ProofStatus::Failed;
-                    proof_data.completed_at = Some(Utc::now());
-                }
+                status: proof_data.status,
+            });
+        }
+        if proof_data.status != ProofStatus::Cancelled {
+            let now = Utc::now();
+            proof_data.status = ProofStatus::Cancelled;
+            proof_data.finish_stage(now);
+            proof_data.completed_at = Some(now);
+            self.ctx.save(&proof_data).await?;
+            tracing::info!("Cancelled proof {}", proof_id);
+        }
+        Ok(proof_data)
+    }
+
+    fn start_workers(&self) {
+        self.workers.get_or_init(|| {
+            for _ in 0..self.queue_config.workers.max(1) {
+                let queue = Arc::clone(&self.queue);
+                let ctx = self.ctx.clone();
+
+                tokio::spawn(async move {
+                    while let Some(job) = queue.pop().await {
+                        hold_slot(ctx.run_job(job)).await;
+                    }
+                });
             }
         });
+    }
 
-        Ok(proof_id)
+    pub async fn get_proof_status(&self, proof_id: Uuid) -> Result<Option<ProofData>> {
+        self.ctx.store.get(proof_id).await
+    }
+
+    /// Like `get_proof_status`, failing with `Error::ProofNotFound` for unknown IDs
+    pub async fn get_proof(&self, proof_id: Uuid) -> Result<ProofData, Error> {
+        self.get_proof_status(proof_id).await?
+            .ok_or(Error::ProofNotFound(proof_id))
+    }
+}
+
+impl Drop for ProofGenerator {
+    fn drop(&mut self) {
+        // Let the workers exit once they have drained the queue
+        self.queue.close();
+    }
+}
+
+impl JobContext {
+    fn jobs(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, CancellationToken>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn run_job(&self, job: ProvingJob) {
+        let proof_id = job.proof_id;
+        let cancel = job.cancel.clone();
+
+        // Dropping the proving future on cancellation aborts it
+        let result = tokio::select! {
+            _ = cancel.cancelled() => None,
+            result = self.generate_proof_background(proof_id, job.utxos) => Some(result),
+        };
+        self.jobs().remove(&proof_id);
+
+        if let Some(Err(e)) = result {
+            tracing::error!("Proof {} failed ({:?}): {:#}", proof_id, e.category, e.error);
+            
+            // Mark as failed, keeping what went wrong for the API
+            let result = self.update(proof_id, |proof_data| {
+                let now = Utc::now();
+                proof_data.status = ProofStatus::Failed;
+                proof_data.failure = Some(ProofFailure {
+                    category: e.category,
+                    message: format!("{:#}", e.error),
+                    stage: proof_data.stage,
+                    stderr_tail: e.stderr.as_deref().and_then(tail),
+                });
+                proof_data.finish_stage(now);
+                proof_data.completed_at = Some(now);
+            }).await;
+            if let Err(e) = result {
+                tracing::error!("Failed to mark proof {} as failed: {:#}", proof_id, e);
+            }
+        }
     }
 
     async fn generate_proof_background(
-        storage: ProofStorage,
+        &self,
         proof_id: Uuid,
         utxos: Vec<Utxo>,
-        _ownership_proofs: Vec<OwnershipProof>,
-    ) -> Result<()> {
+    ) -> Result<(), JobError> {
+        let progress = JobProgress { ctx: self, proof_id };
+
         // Mark as in progress
-        {
-            let mut storage = storage.lock().await;
-            if let Some(proof_data) = storage.get_mut(&proof_id) {
-                proof_data.status = ProofStatus::InProgress;
+        self.update(proof_id, |proof_data| {
+            proof_data.status = ProofStatus::InProgress;
+            proof_data.enter_stage(ProofStage::ValidatingInputs);
+        }).await?;
+
+        // Calculate total amount
+        let total_amount = total_amount(&utxos)
+            .map_err(|e| JobError::new(FailureCategory::InvalidInput, e.into()))?;
+
+        // Generate STARK proof; the backend reports its own stages
+        let request = ProvingRequest { utxos, total_amount };
+        let zk_proof = self.prover.prove(&request, &progress).await
+            .map_err(|e| JobError::prover(self.prover.name(), e))?;
+
+        if let Some(verifier) = &self.self_verifier {
+            progress.enter(ProofStage::SelfVerifying).await;
+            let result = verifier
+                .verify_proof(&zk_proof.proof, Some(&zk_proof.public_inputs.to_string()))
+                .await
+                .map_err(|e| JobError::new(FailureCategory::SelfVerificationFailed, e.into()))?;
+            if !result.is_valid {
+                let reason = result.reason.unwrap_or_else(|| "rejected by the verifier".to_string());
+                return Err(JobError::new(
+                    FailureCategory::SelfVerificationFailed,
+                    anyhow::Error::from(Error::ProofInvalid(reason)).context("Generated proof failed self-verification"),
+                ));
             }
         }
 
-        // Simulate proof generation delay
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        // Keep the (potentially large) proof out of the proof record
+        let artifact = self.artifacts.put(zk_proof.proof.as_bytes()).await?;
 
-        // Validate ownership proofs (mocked)
-        Self::validate_ownership_proofs(&utxos)?;
-
-        // Calculate total amount
-        let total_amount: u64 = utxos.iter().map(|utxo| utxo.amount).sum();
+        // Update proof data with results
+        self.update(proof_id, |proof_data| {
+            let now = Utc::now();
+            proof_data.status = ProofStatus::Completed;
+            proof_data.total_amount = Some(total_amount);
+            proof_data.proof_artifact = Some(artifact);
+            proof_data.public_inputs = Some(zk_proof.public_inputs);
+            proof_data.finish_stage(now);
+            proof_data.progress_percent = Some(100);
+            proof_data.completed_at = Some(now);
+        }).await?;
 
-        // Generate mock STARK proof
-        let zk_proof = Self::generate_stark_proof(&utxos, total_amount).await?;
+        Ok(())
+    }
 
-        // Update proof data with results
-        {
-            let mut storage = storage.lock().await;
-            if let Some(proof_data) = storage.get_mut(&proof_id) {
-                proof_data.status = ProofStatus::Completed;
-                proof_data.total_amount = Some(total_amount);
-                proof_data.proof = Some(zk_proof.to_base64());
-                proof_data.public_inputs = Some(zk_proof.public_inputs);
-                proof_data.completed_at = Some(Utc::now());
-            }
+    /// Load, modify and save a proof record. `Cancelled` is final, so updates
+    /// racing with a cancellation are dropped.
+    async fn update<F>(&self, proof_id: Uuid, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut ProofData),
+    {
+        let _records = self.records.lock().await;
+        let mut proof_data = self.store.get(proof_id).await?
+            .ok_or_else(|| anyhow!("Proof {} disappeared from the store", proof_id))?;
+        if proof_data.status == ProofStatus::Cancelled {
+            return Ok(());
         }
+        f(&mut proof_data);
+        self.save(&proof_data).await
+    }
 
+    /// Save a record and announce it to subscribers
+    async fn save(&self, proof_data: &ProofData) -> Result<()> {
+        self.store.save(proof_data).await?;
+        // No subscribers is not an error
+        let _ = self.events.send(proof_data.clone());
         Ok(())
     }
+}
 
-    fn validate_ownership_proofs(utxos: &[Utxo]) -> Result<()> {
-        // Mock validation - in production, this would verify signatures
-        for (i, utxo) in utxos.iter().enumerate() {
-            if utxo.txid.is_empty() || utxo.script_pubkey.is_empty() {
-                return Err(anyhow!("Invalid UTXO at index {}: missing required fields", i));
-            }
-            
-            if utxo.amount == 0 {
-                return Err(anyhow!("Invalid UTXO at index {}: amount cannot be zero", i));
+/// Lines of prover stderr kept on a failed job
+const STDERR_TAIL_LINES: usize = 20;
+
+/// A job failure, before the failing stage is attached
+struct JobError {
+    category: FailureCategory,
+    error: anyhow::Error,
+    stderr: Option<String>,
+}
+
+impl JobError {
+    fn new(category: FailureCategory, error: anyhow::Error) -> Self {
+        Self { category, error, stderr: None }
+    }
+
+    /// Classify an error returned by a prover backend
+    fn prover(name: &str, error: anyhow::Error) -> Self {
+        let mut category = FailureCategory::ProverError;
+        let mut stderr = None;
+
+        if let Some(e) = error.downcast_ref::<ToolchainError>() {
+            let failing = e.steps.iter().find(|info| info.step == e.step).or(e.steps.last());
+            if failing.is_some_and(|info| info.timed_out) {
+                category = FailureCategory::Timeout;
             }
+            stderr = failing.map(|info| info.stderr.clone());
+        } else if let Some(e) = error.downcast_ref::<ProverExit>() {
+            stderr = Some(e.stderr.clone());
+        } else if let Some(Error::BackendUnavailable { .. }) = error.downcast_ref::<Error>() {
+            category = FailureCategory::BackendUnavailable;
         }
-        
-        Ok(())
-    }
 
-    async fn generate_stark_proof(utxos: &[Utxo], total_amount: u64) -> Result<ZkProof> {
-        // Mock STARK proof generation
-        // In production, this would call into a Cairo program or similar
-        
-        let mock_proof_data = format!("mock_stark_proof_for_{}_utxos_total_{}", utxos.len(), total_amount)
-            .as_bytes()
-            .to_vec();
-
-        let public_inputs = serde_json::json!({
-            "total_amount": total_amount,
-            "utxo_count": utxos.len(),
-            "timestamp": Utc::now().timestamp()
-        });
+        Self {
+            category,
+            error: error.context(format!("{} prover failed", name)),
+            stderr,
+        }
+    }
+}
 
-        Ok(ZkProof {
-            proof_data: mock_proof_data,
-            public_inputs,
-            total_amount,
-        })
+/// Storage and other unexpected errors
+impl From<anyhow::Error> for JobError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::new(FailureCategory::Internal, error)
     }
+}
 
-    pub async fn get_proof_status(&self, proof_id: Uuid) -> Result<Option<ProofData>> {
-        let storage = self.storage.lock().await;
-        Ok(storage.get(&proof_id).cloned())
+/// Last `STDERR_TAIL_LINES` lines of `text`, or `None` if it is blank
+fn tail(text: &str) -> Option<String> {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let tail = lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n");
+    (!tail.trim().is_empty()).then_some(tail)
+}
+
+/// Persists the stages a running job reports
+struct JobProgress<'a> {
+    ctx: &'a JobContext,
+    proof_id: Uuid,
+}
+
+#[async_trait]
+impl ProgressReporter for JobProgress<'_> {
+    async fn enter(&self, stage: ProofStage) {
+        let result = self.ctx.update(self.proof_id, |proof_data| proof_data.enter_stage(stage)).await;
+        if let Err(e) = result {
+            // Progress is informational; never fail the job over it
+            tracing::error!("Failed to record stage {:?} of proof {}: {:#}", stage, self.proof_id, e);
+        }
     }
 }
 
+/// Verifies STARK proofs through a pluggable `StarkVerifier` backend, and
+/// that they are proofs of the expected programs
+#[derive(Clone)]
 pub struct ProofVerifier {
-    // In production, this might hold verification keys, etc.
+    stark: Arc<dyn StarkVerifier>,
+    expected: ExpectedPrograms,
 }
 
 impl ProofVerifier {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(stark: Arc<dyn StarkVerifier>) -> Self {
+        Self { stark, expected: ExpectedPrograms::default() }
     }
 
-    /// Verify a STARK proof (mocked implementation)
+    /// Only accept proofs whose output carries these program hashes
+    pub fn with_expected_programs(mut self, expected: ExpectedPrograms) -> Self {
+        self.expected = expected;
+        self
+    }
+
+    /// Verifier that only accepts mock proofs, for tests and local development
+    pub fn mock() -> Self {
+        Self::new(Arc::new(MockStarkVerifier))
+    }
+
+    /// Name of the STARK backend, `mock` for `ProofVerifier::mock`
+    pub fn name(&self) -> &'static str {
+        self.stark.name()
+    }
+
+    pub fn is_mock(&self) -> bool {
+        self.name() == MockStarkVerifier.name()
+    }
+
+    /// Verify a STARK proof, decoding its public output and checking it
+    /// against the expected programs. The amount in `public_inputs_json`, if
+    /// given, is reported as the prover's claim.
     pub async fn verify_proof(
         &self,
-        proof_base64: &str,
-        public_inputs_json: &str,
-    ) -> Result<(bool, Option<u64>)> {
-        // Mock verification logic
-        if proof_base64.is_empty() || public_inputs_json.is_empty() {
-            return Err(anyhow!("Empty proof or public inputs"));
+        proof: &str,
+        public_inputs_json: Option<&str>,
+    ) -> Result<VerificationResult, Error> {
+        if proof.is_empty() {
+            return Err(Error::InvalidRequest("Empty proof".to_string()));
         }
 
-        // Parse public inputs
-        let public_inputs: serde_json::Value = serde_json::from_str(public_inputs_json)
-            .map_err(|e| anyhow!("Invalid public inputs JSON: {}", e))?;
+        // The amount is only the prover's claim, reported as sent
+        let claimed_total_amount = match public_inputs_json.filter(|json| !json.is_empty()) {
+            Some(json) => {
+                let public_inputs: serde_json::Value = serde_json::from_str(json)
+                    .map_err(|e| Error::InvalidRequest(format!("Invalid public inputs JSON: {}", e)))?;
+                public_inputs.get("total_amount").and_then(|v| v.as_u64())
+            }
+            None => None,
+        };
 
-        // Extract total amount from public inputs
-        let total_amount = public_inputs.get("total_amount")
-            .and_then(|v| v.as_u64());
+        let verdict = self.verify_stark_proof(proof).await?;
 
-        // Mock verification - in production, this would verify the STARK proof
-        let is_valid = Self::verify_stark_proof(proof_base64, &public_inputs).await?;
+        // Only trust the output of a proof that verified
+        let decoded = match (&verdict.output, verdict.is_valid) {
+            (Some(output), true) => Some(PublicOutput::decode(output)),
+            _ => None,
+        };
 
-        Ok((is_valid, total_amount))
+        Ok(match decoded {
+            Some(Err(e)) => VerificationResult {
+                is_valid: false,
+                claimed_total_amount,
+                public_output: None,
+                reason: Some(format!("Unexpected program output: {}", e)),
+            },
+            Some(Ok(public_output)) => {
+                let mismatch = public_output.check(&self.expected).err();
+                VerificationResult {
+                    is_valid: mismatch.is_none(),
+                    claimed_total_amount,
+                    public_output: Some(public_output),
+                    reason: mismatch,
+                }
+            }
+            // Without an output there is no program hash to check
+            None if verdict.is_valid && !self.expected.is_empty() => VerificationResult {
+                is_valid: false,
+                claimed_total_amount,
+                public_output: None,
+                reason: Some("The verifier exposes no program output to check the program hashes against".to_string()),
+            },
+            None => VerificationResult {
+                is_valid: verdict.is_valid,
+                claimed_total_amount,
+                public_output: None,
+                reason: verdict.reason,
+            },
+        })
     }
 
-    async fn verify_stark_proof(
-        proof_base64: &str,
-        public_inputs: &serde_json::Value,
-    ) -> Result<bool> {
-        // Simulate verification delay
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    async fn verify_stark_proof(&self, proof: &str) -> Result<StarkVerdict, Error> {
+        // Verification is CPU-bound and can take seconds for real proofs
+        let stark = Arc::clone(&self.stark);
+        let proof = proof.to_string();
+        tokio::task::spawn_blocking(move || stark.verify(&proof))
+            .await
+            .map_err(|e| anyhow!("Verification task panicked: {}", e))?
+            // The verifier only errors when it cannot make sense of the proof
+            .map_err(|e| Error::ProofMalformed(format!("{:#}", e)))
+    }
+}
 
-        // Mock verification logic - in production, this would verify the STARK proof
-        // For now, we accept proofs that start with "mock_proof_" and have valid public inputs
-        let is_mock_proof = proof_base64.starts_with("mock_proof_");
-        let has_valid_inputs = public_inputs.get("total_amount").is_some() 
-            && public_inputs.get("utxo_count").is_some();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::{Amount, Txid};
+    use crate::prover::ExternalProver;
+
+    fn utxos() -> Vec<Utxo> {
+        vec![Utxo {
+            txid: Txid::from_bytes([1; 32]),
+            vout: 0,
+            amount: Amount::from_sat(100000000).unwrap(), // 1 BTC in satoshis
+            script_pubkey: "76a914fb37342f6275b13936799def06f2eb4c0f20151588ac".parse().unwrap(),
+        }]
+    }
 
-        Ok(is_mock_proof && has_valid_inputs)
+    /// Mock ownership proofs signing a fresh challenge from `generator`
+    fn ownership_proofs(generator: &ProofGenerator) -> Vec<OwnershipProof> {
+        vec![OwnershipProof {
+            signature: "mock_signature".to_string(),
+            challenge: generator.issue_challenge().message,
+            format: SignatureFormat::Legacy,
+        }]
     }
-}
 
-impl Default for ProofGenerator {
-    fn default() -> Self {
-        Self::new()
+    /// Generator accepting the mock ownership proofs
+    fn generator(prover: impl ProverBackend + 'static) -> ProofGenerator {
+        ProofGenerator::new(Arc::new(prover)).with_ownership_verifier(Arc::new(MockOwnershipVerifier))
     }
-}
 
-impl Default for ProofVerifier {
-    fn default() -> Self {
-        Self::new()
+    async fn submit(generator: &ProofGenerator) -> Uuid {
+        generator.generate_proof_async(&utxos(), &ownership_proofs(generator), Priority::Normal, None).await.unwrap()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Next update of `proof_id` matching `done`
+    async fn wait_for(
+        events: &mut broadcast::Receiver<ProofData>,
+        proof_id: Uuid,
+        done: impl Fn(&ProofData) -> bool,
+    ) -> ProofData {
+        loop {
+            let event = events.recv().await.unwrap();
+            if event.proof_id == proof_id && done(&event) {
+                return event;
+            }
+        }
+    }
+
+    /// Proves one UTXO at a time: enters `Executing`, then blocks until `gate` is notified
+    struct GatedProver {
+        gate: Arc<tokio::sync::Notify>,
+    }
+
+    impl GatedProver {
+        fn new() -> Self {
+            Self { gate: Arc::new(tokio::sync::Notify::new()) }
+        }
+    }
+
+    #[async_trait]
+    impl ProverBackend for GatedProver {
+        fn name(&self) -> &'static str {
+            "gated"
+        }
+
+        fn max_utxos(&self) -> Option<usize> {
+            Some(1)
+        }
+
+        async fn prove(&self, request: &ProvingRequest, progress: &dyn ProgressReporter) -> Result<ZkProof> {
+            progress.enter(ProofStage::Executing).await;
+            self.gate.notified().await;
+            MockProver::new().with_delay(std::time::Duration::ZERO).prove(request, progress).await
+        }
+    }
+
+    /// Runs uninterruptible work that waits for `release`, logging when jobs start and the work ends
+    struct BlockingProver {
+        release: Arc<Mutex<std::sync::mpsc::Receiver<()>>>,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl ProverBackend for BlockingProver {
+        fn name(&self) -> &'static str {
+            "blocking"
+        }
+
+        async fn prove(&self, request: &ProvingRequest, progress: &dyn ProgressReporter) -> Result<ZkProof> {
+            self.log.lock().unwrap().push("start");
+            progress.enter(ProofStage::Executing).await;
+            let (release, log) = (Arc::clone(&self.release), Arc::clone(&self.log));
+            crate::prover::run_blocking(move || {
+                release.lock().unwrap().recv()?;
+                log.lock().unwrap().push("done");
+                Ok(())
+            })
+            .await?;
+            MockProver::new().with_delay(std::time::Duration::ZERO).prove(request, progress).await
+        }
+    }
 
     #[tokio::test]
     async fn test_proof_generation() {
-        let generator = ProofGenerator::new();
-        let utxos = vec![
-            Utxo {
-                txid: "mock_txid_1".to_string(),
-                vout: 0,
-                amount: 100000000, // 1 BTC in satoshis
-                script_pubkey: "mock_script".to_string(),
-            }
-        ];
-        let ownership_proofs = vec![
-            OwnershipProof {
-                signature: "mock_signature".to_string(),
-                challenge: "mock_challenge".to_string(),
-            }
-        ];
+        let generator = generator(MockProver::new().with_delay(std::time::Duration::ZERO))
+            .with_self_verify(ProofVerifier::mock());
+        let mut events = generator.subscribe();
 
-        let proof_id = generator.generate_proof_async(&utxos, &ownership_proofs).await.unwrap();
+        let proof_id = submit(&generator).await;
         assert!(!proof_id.is_nil());
 
-        // Wait for background processing
-        tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-
-        let proof_data = generator.get_proof_status(proof_id).await.unwrap().unwrap();
+        let proof_data = wait_for(&mut events, proof_id, |proof| proof.status.is_terminal()).await;
         assert_eq!(proof_data.status, ProofStatus::Completed);
-        assert_eq!(proof_data.total_amount, Some(100000000));
-        assert!(proof_data.proof.is_some());
+        assert_eq!(proof_data.total_amount.map(Amount::to_sat), Some(100000000));
+        assert_eq!(proof_data.progress_percent, Some(100));
+        let stages: Vec<_> = proof_data.stages.iter().map(|s| s.stage).collect();
+        assert_eq!(stages, vec![
+            ProofStage::ValidatingInputs,
+            ProofStage::Executing,
+            ProofStage::Proving,
+            ProofStage::SelfVerifying,
+        ]);
+        assert!(proof_data.stages.iter().all(|s| s.finished_at.is_some()));
+        let artifact = proof_data.proof_artifact.unwrap();
+        let proof = generator.artifacts().read(&artifact).await.unwrap();
+        assert!(proof.starts_with(b"mock_proof_"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_jobs_when_queue_full() {
+        let generator = generator(GatedProver::new())
+            .with_queue(QueueConfig { workers: 1, max_depth: 1 });
+
+        // Whether or not the worker has picked up the first job yet, the third cannot fit
+        let mut results = Vec::new();
+        for _ in 0..3 {
+            results.push(generator.generate_proof_async(&utxos(), &ownership_proofs(&generator), Priority::Normal, None).await);
+        }
+        assert!(results[0].is_ok());
+        let err = results[2].as_ref().unwrap_err();
+        assert!(matches!(err, Error::QueueFull(_)));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_more_utxos_than_the_prover_takes() {
+        let generator = generator(GatedProver::new());
+        let mut utxos = utxos();
+        utxos.push(Utxo { vout: 1, ..utxos[0].clone() });
+        let ownership_proofs = [ownership_proofs(&generator), ownership_proofs(&generator)].concat();
+
+        let err = generator.generate_proof_async(&utxos, &ownership_proofs, Priority::Normal, None).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidRequest(ref message) if message.contains("at most 1 UTXO")));
+        assert_eq!(generator.queue_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_jobs_when_backend_unavailable() {
+        let generator = generator(ExternalProver::new("/nonexistent/prover", std::env::temp_dir()));
+        let err = generator.generate_proof_async(&utxos(), &ownership_proofs(&generator), Priority::Normal, None).await.unwrap_err();
+        assert!(matches!(err, Error::BackendUnavailable { ref backend, .. } if backend == "external"));
+        assert_eq!(err.code(), "backend_unavailable");
+        assert_eq!(generator.queue_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_self_verification_rejects_invalid_proof() {
+        let generator = generator(MockProver::new().with_delay(std::time::Duration::ZERO))
+            .with_self_verify(ProofVerifier::new(Arc::new(OutputVerifier(Vec::new()))));
+        let mut events = generator.subscribe();
+
+        let proof_id = submit(&generator).await;
+        let failed = wait_for(&mut events, proof_id, |proof| proof.status.is_terminal()).await;
+        let failure = failed.failure.unwrap();
+        assert_eq!(failure.category, FailureCategory::SelfVerificationFailed);
+        assert_eq!(failure.stage, Some(ProofStage::SelfVerifying));
+        assert!(failure.message.starts_with("Generated proof failed self-verification: Invalid proof:"));
+        assert!(failed.proof_artifact.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_queued_and_running_jobs() {
+        let prover = GatedProver::new();
+        let gate = Arc::clone(&prover.gate);
+        let generator = generator(prover);
+        let mut events = generator.subscribe();
+        let executing = |proof: &ProofData| proof.stage == Some(ProofStage::Executing);
+
+        let running = submit(&generator).await;
+        let queued = submit(&generator).await;
+        wait_for(&mut events, running, executing).await;
+
+        let cancelled = generator.cancel_proof(queued).await.unwrap();
+        assert_eq!(cancelled.status, ProofStatus::Cancelled);
+        assert_eq!(generator.queue_len(), 0);
+
+        assert_eq!(generator.get_proof_status(running).await.unwrap().unwrap().status, ProofStatus::InProgress);
+        let cancelled = generator.cancel_proof(running).await.unwrap();
+        assert_eq!(cancelled.status, ProofStatus::Cancelled);
+        assert!(cancelled.completed_at.is_some());
+
+        // The worker is free again for new jobs, and the cancelled one stays cancelled
+        let next = submit(&generator).await;
+        wait_for(&mut events, next, executing).await;
+        gate.notify_one();
+        let finished = wait_for(&mut events, next, |proof| proof.status.is_terminal()).await;
+        assert_eq!(finished.status, ProofStatus::Completed);
+        assert_eq!(generator.get_proof_status(running).await.unwrap().unwrap().status, ProofStatus::Cancelled);
+
+        assert!(matches!(generator.cancel_proof(Uuid::new_v4()).await, Err(Error::ProofNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_blocking_work_keeps_its_worker() {
+        let (release, receiver) = std::sync::mpsc::channel();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let generator = generator(BlockingProver { release: Arc::new(Mutex::new(receiver)), log: Arc::clone(&log) });
+        let mut events = generator.subscribe();
+        let executing = |proof: &ProofData| proof.stage == Some(ProofStage::Executing);
+
+        let cancelled = submit(&generator).await;
+        wait_for(&mut events, cancelled, executing).await;
+        generator.cancel_proof(cancelled).await.unwrap();
+
+        // The single worker waits for the cancelled job's work before starting the next job
+        let next = submit(&generator).await;
+        let started = tokio::time::timeout(
+            std::time::Duration::from_millis(100),
+            wait_for(&mut events, next, |proof| proof.status == ProofStatus::InProgress),
+        );
+        assert!(started.await.is_err());
+
+        release.send(()).unwrap();
+        release.send(()).unwrap();
+        let finished = wait_for(&mut events, next, |proof| proof.status.is_terminal()).await;
+        assert_eq!(finished.status, ProofStatus::Completed);
+        assert_eq!(*log.lock().unwrap(), ["start", "done", "start", "done"]);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_every_transition() {
+        let generator = generator(MockProver::new().with_delay(std::time::Duration::from_millis(20)));
+        let mut events = generator.subscribe();
+
+        let proof_id = submit(&generator).await;
+        let mut seen = Vec::new();
+        loop {
+            let event = events.recv().await.unwrap();
+            assert_eq!(event.proof_id, proof_id);
+            seen.push((event.status.clone(), event.stage));
+            if event.status.is_terminal() {
+                break;
+            }
+        }
+        assert_eq!(seen, vec![
+            (ProofStatus::Pending, None),
+            (ProofStatus::InProgress, Some(ProofStage::ValidatingInputs)),
+            (ProofStatus::InProgress, Some(ProofStage::Executing)),
+            (ProofStatus::InProgress, Some(ProofStage::Proving)),
+            (ProofStatus::Completed, Some(ProofStage::Proving)),
+        ]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failed_job_records_failure() {
+        let work_dir = std::env::temp_dir().join(format!("zkpoor-failing-{}", Uuid::new_v4()));
+        let generator = generator(
+            ExternalProver::new("sh", &work_dir).with_args(["-c", "echo 'out of memory' >&2; exit 137"]),
+        );
+        let mut events = generator.subscribe();
+
+        let proof_id = submit(&generator).await;
+        let failed = wait_for(&mut events, proof_id, |proof| proof.status.is_terminal()).await;
+        assert_eq!(failed.status, ProofStatus::Failed);
+        let failure = failed.failure.unwrap();
+        assert_eq!(failure.category, FailureCategory::ProverError);
+        assert_eq!(failure.stage, Some(ProofStage::Proving));
+        assert!(failure.message.starts_with("external prover failed"));
+        assert_eq!(failure.stderr_tail.as_deref(), Some("out of memory"));
+
+        std::fs::remove_dir_all(&work_dir).unwrap();
+    }
+
+    #[test]
+    fn test_classifies_prover_errors() {
+        use crate::toolchain::{StepInfo, ToolchainStep};
+
+        let step = |timed_out| StepInfo {
+            step: ToolchainStep::Prove,
+            stdout: String::new(),
+            stderr: (1..=30).map(|i| format!("line {}\n", i)).collect(),
+            exit_code: None,
+            elapsed_secs: 1.0,
+            max_memory_kb: None,
+            timed_out,
+        };
+        let error = |timed_out| ToolchainError {
+            step: ToolchainStep::Prove,
+            message: "killed".to_string(),
+            steps: vec![step(timed_out)],
+        };
+
+        let failure = JobError::prover("toolchain", error(true).into());
+        assert_eq!(failure.category, FailureCategory::Timeout);
+        let stderr_tail = tail(failure.stderr.as_deref().unwrap()).unwrap();
+        assert_eq!(stderr_tail.lines().count(), STDERR_TAIL_LINES);
+        assert!(stderr_tail.ends_with("line 30"));
+        assert!(format!("{:#}", failure.error).starts_with("toolchain prover failed: PROVE step failed"));
+
+        assert_eq!(JobError::prover("toolchain", error(false).into()).category, FailureCategory::ProverError);
+        let unavailable = Error::BackendUnavailable { backend: "external".to_string(), reason: "gone".to_string() };
+        let failure = JobError::prover("external", unavailable.into());
+        assert_eq!(failure.category, FailureCategory::BackendUnavailable);
+        assert_eq!(format!("{:#}", failure.error), "external prover failed: external backend unavailable: gone");
+
+        let failure = JobError::prover("mock", anyhow!("boom"));
+        assert_eq!(failure.category, FailureCategory::ProverError);
+        assert!(failure.stderr.is_none());
+        assert_eq!(tail("  \n"), None);
     }
 
     #[tokio::test]
     async fn test_proof_verification() {
-        let verifier = ProofVerifier::new();
+        let verifier = ProofVerifier::mock();
         let proof = "mock_proof_test";
         let public_inputs = r#"{"total_amount": 100000000, "utxo_count": 1}"#;
 
-        let (is_valid, total_amount) = verifier.verify_proof(proof, public_inputs).await.unwrap();
-        assert!(is_valid);
-        assert_eq!(total_amount, Some(100000000));
+        let result = verifier.verify_proof(proof, Some(public_inputs)).await.unwrap();
+        assert!(result.is_valid);
+        assert_eq!(result.claimed_total_amount, Some(100000000));
+        assert!(result.reason.is_none());
+        assert!(result.public_output.is_none());
+    }
+
+    /// Verifier stub exposing a fixed output segment
+    struct OutputVerifier(Vec<crate::program::Felt>);
+
+    impl StarkVerifier for OutputVerifier {
+        fn name(&self) -> &'static str {
+            "output"
+        }
+
+        fn verify(&self, _proof: &str) -> Result<StarkVerdict> {
+            Ok(StarkVerdict::valid().with_output(self.0.clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proof_verification_decodes_public_output() {
+        use crate::program::Felt;
+
+        let output: Vec<Felt> = [1u32, 8, 0xaa, 1, 0, 2, 0, 0xbb, 0xcc].into_iter().map(Felt::from).collect();
+        let verifier = ProofVerifier::new(Arc::new(OutputVerifier(output.clone())));
+        let result = verifier.verify_proof("proof", None).await.unwrap();
+        assert!(result.is_valid);
+        assert_eq!(result.public_output.unwrap().program_hash, Felt::from(0xccu32));
+
+        let verifier = ProofVerifier::new(Arc::new(OutputVerifier(output[..5].to_vec())));
+        let result = verifier.verify_proof("proof", None).await.unwrap();
+        assert!(!result.is_valid);
+        assert!(result.reason.unwrap().contains("Unexpected program output"));
+    }
+
+    #[tokio::test]
+    async fn test_proof_verification_rejects_other_programs() {
+        use crate::program::Felt;
+
+        let output: Vec<Felt> = [1u32, 8, 0xaa, 1, 0, 2, 0, 0xbb, 0xcc].into_iter().map(Felt::from).collect();
+        let expected = ExpectedPrograms {
+            task_program_hash: Some(Felt::from(0xaau32)),
+            bootloader_hash: Some(Felt::from(0xbbu32)),
+            program_hash: Some(Felt::from(0xccu32)),
+        };
+        let verifier = ProofVerifier::new(Arc::new(OutputVerifier(output.clone())))
+            .with_expected_programs(expected.clone());
+        assert!(verifier.verify_proof("proof", None).await.unwrap().is_valid);
+
+        // A valid STARK proof of another program is not a zkpoor proof
+        let verifier = ProofVerifier::new(Arc::new(OutputVerifier(output)))
+            .with_expected_programs(ExpectedPrograms { program_hash: Some(Felt::from(0xddu32)), ..expected.clone() });
+        let result = verifier.verify_proof("proof", None).await.unwrap();
+        assert!(!result.is_valid);
+        assert_eq!(result.public_output.unwrap().program_hash, Felt::from(0xccu32));
+        assert!(result.reason.unwrap().contains("program_hash is 0xcc, expected 0xdd"));
+
+        // Nor is a proof whose output cannot be checked
+        let verifier = ProofVerifier::mock().with_expected_programs(expected);
+        let result = verifier.verify_proof("mock_proof_test", None).await.unwrap();
+        assert!(!result.is_valid);
+        assert!(result.reason.unwrap().contains("no program output"));
+    }
+
+    #[tokio::test]
+    async fn test_proof_verification_rejects_unknown_proof() {
+        let verifier = ProofVerifier::mock();
+        let public_inputs = r#"{"total_amount": 100000000, "utxo_count": 1}"#;
+
+        let result = verifier.verify_proof("not_a_proof", Some(public_inputs)).await.unwrap();
+        assert!(!result.is_valid);
+        assert!(result.reason.is_some());
     }
 }
\ No newline at end of file
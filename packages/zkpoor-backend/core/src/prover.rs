@@ -0,0 +1,347 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use anyhow::{Result, anyhow};
+
+use crate::bitcoin::Amount;
+use crate::error::Error;
+use crate::process::{find_program, spawn_group, JobDir};
+use crate::types::*;
+
+/// Everything a prover backend needs to produce a proof
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvingRequest {
+    pub utxos: Vec<Utxo>,
+    pub total_amount: Amount,
+}
+
+/// Receives the stages a backend goes through while proving
+#[async_trait]
+pub trait ProgressReporter: Send + Sync {
+    async fn enter(&self, stage: ProofStage);
+}
+
+/// Discards progress, for callers that do not track it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+#[async_trait]
+impl ProgressReporter for NoProgress {
+    async fn enter(&self, _stage: ProofStage) {}
+}
+
+tokio::task_local! {
+    /// Held by blocking work started for the job a worker is running
+    static JOB_WORK: mpsc::Sender<()>;
+}
+
+/// Run CPU-bound proving work on tokio's blocking pool. Unlike a child process
+/// it cannot be killed: if the job is cancelled the work runs to completion,
+/// and the worker that ran the job does not take another one until it has.
+pub async fn run_blocking<T, F>(work: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let slot = JOB_WORK.try_with(mpsc::Sender::clone).ok();
+    tokio::task::spawn_blocking(move || {
+        let _slot = slot;
+        work()
+    })
+    .await
+    .map_err(|e| anyhow!("Blocking task panicked: {}", e))?
+}
+
+/// Run a job, then wait for any `run_blocking` work it left behind
+pub(crate) async fn hold_slot<F: Future>(job: F) -> F::Output {
+    let (slot, mut released) = mpsc::channel::<()>(1);
+    let output = JOB_WORK.scope(slot, job).await;
+    // Nothing is ever sent; this returns once every clone has been dropped
+    released.recv().await;
+    output
+}
+
+/// A STARK proving backend, selected when the `ProofGenerator` is constructed
+#[async_trait]
+pub trait ProverBackend: Send + Sync {
+    /// Short backend name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Most UTXOs a single proof can cover, checked before a job is queued;
+    /// `None` for no limit
+    fn max_utxos(&self) -> Option<usize> {
+        None
+    }
+
+    /// Fail with `Error::BackendUnavailable` if the backend cannot run, e.g.
+    /// because its binary is missing; checked before a job is queued
+    fn check_available(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Dropping the returned future cancels the job; backends running child
+    /// processes must kill them when that happens
+    async fn prove(&self, request: &ProvingRequest, progress: &dyn ProgressReporter) -> Result<ZkProof>;
+}
+
+/// Fast fake prover producing proofs only `MockStarkVerifier` accepts
+#[derive(Debug, Clone)]
+pub struct MockProver {
+    delay: Duration,
+}
+
+impl MockProver {
+    pub fn new() -> Self {
+        Self {
+            delay: Duration::from_secs(2),
+        }
+    }
+
+    /// Simulated proving time
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+impl Default for MockProver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ProverBackend for MockProver {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn prove(&self, request: &ProvingRequest, progress: &dyn ProgressReporter) -> Result<ZkProof> {
+        // Simulate proof generation delay
+        progress.enter(ProofStage::Executing).await;
+        tokio::time::sleep(self.delay / 2).await;
+        progress.enter(ProofStage::Proving).await;
+        tokio::time::sleep(self.delay - self.delay / 2).await;
+
+        let mock_proof_data = format!(
+            "mock_stark_proof_for_{}_utxos_total_{}",
+            request.utxos.len(),
+            request.total_amount
+        );
+        let hex: String = mock_proof_data.bytes().map(|b| format!("{:02x}", b)).collect();
+
+        let public_inputs = serde_json::json!({
+            "total_amount": request.total_amount,
+            "utxo_count": request.utxos.len(),
+            "timestamp": Utc::now().timestamp()
+        });
+
+        Ok(ZkProof {
+            proof: format!("mock_proof_{}", hex),
+            public_inputs,
+            total_amount: request.total_amount,
+        })
+    }
+}
+
+/// A prover process exited unsuccessfully
+#[derive(Debug, thiserror::Error)]
+#[error("Prover {program} exited with {status}")]
+pub struct ProverExit {
+    pub program: String,
+    pub status: String,
+    pub stderr: String,
+}
+
+/// What an external prover writes to its output file
+#[derive(Debug, Deserialize)]
+struct ExternalProverOutput {
+    proof: String,
+    public_inputs: serde_json::Value,
+}
+
+/// Runs a prover executable as a child process.
+///
+/// Each job gets its own directory under `work_dir` holding `request.json`
+/// (a serialized `ProvingRequest`), removed when the job ends. The program is invoked with the configured
+/// arguments followed by `--request <request.json> --output <proof.json>` and
+/// must write `{ "proof": ..., "public_inputs": ... }` to the output path.
+#[derive(Debug, Clone)]
+pub struct ExternalProver {
+    program: PathBuf,
+    args: Vec<String>,
+    work_dir: PathBuf,
+}
+
+impl ExternalProver {
+    pub fn new(program: impl Into<PathBuf>, work_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            work_dir: work_dir.into(),
+        }
+    }
+
+    /// Extra arguments passed before `--request`/`--output`
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+#[async_trait]
+impl ProverBackend for ExternalProver {
+    fn name(&self) -> &'static str {
+        "external"
+    }
+
+    fn check_available(&self) -> Result<(), Error> {
+        if find_program(&self.program) {
+            return Ok(());
+        }
+        Err(Error::BackendUnavailable {
+            backend: self.name().to_string(),
+            reason: format!("Prover {} not found", self.program.display()),
+        })
+    }
+
+    async fn prove(&self, request: &ProvingRequest, progress: &dyn ProgressReporter) -> Result<ZkProof> {
+        let job_dir = JobDir::create(&self.work_dir).await?;
+        let request_path = job_dir.path().join("request.json");
+        let output_path = job_dir.path().join("proof.json");
+        tokio::fs::write(&request_path, serde_json::to_vec(request)?).await?;
+
+        progress.enter(ProofStage::Proving).await;
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .arg("--request")
+            .arg(&request_path)
+            .arg("--output")
+            .arg(&output_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let (child, group) = spawn_group(&mut command).map_err(|e| Error::BackendUnavailable {
+            backend: self.name().to_string(),
+            reason: format!("Failed to run prover {}: {}", self.program.display(), e),
+        })?;
+        let output = child.wait_with_output().await
+            .map_err(|e| anyhow!("Failed to run prover {}: {}", self.program.display(), e))?;
+        group.disarm();
+
+        if !output.status.success() {
+            return Err(ProverExit {
+                program: self.program.display().to_string(),
+                status: output.status.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            }
+            .into());
+        }
+
+        let raw = tokio::fs::read(&output_path)
+            .await
+            .map_err(|e| anyhow!("Prover did not write {}: {}", output_path.display(), e))?;
+        let ExternalProverOutput { proof, public_inputs } = serde_json::from_slice(&raw)
+            .map_err(|e| anyhow!("Invalid prover output: {}", e))?;
+
+        Ok(ZkProof {
+            proof,
+            public_inputs,
+            total_amount: request.total_amount,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::Txid;
+    use uuid::Uuid;
+
+    fn request() -> ProvingRequest {
+        ProvingRequest {
+            utxos: vec![Utxo {
+                txid: Txid::from_bytes([1; 32]),
+                vout: 0,
+                amount: Amount::from_sat(100000000).unwrap(),
+                script_pubkey: "76a914fb37342f6275b13936799def06f2eb4c0f20151588ac".parse().unwrap(),
+            }],
+            total_amount: Amount::from_sat(100000000).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_prover() {
+        let prover = MockProver::new().with_delay(Duration::ZERO);
+        let proof = prover.prove(&request(), &NoProgress).await.unwrap();
+
+        assert!(proof.proof.starts_with("mock_proof_"));
+        assert_eq!(proof.total_amount.to_sat(), 100000000);
+        assert_eq!(proof.public_inputs["utxo_count"], 1);
+    }
+
+    /// Notifies once a job enters `Proving`
+    #[derive(Default)]
+    struct EnteredProving(tokio::sync::Notify);
+
+    #[async_trait]
+    impl ProgressReporter for EnteredProving {
+        async fn enter(&self, stage: ProofStage) {
+            if stage == ProofStage::Proving {
+                self.0.notify_one();
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_external_prover() {
+        let work_dir = std::env::temp_dir().join(format!("zkpoor-external-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&work_dir).unwrap();
+
+        // Stub prover: echoes the request total back as public inputs
+        let script = work_dir.join("prover.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\n\
+             total=$(sed 's/.*\"total_amount\":\\([0-9]*\\).*/\\1/' \"$2\")\n\
+             printf '{\"proof\":\"stub\",\"public_inputs\":{\"total_amount\":%s}}' \"$total\" > \"$4\"\n",
+        )
+        .unwrap();
+
+        let prover = ExternalProver::new("sh", &work_dir).with_args([script.to_str().unwrap()]);
+        let proof = prover.prove(&request(), &NoProgress).await.unwrap();
+        assert_eq!(proof.proof, "stub");
+        assert_eq!(proof.public_inputs["total_amount"], 100000000);
+
+        let failing = ExternalProver::new("sh", &work_dir).with_args(["-c", "echo 'out of memory' >&2; exit 137"]);
+        let err = failing.prove(&request(), &NoProgress).await.unwrap_err();
+        let exit = err.downcast_ref::<ProverExit>().unwrap();
+        assert_eq!(exit.stderr.trim(), "out of memory");
+
+        // Cancel a job once it is running by dropping its future
+        let stuck = ExternalProver::new("sh", &work_dir).with_args(["-c", "sleep 30"]);
+        let (request, proving) = (request(), EnteredProving::default());
+        tokio::select! {
+            _ = stuck.prove(&request, &proving) => panic!("prover should still be running"),
+            _ = proving.0.notified() => {}
+        }
+
+        // Only the stub script is left; every job directory was removed
+        let entries: Vec<_> = std::fs::read_dir(&work_dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(entries, ["prover.sh"]);
+
+        std::fs::remove_dir_all(&work_dir).unwrap();
+    }
+}
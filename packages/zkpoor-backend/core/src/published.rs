@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use anyhow::{Result, anyhow};
+
+use crate::proof::ProofVerifier;
+use crate::types::VerificationResult;
+
+/// A proof published by the operator, with the outcome of verifying it
+#[derive(Debug, Clone, Serialize)]
+pub struct PublishedProof {
+    /// File stem, e.g. `reserves-2024-06` for `reserves-2024-06.json`
+    pub name: String,
+    /// Hex SHA-256 of the proof file, so clients can check what was verified
+    pub sha256: String,
+    pub size: u64,
+    pub result: VerificationResult,
+    pub verified_at: DateTime<Utc>,
+}
+
+/// Proofs published from a directory and verified once at startup.
+///
+/// Each `<name>.json` is a proof as served by `/proof/{id}/artifact`. Only
+/// what the proof attests to, its decoded public output, is reported: no
+/// unproven amount is published next to a valid verdict.
+#[derive(Debug, Clone, Default)]
+pub struct PublishedProofs {
+    proofs: BTreeMap<String, PublishedProof>,
+}
+
+impl PublishedProofs {
+    /// Load and verify every proof in `dir`. Unreadable files are an error;
+    /// proofs that fail verification are kept and reported as invalid.
+    pub async fn load(dir: impl AsRef<Path>, verifier: &ProofVerifier) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut entries = tokio::fs::read_dir(dir).await
+            .map_err(|e| anyhow!("Failed to read published proofs directory {}: {}", dir.display(), e))?;
+
+        let mut proofs = BTreeMap::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let Some(name) = file_name.strip_suffix(".json") else { continue };
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+
+            let proof = tokio::fs::read_to_string(entry.path()).await
+                .map_err(|e| anyhow!("Failed to read published proof {}: {}", file_name, e))?;
+
+            let result = verifier.verify_proof(&proof, None).await
+                .map_err(|e| anyhow!("Failed to verify published proof {}: {}", file_name, e))?;
+            if result.is_valid {
+                tracing::info!("Published proof {} verified", name);
+            } else {
+                tracing::warn!("Published proof {} does not verify: {:?}", name, result.reason);
+            }
+
+            proofs.insert(name.to_string(), PublishedProof {
+                name: name.to_string(),
+                sha256: hex::encode(Sha256::digest(proof.as_bytes())),
+                size: proof.len() as u64,
+                result,
+                verified_at: Utc::now(),
+            });
+        }
+
+        Ok(Self { proofs })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PublishedProof> {
+        self.proofs.get(name)
+    }
+
+    /// Published proofs in name order
+    pub fn iter(&self) -> impl Iterator<Item = &PublishedProof> {
+        self.proofs.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_load_verifies_each_proof() {
+        let dir = std::env::temp_dir().join(format!("zkpoor-published-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("reserves.json"), "mock_proof_reserves").unwrap();
+        std::fs::write(dir.join("forged.json"), "not_a_proof").unwrap();
+        std::fs::write(dir.join("README.md"), "ignored").unwrap();
+
+        let published = PublishedProofs::load(&dir, &ProofVerifier::mock()).await.unwrap();
+        let names: Vec<_> = published.iter().map(|proof| proof.name.as_str()).collect();
+        assert_eq!(names, ["forged", "reserves"]);
+
+        let reserves = published.get("reserves").unwrap();
+        assert!(reserves.result.is_valid);
+        assert_eq!(reserves.result.claimed_total_amount, None);
+        assert_eq!(reserves.sha256, hex::encode(Sha256::digest(b"mock_proof_reserves")));
+
+        let forged = published.get("forged").unwrap();
+        assert!(!forged.result.is_valid);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_dir_fails() {
+        let dir = std::env::temp_dir().join(format!("zkpoor-published-{}", Uuid::new_v4()));
+        assert!(PublishedProofs::load(&dir, &ProofVerifier::mock()).await.is_err());
+    }
+}
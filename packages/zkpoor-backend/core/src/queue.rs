@@ -0,0 +1,266 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+/// Scheduling priority of a proving job. Jobs of equal priority run in FIFO order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Sizing of the proving worker pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueueConfig {
+    /// Jobs proved concurrently. Proving is memory-bound, so keep this small.
+    pub workers: usize,
+    /// Jobs allowed to wait for a worker before new ones are rejected
+    pub max_depth: usize,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { workers: 1, max_depth: 16 }
+    }
+}
+
+/// Returned when a job is submitted to a full queue
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Proving queue is full ({max_depth} jobs waiting)")]
+pub struct QueueFull {
+    pub max_depth: usize,
+}
+
+struct Entry<T> {
+    priority: Priority,
+    seq: u64,
+    job: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    // Max-heap: higher priority first, then lower sequence number (older) first
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+struct Inner<T> {
+    heap: BinaryHeap<Entry<T>>,
+    /// Slots handed out by `reserve` but not yet filled
+    reserved: usize,
+    next_seq: u64,
+    closed: bool,
+}
+
+/// Bounded priority queue feeding the proving workers
+pub struct JobQueue<T> {
+    inner: Mutex<Inner<T>>,
+    notify: Notify,
+    max_depth: usize,
+}
+
+impl<T> JobQueue<T> {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                heap: BinaryHeap::new(),
+                reserved: 0,
+                next_seq: 0,
+                closed: false,
+            }),
+            notify: Notify::new(),
+            max_depth,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner<T>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Jobs waiting for a worker, including reserved slots
+    pub fn len(&self) -> usize {
+        let inner = self.lock();
+        inner.heap.len() + inner.reserved
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Claim a place in the queue before the job is ready to submit, so that
+    /// callers can persist the job knowing it will be accepted
+    pub fn reserve(self: &Arc<Self>) -> Result<Reservation<T>, QueueFull> {
+        let mut inner = self.lock();
+        if inner.heap.len() + inner.reserved >= self.max_depth {
+            return Err(QueueFull { max_depth: self.max_depth });
+        }
+        inner.reserved += 1;
+        Ok(Reservation { queue: Some(Arc::clone(self)) })
+    }
+
+    /// Submit a job, or fail if the queue is full
+    pub fn push(self: &Arc<Self>, job: T, priority: Priority) -> Result<(), QueueFull> {
+        self.reserve()?.push(job, priority);
+        Ok(())
+    }
+
+    /// Wait for the next job; `None` once the queue is closed and drained
+    pub async fn pop(&self) -> Option<T> {
+        loop {
+            // Register interest before checking, so a concurrent push or close is not missed
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut inner = self.lock();
+                if let Some(entry) = inner.heap.pop() {
+                    return Some(entry.job);
+                }
+                if inner.closed {
+                    return None;
+                }
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Take the first waiting job matching `f` out of the queue
+    pub fn remove(&self, mut f: impl FnMut(&T) -> bool) -> Option<T> {
+        let mut inner = self.lock();
+        let mut removed = None;
+        let remaining: Vec<Entry<T>> = std::mem::take(&mut inner.heap)
+            .into_vec()
+            .into_iter()
+            .filter_map(|entry| {
+                if removed.is_none() && f(&entry.job) {
+                    removed = Some(entry.job);
+                    None
+                } else {
+                    Some(entry)
+                }
+            })
+            .collect();
+        inner.heap = remaining.into();
+        removed
+    }
+
+    /// Stop the workers once the remaining jobs are done
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.notify.notify_waiters();
+    }
+}
+
+/// A reserved queue slot; released if dropped without pushing
+pub struct Reservation<T> {
+    queue: Option<Arc<JobQueue<T>>>,
+}
+
+impl<T> Reservation<T> {
+    pub fn push(mut self, job: T, priority: Priority) {
+        let queue = self.queue.take().expect("reservation already used");
+        {
+            let mut inner = queue.lock();
+            inner.reserved -= 1;
+            let seq = inner.next_seq;
+            inner.next_seq += 1;
+            inner.heap.push(Entry { priority, seq, job });
+        }
+        queue.notify.notify_one();
+    }
+}
+
+impl<T> Drop for Reservation<T> {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.lock().reserved -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_priority_then_fifo_order() {
+        let queue = Arc::new(JobQueue::new(10));
+        queue.push("low", Priority::Low).unwrap();
+        queue.push("normal-1", Priority::Normal).unwrap();
+        queue.push("high", Priority::High).unwrap();
+        queue.push("normal-2", Priority::Normal).unwrap();
+
+        let mut order = Vec::new();
+        while !queue.is_empty() {
+            order.push(queue.pop().await.unwrap());
+        }
+        assert_eq!(order, ["high", "normal-1", "normal-2", "low"]);
+    }
+
+    #[tokio::test]
+    async fn test_remove() {
+        let queue = Arc::new(JobQueue::new(10));
+        for job in 1..=3 {
+            queue.push(job, Priority::Normal).unwrap();
+        }
+        assert_eq!(queue.remove(|job| *job == 2), Some(2));
+        assert_eq!(queue.remove(|job| *job == 2), None);
+        assert_eq!(queue.pop().await, Some(1));
+        assert_eq!(queue.pop().await, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_when_full() {
+        let queue = Arc::new(JobQueue::new(2));
+        queue.push(1, Priority::Normal).unwrap();
+        let reservation = queue.reserve().unwrap();
+        assert_eq!(queue.push(3, Priority::High).unwrap_err().max_depth, 2);
+
+        // An abandoned reservation frees its slot
+        drop(reservation);
+        queue.push(3, Priority::High).unwrap();
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pop_waits_for_push_and_close() {
+        let queue = Arc::new(JobQueue::new(1));
+
+        let consumer = {
+            let queue = Arc::clone(&queue);
+            tokio::spawn(async move { (queue.pop().await, queue.pop().await) })
+        };
+        tokio::task::yield_now().await;
+        queue.push(7, Priority::Normal).unwrap();
+        // Closing does not drop jobs that are still waiting
+        queue.close();
+
+        assert_eq!(consumer.await.unwrap(), (Some(7), None));
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+use std::str::FromStr;
+use bech32::{hrp, Fe32, Hrp};
+use serde::{Deserialize, Serialize};
+
+use crate::bitcoin::ScriptPubKey;
+
+/// Scripts larger than this can never be spent (consensus `MAX_SCRIPT_SIZE`)
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
+
+const OP_0: u8 = 0x00;
+const OP_PUSHBYTES_20: u8 = 0x14;
+const OP_PUSHBYTES_32: u8 = 0x20;
+const OP_PUSHBYTES_33: u8 = 0x21;
+const OP_PUSHBYTES_65: u8 = 0x41;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_RETURN: u8 = 0x6a;
+const OP_DUP: u8 = 0x76;
+const OP_EQUAL: u8 = 0x87;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_HASH160: u8 = 0xa9;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKMULTISIG: u8 = 0xae;
+
+/// Bitcoin network an address is rendered for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+    Signet,
+    Regtest,
+}
+
+impl Network {
+    fn p2pkh_prefix(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            _ => 0x6f,
+        }
+    }
+
+    fn p2sh_prefix(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x05,
+            _ => 0xc4,
+        }
+    }
+
+    fn hrp(self) -> Hrp {
+        match self {
+            Network::Mainnet => hrp::BC,
+            Network::Testnet | Network::Signet => hrp::TB,
+            Network::Regtest => hrp::BCRT,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Signet => "signet",
+            Network::Regtest => "regtest",
+        })
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" | "bitcoin" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            "signet" => Ok(Network::Signet),
+            "regtest" => Ok(Network::Regtest),
+            other => Err(format!("unknown network '{}', expected mainnet, testnet, signet or regtest", other)),
+        }
+    }
+}
+
+/// Standard output templates, as recognised by Bitcoin Core's `Solver`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptTemplate {
+    /// `<pubkey> OP_CHECKSIG`
+    P2pk { pubkey: Vec<u8> },
+    /// `OP_DUP OP_HASH160 <20 bytes> OP_EQUALVERIFY OP_CHECKSIG`
+    P2pkh { pubkey_hash: [u8; 20] },
+    /// `OP_HASH160 <20 bytes> OP_EQUAL`
+    P2sh { script_hash: [u8; 20] },
+    /// `OP_0 <20 bytes>`
+    P2wpkh { pubkey_hash: [u8; 20] },
+    /// `OP_0 <32 bytes>`
+    P2wsh { script_hash: [u8; 32] },
+    /// `OP_1 <32 bytes>`
+    P2tr { output_key: [u8; 32] },
+    /// Witness versions 1 to 16 not otherwise defined, reserved for future soft forks
+    WitnessUnknown { version: u8, program: Vec<u8> },
+    /// `OP_m <pubkey>... OP_n OP_CHECKMULTISIG`
+    Multisig { required: u8, pubkeys: Vec<Vec<u8>> },
+    /// `OP_RETURN ...`, provably unspendable
+    NullData,
+    NonStandard,
+}
+
+impl ScriptTemplate {
+    /// Short name, as used in the API
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScriptTemplate::P2pk { .. } => "p2pk",
+            ScriptTemplate::P2pkh { .. } => "p2pkh",
+            ScriptTemplate::P2sh { .. } => "p2sh",
+            ScriptTemplate::P2wpkh { .. } => "p2wpkh",
+            ScriptTemplate::P2wsh { .. } => "p2wsh",
+            ScriptTemplate::P2tr { .. } => "p2tr",
+            ScriptTemplate::WitnessUnknown { .. } => "witness_unknown",
+            ScriptTemplate::Multisig { .. } => "multisig",
+            ScriptTemplate::NullData => "nulldata",
+            ScriptTemplate::NonStandard => "nonstandard",
+        }
+    }
+
+    /// Address paying to this template on `network`. Bare public keys,
+    /// multisig and non-standard scripts have none.
+    pub fn address(&self, network: Network) -> Option<String> {
+        let base58 = |prefix: u8, hash: &[u8; 20]| {
+            let mut payload = vec![prefix];
+            payload.extend_from_slice(hash);
+            bs58::encode(payload).with_check().into_string()
+        };
+        let segwit = |version: u8, program: &[u8]| {
+            let version = Fe32::try_from(version).ok()?;
+            bech32::segwit::encode(network.hrp(), version, program).ok()
+        };
+
+        match self {
+            ScriptTemplate::P2pkh { pubkey_hash } => Some(base58(network.p2pkh_prefix(), pubkey_hash)),
+            ScriptTemplate::P2sh { script_hash } => Some(base58(network.p2sh_prefix(), script_hash)),
+            ScriptTemplate::P2wpkh { pubkey_hash } => segwit(0, pubkey_hash),
+            ScriptTemplate::P2wsh { script_hash } => segwit(0, script_hash),
+            ScriptTemplate::P2tr { output_key } => segwit(1, output_key),
+            ScriptTemplate::WitnessUnknown { version, program } => segwit(*version, program),
+            ScriptTemplate::P2pk { .. }
+            | ScriptTemplate::Multisig { .. }
+            | ScriptTemplate::NullData
+            | ScriptTemplate::NonStandard => None,
+        }
+    }
+}
+
+impl fmt::Display for ScriptTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl ScriptPubKey {
+    /// The standard template this script follows
+    pub fn template(&self) -> ScriptTemplate {
+        let script = self.as_bytes();
+        match script {
+            [OP_DUP, OP_HASH160, OP_PUSHBYTES_20, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG] if hash.len() == 20 => {
+                ScriptTemplate::P2pkh { pubkey_hash: hash.try_into().unwrap() }
+            }
+            [OP_HASH160, OP_PUSHBYTES_20, hash @ .., OP_EQUAL] if hash.len() == 20 => {
+                ScriptTemplate::P2sh { script_hash: hash.try_into().unwrap() }
+            }
+            [OP_0, OP_PUSHBYTES_20, hash @ ..] if hash.len() == 20 => {
+                ScriptTemplate::P2wpkh { pubkey_hash: hash.try_into().unwrap() }
+            }
+            [OP_0, OP_PUSHBYTES_32, hash @ ..] if hash.len() == 32 => {
+                ScriptTemplate::P2wsh { script_hash: hash.try_into().unwrap() }
+            }
+            [OP_1, OP_PUSHBYTES_32, key @ ..] if key.len() == 32 => {
+                ScriptTemplate::P2tr { output_key: key.try_into().unwrap() }
+            }
+            [version @ OP_1..=OP_16, len, program @ ..] if (2..=40).contains(len) && program.len() == *len as usize => {
+                ScriptTemplate::WitnessUnknown { version: version - OP_1 + 1, program: program.to_vec() }
+            }
+            [OP_RETURN, ..] => ScriptTemplate::NullData,
+            [len @ (OP_PUSHBYTES_33 | OP_PUSHBYTES_65), pubkey @ .., OP_CHECKSIG] if pubkey.len() == *len as usize => {
+                if is_pubkey(pubkey) {
+                    ScriptTemplate::P2pk { pubkey: pubkey.to_vec() }
+                } else {
+                    ScriptTemplate::NonStandard
+                }
+            }
+            [m @ OP_1..=OP_16, .., n @ OP_1..=OP_16, OP_CHECKMULTISIG] => multisig(*m, *n, &script[1..script.len() - 2]),
+            _ => ScriptTemplate::NonStandard,
+        }
+    }
+
+    /// Whether any input could ever spend an output with this script.
+    /// `OP_RETURN` outputs and oversized scripts never can.
+    pub fn is_spendable(&self) -> bool {
+        self.len() <= MAX_SCRIPT_SIZE && self.template() != ScriptTemplate::NullData
+    }
+
+    /// Shorthand for `template().address(network)`
+    pub fn address(&self, network: Network) -> Option<String> {
+        self.template().address(network)
+    }
+}
+
+/// Compressed or uncompressed SEC1 public key, by its prefix and length
+fn is_pubkey(key: &[u8]) -> bool {
+    matches!((key.len(), key.first()), (33, Some(0x02 | 0x03)) | (65, Some(0x04)))
+}
+
+/// `pushes` holds the public key pushes between `OP_m` and `OP_n`
+fn multisig(m: u8, n: u8, mut pushes: &[u8]) -> ScriptTemplate {
+    let (required, total) = (m - OP_1 + 1, n - OP_1 + 1);
+    let mut pubkeys = Vec::new();
+    while let [len @ (OP_PUSHBYTES_33 | OP_PUSHBYTES_65), rest @ ..] = pushes {
+        let len = *len as usize;
+        if rest.len() < len || !is_pubkey(&rest[..len]) {
+            return ScriptTemplate::NonStandard;
+        }
+        pubkeys.push(rest[..len].to_vec());
+        pushes = &rest[len..];
+    }
+    if !pushes.is_empty() || pubkeys.len() != total as usize || required > total {
+        return ScriptTemplate::NonStandard;
+    }
+    ScriptTemplate::Multisig { required, pubkeys }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(hex: &str) -> ScriptPubKey {
+        hex.parse().unwrap()
+    }
+
+    #[test]
+    fn test_p2pkh_and_p2sh_addresses() {
+        // From demo-utxos.json
+        let p2pkh = script("76a914044f70e664eb08b6efe9c8daebb85364a16dd59b88ac");
+        assert_eq!(p2pkh.template().name(), "p2pkh");
+        assert_eq!(p2pkh.address(Network::Mainnet).unwrap(), "1PnsRhYv3mYGe7EKuURACUzjRabcHuaRT");
+        assert_eq!(p2pkh.address(Network::Testnet).unwrap(), "mfukAUnXj5Co3kar3USnz7hKbRBJb9Sgaj");
+        assert_eq!(p2pkh.address(Network::Regtest), p2pkh.address(Network::Signet));
+
+        let p2sh = script("a914e9c3dd0c07aac76179ebc76a6c78d4d67c6c160a87");
+        assert_eq!(p2sh.template().name(), "p2sh");
+        assert_eq!(p2sh.address(Network::Mainnet).unwrap(), "3P14159f73E4gFr7JterCCQh9QjiTjiZrG");
+        assert_eq!(p2sh.address(Network::Testnet).unwrap(), "2NEZG4p5giVjQt3Uez2Gip9PxMkwtF1Wdi9");
+    }
+
+    #[test]
+    fn test_segwit_addresses() {
+        // BIP 173 and BIP 350 test vectors
+        let p2wpkh = script("0014751e76e8199196d454941c45d1b3a323f1433bd6");
+        assert_eq!(p2wpkh.template().name(), "p2wpkh");
+        assert_eq!(p2wpkh.address(Network::Mainnet).unwrap(), "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        assert_eq!(p2wpkh.address(Network::Testnet).unwrap(), "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx");
+        assert_eq!(p2wpkh.address(Network::Signet), p2wpkh.address(Network::Testnet));
+        assert_eq!(p2wpkh.address(Network::Regtest).unwrap(), "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080");
+
+        let p2wsh = script("00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262");
+        assert_eq!(p2wsh.template().name(), "p2wsh");
+        assert_eq!(
+            p2wsh.address(Network::Testnet).unwrap(),
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7"
+        );
+
+        let p2tr = script("512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+        assert_eq!(p2tr.template().name(), "p2tr");
+        assert_eq!(
+            p2tr.address(Network::Mainnet).unwrap(),
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0"
+        );
+
+        let future = script("6002751e");
+        assert_eq!(future.template(), ScriptTemplate::WitnessUnknown { version: 16, program: vec![0x75, 0x1e] });
+        assert_eq!(future.address(Network::Mainnet).unwrap(), "bc1sw50qgdz25j");
+
+        // Version 0 programs must be 20 or 32 bytes
+        assert_eq!(script("0010751e76e8199196d454941c45d1b3a323").template(), ScriptTemplate::NonStandard);
+    }
+
+    #[test]
+    fn test_bare_scripts_have_no_address() {
+        let pubkey = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let p2pk = script(&format!("21{}ac", pubkey));
+        assert_eq!(p2pk.template(), ScriptTemplate::P2pk { pubkey: hex::decode(pubkey).unwrap() });
+        assert_eq!(p2pk.address(Network::Mainnet), None);
+
+        let multisig = script(&format!("5121{}21{}52ae", pubkey, pubkey));
+        match multisig.template() {
+            ScriptTemplate::Multisig { required, pubkeys } => {
+                assert_eq!(required, 1);
+                assert_eq!(pubkeys.len(), 2);
+            }
+            other => panic!("expected multisig, got {:?}", other),
+        }
+        assert_eq!(multisig.address(Network::Mainnet), None);
+
+        // Key count must match OP_n, and m cannot exceed n
+        assert_eq!(script(&format!("5121{}53ae", pubkey)).template(), ScriptTemplate::NonStandard);
+        assert_eq!(script(&format!("5221{}51ae", pubkey)).template(), ScriptTemplate::NonStandard);
+        // Not a public key
+        assert_eq!(script(&format!("21{}ac", "05".repeat(33))).template(), ScriptTemplate::NonStandard);
+    }
+
+    #[test]
+    fn test_unspendable_scripts() {
+        let op_return = script("6a0b68656c6c6f20776f726c64");
+        assert_eq!(op_return.template(), ScriptTemplate::NullData);
+        assert!(!op_return.is_spendable());
+        assert!(!ScriptPubKey::from_bytes(vec![OP_1; MAX_SCRIPT_SIZE + 1]).is_spendable());
+
+        assert!(script("76a914044f70e664eb08b6efe9c8daebb85364a16dd59b88ac").is_spendable());
+        assert!(script("51").is_spendable());
+        assert_eq!(script("51").template(), ScriptTemplate::NonStandard);
+    }
+
+    #[test]
+    fn test_network_parse() {
+        assert_eq!("signet".parse::<Network>().unwrap(), Network::Signet);
+        assert_eq!(serde_json::to_value(Network::Regtest).unwrap(), "regtest");
+        assert!("mainnet2".parse::<Network>().is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::program::Felt;
+
+/// Result of checking a STARK proof against its embedded public input
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StarkVerdict {
+    pub is_valid: bool,
+    /// Why the proof was rejected, if it was
+    pub reason: Option<String>,
+    /// Output segment of the proven program's public memory, when the backend exposes it
+    pub output: Option<Vec<Felt>>,
+}
+
+impl StarkVerdict {
+    pub fn valid() -> Self {
+        Self {
+            is_valid: true,
+            reason: None,
+            output: None,
+        }
+    }
+
+    pub fn invalid(reason: impl Into<String>) -> Self {
+        Self {
+            is_valid: false,
+            reason: Some(reason.into()),
+            output: None,
+        }
+    }
+
+    pub fn with_output(mut self, output: Vec<Felt>) -> Self {
+        self.output = Some(output);
+        self
+    }
+}
+
+/// A STARK verification backend.
+///
+/// Implementations are synchronous and CPU-bound; `ProofVerifier` runs them on
+/// the blocking thread pool. An `Err` means the proof could not be decoded at
+/// all, while a decoded proof that fails verification yields an invalid verdict.
+pub trait StarkVerifier: Send + Sync {
+    /// Short backend name, reported by `/health`
+    fn name(&self) -> &'static str;
+
+    fn verify(&self, proof: &str) -> Result<StarkVerdict>;
+}
+
+/// Accepts the proofs produced by the mock prover (anything prefixed with `mock_proof_`).
+/// Only meant for tests and local development.
+#[derive(Debug, Default, Clone)]
+pub struct MockStarkVerifier;
+
+impl StarkVerifier for MockStarkVerifier {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn verify(&self, proof: &str) -> Result<StarkVerdict> {
+        if proof.starts_with("mock_proof_") {
+            Ok(StarkVerdict::valid())
+        } else {
+            Ok(StarkVerdict::invalid("not a mock proof"))
+        }
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use anyhow::{Result, anyhow};
+
+use crate::auth::{ApiKey, Role};
+use crate::types::{FailureCategory, ProofData, ProofFailure, ProofStatus};
+
+/// Persistence for proof jobs and the API keys that submit them
+#[async_trait]
+pub trait ProofStore: Send + Sync {
+    /// Insert or replace a proof record
+    async fn save(&self, proof: &ProofData) -> Result<()>;
+
+    async fn get(&self, proof_id: Uuid) -> Result<Option<ProofData>>;
+
+    /// Insert an API key, indexed by the SHA-256 of its secret
+    async fn save_key(&self, key: &ApiKey, secret_hash: &str) -> Result<()>;
+
+    async fn find_key(&self, secret_hash: &str) -> Result<Option<ApiKey>>;
+
+    async fn get_key(&self, key_id: Uuid) -> Result<Option<ApiKey>>;
+
+    /// All keys, oldest first
+    async fn list_keys(&self) -> Result<Vec<ApiKey>>;
+
+    /// Mark a key revoked at `at`, unless it already is; `None` if there is no such key
+    async fn revoke_key(&self, key_id: Uuid, at: DateTime<Utc>) -> Result<Option<ApiKey>>;
+}
+
+/// Which `ProofStore` the server uses
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreConfig {
+    Memory,
+    Sqlite { path: PathBuf },
+}
+
+impl StoreConfig {
+    pub fn open(&self) -> Result<Arc<dyn ProofStore>> {
+        Ok(match self {
+            StoreConfig::Memory => Arc::new(MemoryProofStore::new()),
+            StoreConfig::Sqlite { path } => Arc::new(SqliteProofStore::open(path)?),
+        })
+    }
+}
+
+/// In-memory storage, lost on restart
+#[derive(Debug, Default)]
+pub struct MemoryProofStore {
+    proofs: Mutex<HashMap<Uuid, ProofData>>,
+    /// Keys by secret hash
+    keys: Mutex<HashMap<String, ApiKey>>,
+}
+
+impl MemoryProofStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ProofStore for MemoryProofStore {
+    async fn save(&self, proof: &ProofData) -> Result<()> {
+        self.proofs.lock().await.insert(proof.proof_id, proof.clone());
+        Ok(())
+    }
+
+    async fn get(&self, proof_id: Uuid) -> Result<Option<ProofData>> {
+        Ok(self.proofs.lock().await.get(&proof_id).cloned())
+    }
+
+    async fn save_key(&self, key: &ApiKey, secret_hash: &str) -> Result<()> {
+        self.keys.lock().await.insert(secret_hash.to_string(), key.clone());
+        Ok(())
+    }
+
+    async fn find_key(&self, secret_hash: &str) -> Result<Option<ApiKey>> {
+        Ok(self.keys.lock().await.get(secret_hash).cloned())
+    }
+
+    async fn get_key(&self, key_id: Uuid) -> Result<Option<ApiKey>> {
+        Ok(self.keys.lock().await.values().find(|key| key.key_id == key_id).cloned())
+    }
+
+    async fn list_keys(&self) -> Result<Vec<ApiKey>> {
+        let mut keys: Vec<ApiKey> = self.keys.lock().await.values().cloned().collect();
+        keys.sort_by_key(|key| key.created_at);
+        Ok(keys)
+    }
+
+    async fn revoke_key(&self, key_id: Uuid, at: DateTime<Utc>) -> Result<Option<ApiKey>> {
+        let mut keys = self.keys.lock().await;
+        Ok(keys.values_mut().find(|key| key.key_id == key_id).map(|key| {
+            key.revoked_at.get_or_insert(at);
+            key.clone()
+        }))
+    }
+}
+
+/// Schema migrations, applied in order and tracked with `PRAGMA user_version`
+const MIGRATIONS: &[&str] = &[
+    // 1: proofs keyed by ID; the full record is kept as JSON so new
+    // `ProofData` fields do not need a migration unless they are queried
+    "CREATE TABLE proofs (
+        proof_id   TEXT PRIMARY KEY NOT NULL,
+        status     TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        data       TEXT NOT NULL
+    );
+    CREATE INDEX proofs_status ON proofs (status);",
+    // 2: API keys; only the SHA-256 of each secret is stored
+    "CREATE TABLE api_keys (
+        key_id     TEXT PRIMARY KEY NOT NULL,
+        name       TEXT NOT NULL,
+        key_hash   TEXT NOT NULL UNIQUE,
+        created_at TEXT NOT NULL,
+        revoked_at TEXT
+    );",
+    // 3: key roles, comma-separated; keys issued before roles existed could submit proofs
+    "ALTER TABLE api_keys ADD COLUMN roles TEXT NOT NULL DEFAULT 'manager';",
+];
+
+/// Embedded SQLite storage that survives restarts
+pub struct SqliteProofStore {
+    conn: Arc<std::sync::Mutex<Connection>>,
+}
+
+impl SqliteProofStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .map_err(|e| anyhow!("Failed to open database {}: {}", path.display(), e))?;
+        Self::from_connection(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self> {
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        migrate(&mut conn)?;
+        fail_interrupted(&conn)?;
+        Ok(Self {
+            conn: Arc::new(std::sync::Mutex::new(conn)),
+        })
+    }
+
+    /// Run a query on the blocking pool
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|_| anyhow!("Database lock poisoned"))?;
+            f(&conn)
+        })
+        .await
+        .map_err(|e| anyhow!("Database task panicked: {}", e))?
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        return Err(anyhow!(
+            "Database schema version {} is newer than this build supports ({})",
+            version,
+            MIGRATIONS.len()
+        ));
+    }
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)
+            .map_err(|e| anyhow!("Migration {} failed: {}", i + 1, e))?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+        tracing::info!("Applied database migration {}", i + 1);
+    }
+    Ok(())
+}
+
+/// Fail the jobs a previous run left pending or in progress. Their workers
+/// are gone, so they would otherwise never finish.
+fn fail_interrupted(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT proof_id, data FROM proofs WHERE status IN ('Pending', 'InProgress')")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let now = Utc::now();
+    for (proof_id, data) in &rows {
+        let mut proof: ProofData = serde_json::from_str(data)
+            .map_err(|e| anyhow!("Corrupt proof record {}: {}", proof_id, e))?;
+        proof.status = ProofStatus::Failed;
+        proof.failure = Some(ProofFailure {
+            category: FailureCategory::Interrupted,
+            message: "Server restarted before the job finished".to_string(),
+            stage: proof.stage,
+            stderr_tail: None,
+        });
+        proof.finish_stage(now);
+        proof.completed_at = Some(now);
+        conn.execute(
+            "UPDATE proofs SET status = 'Failed', data = ?2 WHERE proof_id = ?1",
+            params![proof_id, serde_json::to_string(&proof)?],
+        )?;
+    }
+    if !rows.is_empty() {
+        tracing::warn!("Marked {} interrupted proof job(s) as failed", rows.len());
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl ProofStore for SqliteProofStore {
+    async fn save(&self, proof: &ProofData) -> Result<()> {
+        let proof_id = proof.proof_id.to_string();
+        let status = serde_json::to_value(&proof.status)?
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let created_at = proof.created_at.to_rfc3339();
+        let data = serde_json::to_string(proof)?;
+
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO proofs (proof_id, status, created_at, data) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (proof_id) DO UPDATE SET status = excluded.status, data = excluded.data",
+                params![proof_id, status, created_at, data],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get(&self, proof_id: Uuid) -> Result<Option<ProofData>> {
+        let data: Option<String> = self
+            .with_conn(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT data FROM proofs WHERE proof_id = ?1",
+                        params![proof_id.to_string()],
+                        |row| row.get(0),
+                    )
+                    .optional()?)
+            })
+            .await?;
+
+        data.map(|data| serde_json::from_str(&data).map_err(|e| anyhow!("Corrupt proof record {}: {}", proof_id, e)))
+            .transpose()
+    }
+
+    async fn save_key(&self, key: &ApiKey, secret_hash: &str) -> Result<()> {
+        let key = key.clone();
+        let secret_hash = secret_hash.to_string();
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO api_keys (key_id, name, key_hash, roles, created_at, revoked_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    key.key_id.to_string(),
+                    key.name,
+                    secret_hash,
+                    key.roles.iter().map(Role::as_str).collect::<Vec<_>>().join(","),
+                    // Fixed precision so the text sorts in time order
+                    key.created_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+                    key.revoked_at.map(|at| at.to_rfc3339_opts(SecondsFormat::Nanos, true)),
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn find_key(&self, secret_hash: &str) -> Result<Option<ApiKey>> {
+        let secret_hash = secret_hash.to_string();
+        self.with_conn(move |conn| {
+            conn.query_row(
+                "SELECT key_id, name, roles, created_at, revoked_at FROM api_keys WHERE key_hash = ?1",
+                params![secret_hash],
+                key_row,
+            )
+            .optional()?
+            .map(decode_key)
+            .transpose()
+        })
+        .await
+    }
+
+    async fn get_key(&self, key_id: Uuid) -> Result<Option<ApiKey>> {
+        self.with_conn(move |conn| {
+            conn.query_row(
+                "SELECT key_id, name, roles, created_at, revoked_at FROM api_keys WHERE key_id = ?1",
+                params![key_id.to_string()],
+                key_row,
+            )
+            .optional()?
+            .map(decode_key)
+            .transpose()
+        })
+        .await
+    }
+
+    async fn list_keys(&self) -> Result<Vec<ApiKey>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT key_id, name, roles, created_at, revoked_at FROM api_keys ORDER BY created_at, key_id",
+            )?;
+            let rows = stmt.query_map([], key_row)?;
+            rows.map(|row| decode_key(row?)).collect()
+        })
+        .await
+    }
+
+    async fn revoke_key(&self, key_id: Uuid, at: DateTime<Utc>) -> Result<Option<ApiKey>> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "UPDATE api_keys SET revoked_at = ?2 WHERE key_id = ?1 AND revoked_at IS NULL",
+                params![key_id.to_string(), at.to_rfc3339_opts(SecondsFormat::Nanos, true)],
+            )?;
+            conn.query_row(
+                "SELECT key_id, name, roles, created_at, revoked_at FROM api_keys WHERE key_id = ?1",
+                params![key_id.to_string()],
+                key_row,
+            )
+            .optional()?
+            .map(decode_key)
+            .transpose()
+        })
+        .await
+    }
+}
+
+/// `key_id, name, roles, created_at, revoked_at` as selected from `api_keys`
+type KeyRow = (String, String, String, String, Option<String>);
+
+fn key_row(row: &rusqlite::Row) -> rusqlite::Result<KeyRow> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+}
+
+fn decode_key((key_id, name, roles, created_at, revoked_at): KeyRow) -> Result<ApiKey> {
+    let corrupt = |e: &dyn std::fmt::Display| anyhow!("Corrupt API key {}: {}", key_id, e);
+    let time = |s: &str| {
+        DateTime::parse_from_rfc3339(s)
+            .map(|at| at.with_timezone(&Utc))
+            .map_err(|e| corrupt(&e))
+    };
+    Ok(ApiKey {
+        key_id: Uuid::parse_str(&key_id).map_err(|e| corrupt(&e))?,
+        name,
+        roles: roles.split(',').map(|role| role.parse().map_err(|e: String| corrupt(&e))).collect::<Result<_>>()?,
+        created_at: time(&created_at)?,
+        revoked_at: revoked_at.as_deref().map(time).transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::Amount;
+
+    fn proof() -> ProofData {
+        ProofData {
+            proof_id: Uuid::new_v4(),
+            status: ProofStatus::Pending,
+            total_amount: None,
+            proof_artifact: None,
+            public_inputs: None,
+            created_at: Utc::now(),
+            completed_at: None,
+            stage: None,
+            stages: Vec::new(),
+            progress_percent: None,
+            failure: None,
+            created_by: None,
+            inputs: None,
+        }
+    }
+
+    async fn round_trip(store: &dyn ProofStore) {
+        let mut data = proof();
+        store.save(&data).await.unwrap();
+        assert_eq!(store.get(data.proof_id).await.unwrap().unwrap().status, ProofStatus::Pending);
+
+        data.status = ProofStatus::Completed;
+        data.total_amount = Some(Amount::from_sat(42).unwrap());
+        store.save(&data).await.unwrap();
+        let loaded = store.get(data.proof_id).await.unwrap().unwrap();
+        assert_eq!(loaded.status, ProofStatus::Completed);
+        assert_eq!(loaded.total_amount.map(Amount::to_sat), Some(42));
+
+        assert!(store.get(Uuid::new_v4()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store() {
+        round_trip(&MemoryProofStore::new()).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store() {
+        round_trip(&SqliteProofStore::open_in_memory().unwrap()).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("zkpoor-store-{}.db", Uuid::new_v4()));
+        let data = proof();
+
+        {
+            let store = SqliteProofStore::open(&path).unwrap();
+            store.save(&data).await.unwrap();
+        }
+
+        // Re-opening must not re-run migrations or lose data
+        let store = SqliteProofStore::open(&path).unwrap();
+        let loaded = store.get(data.proof_id).await.unwrap().unwrap();
+        assert_eq!(loaded.created_at, data.created_at);
+
+        // Nothing is left to run the job that was pending
+        assert_eq!(loaded.status, ProofStatus::Failed);
+        assert_eq!(loaded.failure.unwrap().category, FailureCategory::Interrupted);
+        assert!(loaded.completed_at.is_some());
+
+        drop(store);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
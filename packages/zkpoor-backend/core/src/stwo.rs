@@ -0,0 +1,249 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use cairo_lang_executable::executable::{EntryPointKind, Executable};
+use cairo_lang_runner::{build_hints_dict, Arg, CairoHintProcessor};
+use cairo_vm::{
+    cairo_run::{cairo_run_program, CairoRunConfig},
+    types::{layout_name::LayoutName, program::Program, relocatable::MaybeRelocatable},
+    Felt252,
+};
+use stwo_cairo_prover::{
+    air::{prove_cairo, verify_cairo, CairoProof, ProverConfig},
+    input::{plain::adapt_finished_runner, ProverInput},
+};
+use stwo_prover::core::vcs::blake2_merkle::{Blake2sMerkleChannel, Blake2sMerkleHasher};
+
+use crate::error::Error;
+use crate::output::PublicOutput;
+use crate::program::{Args, CairoSerialize, ChainDataFiles, Felt};
+use crate::prover::{run_blocking, ProgressReporter, ProverBackend, ProvingRequest};
+use crate::stark::{StarkVerdict, StarkVerifier};
+use crate::types::{ProofStage, ZkProof};
+
+/// Verifies stwo Cairo proofs (JSON-serialized `CairoProof<Blake2sMerkleHasher>`),
+/// the same way the wasm backend does in the browser.
+#[derive(Debug, Default, Clone)]
+pub struct StwoVerifier;
+
+impl StarkVerifier for StwoVerifier {
+    fn name(&self) -> &'static str {
+        "stwo"
+    }
+
+    fn verify(&self, proof: &str) -> Result<StarkVerdict> {
+        let cairo_proof: CairoProof<Blake2sMerkleHasher> = serde_json::from_str(proof)
+            .map_err(|e| anyhow!("Failed to deserialize proof: {}", e))?;
+
+        // Read the output segment before verification consumes the proof
+        let output = output_segment(&cairo_proof)?;
+
+        match verify_cairo::<Blake2sMerkleChannel>(cairo_proof) {
+            Ok(()) => Ok(StarkVerdict::valid().with_output(output)),
+            Err(e) => Ok(StarkVerdict::invalid(e.to_string())),
+        }
+    }
+}
+
+/// Values written to the output builtin, as claimed by the proof
+fn output_segment(proof: &CairoProof<Blake2sMerkleHasher>) -> Result<Vec<Felt>> {
+    proof
+        .claim
+        .public_data
+        .public_memory
+        .output
+        .iter()
+        .map(|(_, limbs)| felt_from_limbs(limbs))
+        .collect()
+}
+
+/// Public memory values are stored as eight little-endian u32 limbs
+fn felt_from_limbs(limbs: &[u32; 8]) -> Result<Felt> {
+    let bytes: Vec<u8> = limbs.iter().rev().flat_map(|limb| limb.to_be_bytes()).collect();
+    Felt::from_be_bytes(&bytes)
+}
+
+/// Runs the compiled zkpoor executable (`zkpoor.executable.json`) in cairo-vm
+/// with each job's `program::Args` and proves its execution with stwo, all
+/// inside the server process. There is no bootloader, so the proof's output
+/// is the program's bare `Result`.
+#[derive(Debug, Clone)]
+pub struct StwoProver {
+    executable_path: PathBuf,
+    chain: ChainDataFiles,
+}
+
+impl StwoProver {
+    pub fn new(executable_path: impl Into<PathBuf>, chain: ChainDataFiles) -> Self {
+        Self {
+            executable_path: executable_path.into(),
+            chain,
+        }
+    }
+
+    /// Run the standalone entry point with `args` as its single `Span<felt252>` argument
+    fn execute_blocking(executable: &Executable, args: &[Felt]) -> Result<ProverInput> {
+        let entrypoint = executable
+            .entrypoints
+            .iter()
+            .find(|entrypoint| matches!(entrypoint.kind, EntryPointKind::Standalone))
+            .ok_or_else(|| anyhow!("Executable has no standalone entry point"))?;
+
+        let data: Vec<MaybeRelocatable> = executable
+            .program
+            .bytecode
+            .iter()
+            .map(Felt252::from)
+            .map(MaybeRelocatable::from)
+            .collect();
+        let (hints, string_to_hint) = build_hints_dict(&executable.program.hints);
+        let program = Program::new_for_proof(
+            entrypoint.builtins.clone(),
+            data,
+            entrypoint.offset,
+            entrypoint.offset + 4,
+            hints,
+            Default::default(),
+            Default::default(),
+            vec![],
+            None,
+        )
+        .map_err(|e| anyhow!("Invalid executable: {}", e))?;
+
+        let user_args = args
+            .iter()
+            .map(|felt| Arg::Value(Felt252::from_bytes_be(&felt.to_be_bytes())))
+            .collect();
+        let mut hint_processor = CairoHintProcessor {
+            runner: None,
+            user_args: vec![vec![Arg::Array(user_args)]],
+            string_to_hint,
+            starknet_state: Default::default(),
+            run_resources: Default::default(),
+            syscalls_used_resources: Default::default(),
+            no_temporary_segments: false,
+            markers: Default::default(),
+            panic_traceback: Default::default(),
+        };
+
+        let config = CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            layout: LayoutName::all_cairo_stwo,
+            proof_mode: true,
+            disable_trace_padding: true,
+            allow_missing_builtins: Some(true),
+            ..Default::default()
+        };
+        let runner = cairo_run_program(&program, &config, &mut hint_processor)
+            .map_err(|e| anyhow!("Cairo execution failed: {}", e))?;
+
+        adapt_finished_runner(runner, false).map_err(|e| anyhow!("Failed to adapt Cairo runner: {}", e))
+    }
+
+    fn prove_blocking(input: ProverInput) -> Result<CairoProof<Blake2sMerkleHasher>> {
+        prove_cairo::<Blake2sMerkleChannel>(input, ProverConfig::default())
+            .map_err(|e| anyhow!("Proving failed: {}", e))
+    }
+}
+
+#[async_trait]
+impl ProverBackend for StwoProver {
+    fn name(&self) -> &'static str {
+        "stwo"
+    }
+
+    fn max_utxos(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn check_available(&self) -> Result<(), Error> {
+        if self.executable_path.is_file() {
+            return Ok(());
+        }
+        Err(Error::BackendUnavailable {
+            backend: self.name().to_string(),
+            reason: format!("Executable {} not found", self.executable_path.display()),
+        })
+    }
+
+    async fn prove(&self, request: &ProvingRequest, progress: &dyn ProgressReporter) -> Result<ZkProof> {
+        let [utxo] = request.utxos.as_slice() else {
+            return Err(anyhow!("The stwo prover proves one UTXO per job, got {}", request.utxos.len()));
+        };
+
+        let raw = tokio::fs::read(&self.executable_path).await.map_err(|e| Error::BackendUnavailable {
+            backend: self.name().to_string(),
+            reason: format!("Failed to read executable {}: {}", self.executable_path.display(), e),
+        })?;
+        let executable: Executable = serde_json::from_slice(&raw)
+            .map_err(|e| anyhow!("Invalid executable {}: {}", self.executable_path.display(), e))?;
+
+        progress.enter(ProofStage::FetchingBlockData).await;
+        let chain = self.chain.load().await?;
+        progress.enter(ProofStage::BuildingArgs).await;
+        let args = Args::new(&chain, utxo)?.to_felts();
+
+        // Trace generation and proving are CPU-bound and cannot be interrupted: a
+        // cancelled job's work runs to completion, holding its worker until then.
+        // Use `ToolchainProver` to kill proving outright.
+        progress.enter(ProofStage::Executing).await;
+        let input = run_blocking(move || Self::execute_blocking(&executable, &args)).await?;
+
+        progress.enter(ProofStage::Proving).await;
+        let proof = run_blocking(move || Self::prove_blocking(input)).await?;
+
+        // Report what the run wrote to its output segment, not what was requested
+        let public_output = PublicOutput::decode(&output_segment(&proof)?)?;
+        let public_inputs = serde_json::json!({
+            "target_utxo": utxo.outpoint().to_string(),
+            "public_output": public_output,
+        });
+
+        Ok(ZkProof {
+            proof: serde_json::to_string(&proof)?,
+            public_inputs,
+            total_amount: request.total_amount,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cairo_vm::cairo_run::cairo_run;
+    use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+
+    /// Proof-mode Cairo 0 program the wasm backend's tests prove in the browser
+    const FIBONACCI: &[u8] = include_bytes!("../../../../wasm-backend/tests/fibonacci_1000.json");
+
+    fn fibonacci_proof() -> CairoProof<Blake2sMerkleHasher> {
+        let config = CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            layout: LayoutName::all_cairo_stwo,
+            proof_mode: true,
+            disable_trace_padding: true,
+            ..Default::default()
+        };
+        let runner = cairo_run(FIBONACCI, &config, &mut BuiltinHintProcessor::new_empty()).unwrap();
+        let input = adapt_finished_runner(runner, false).unwrap();
+        prove_cairo::<Blake2sMerkleChannel>(input, ProverConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn test_verifies_real_proof() {
+        let proof = fibonacci_proof();
+        let verdict = StwoVerifier.verify(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert!(verdict.is_valid, "{:?}", verdict.reason);
+
+        // A proof whose claimed program differs from the one it proves is rejected
+        let mut tampered = proof;
+        tampered.claim.public_data.public_memory.program[0].1[0] ^= 1;
+        let verdict = StwoVerifier.verify(&serde_json::to_string(&tampered).unwrap()).unwrap();
+        assert!(!verdict.is_valid);
+
+        assert!(StwoVerifier.verify("{}").is_err());
+    }
+}
@@ -0,0 +1,717 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::process::Command;
+use anyhow::{Result, anyhow};
+
+use crate::error::Error;
+use crate::process::{find_program, spawn_group, JobDir};
+use crate::program::{Args, ChainDataFiles};
+use crate::prover::{ProgressReporter, ProverBackend, ProvingRequest};
+use crate::types::{ProofStage, ZkProof};
+
+/// A step of the Cairo proving pipeline (mirrors `program/scripts/prove_pow.py`)
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ToolchainStep {
+    ProgramInput,
+    CairoRunner,
+    CairoExecute,
+    Prove,
+}
+
+impl fmt::Display for ToolchainStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ToolchainStep::ProgramInput => "PROGRAM_INPUT",
+            ToolchainStep::CairoRunner => "CAIRO_RUNNER",
+            ToolchainStep::CairoExecute => "CAIRO_EXECUTE",
+            ToolchainStep::Prove => "PROVE",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Outcome and resource usage of one child process
+#[derive(Debug, Clone, Serialize)]
+pub struct StepInfo {
+    pub step: ToolchainStep,
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` if the process was killed (e.g. on timeout)
+    pub exit_code: Option<i32>,
+    pub elapsed_secs: f64,
+    /// Peak resident set size, only known when a `time` wrapper is configured
+    pub max_memory_kb: Option<u64>,
+    pub timed_out: bool,
+}
+
+impl StepInfo {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Time and memory of a step, kept with the job after its logs are removed
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StepStats {
+    pub step: ToolchainStep,
+    pub elapsed_secs: f64,
+    pub max_memory_kb: Option<u64>,
+}
+
+impl From<&StepInfo> for StepStats {
+    fn from(info: &StepInfo) -> Self {
+        Self { step: info.step, elapsed_secs: info.elapsed_secs, max_memory_kb: info.max_memory_kb }
+    }
+}
+
+/// Error raised when a pipeline step fails, carrying every step run so far
+#[derive(Debug, thiserror::Error)]
+#[error("{step} step failed: {message}")]
+pub struct ToolchainError {
+    pub step: ToolchainStep,
+    pub message: String,
+    pub steps: Vec<StepInfo>,
+}
+
+/// Per-step wall-clock limits
+#[derive(Debug, Clone)]
+pub struct StepTimeouts {
+    pub cairo_runner: Duration,
+    pub cairo_execute: Duration,
+    pub prove: Duration,
+}
+
+impl Default for StepTimeouts {
+    fn default() -> Self {
+        Self {
+            cairo_runner: Duration::from_secs(30 * 60),
+            cairo_execute: Duration::from_secs(10 * 60),
+            prove: Duration::from_secs(2 * 60 * 60),
+        }
+    }
+}
+
+/// GNU `time`, used to measure peak memory when it is installed
+pub const DEFAULT_TIME_BINARY: &str = "/usr/bin/time";
+
+/// Binary and artifact locations for the external Cairo toolchain
+#[derive(Debug, Clone)]
+pub struct ToolchainConfig {
+    pub cairo_program_runner: PathBuf,
+    pub cairo_execute: PathBuf,
+    pub adapted_stwo: PathBuf,
+    /// Compiled simple bootloader passed to `cairo_program_runner`
+    pub bootloader: PathBuf,
+    /// Compiled zkpoor executable (`zkpoor.executable.json`)
+    pub executable: PathBuf,
+    /// Prover parameters passed to `adapted_stwo --params_json`
+    pub prover_params: PathBuf,
+    pub layout: String,
+    /// `adapted_stwo --proof-format`: `json` is what `StwoVerifier` reads,
+    /// `cairo-serde` is what the recursive Cairo verifier reads
+    pub proof_format: String,
+    /// Optional GNU `time` used to measure peak memory (`time -v <cmd>`)
+    pub time_binary: Option<PathBuf>,
+    pub timeouts: StepTimeouts,
+}
+
+impl Default for ToolchainConfig {
+    fn default() -> Self {
+        let time_binary = PathBuf::from(DEFAULT_TIME_BINARY);
+        Self {
+            cairo_program_runner: PathBuf::from("cairo_program_runner"),
+            cairo_execute: PathBuf::from("cairo-execute"),
+            adapted_stwo: PathBuf::from("adapted_stwo"),
+            bootloader: PathBuf::from("bootloaders/simple_bootloader_compiled.json"),
+            executable: PathBuf::from("target/release/zkpoor.executable.json"),
+            prover_params: PathBuf::from("prover_params.json"),
+            layout: "all_cairo_stwo".to_string(),
+            proof_format: "json".to_string(),
+            time_binary: time_binary.exists().then_some(time_binary),
+            timeouts: StepTimeouts::default(),
+        }
+    }
+}
+
+/// Successful pipeline run
+#[derive(Debug, Clone)]
+pub struct ToolchainRun {
+    pub proof_path: PathBuf,
+    pub steps: Vec<StepInfo>,
+}
+
+/// Drives `cairo_program_runner` and `adapted_stwo` as child processes
+#[derive(Debug, Clone)]
+pub struct CairoToolchain {
+    config: ToolchainConfig,
+}
+
+impl CairoToolchain {
+    pub fn new(config: ToolchainConfig) -> Self {
+        Self { config }
+    }
+
+    /// The first pipeline binary that cannot be found. `cairo-execute` is
+    /// left out: it only re-runs a failed program for a readable error.
+    pub fn missing_binary(&self) -> Option<&Path> {
+        [&self.config.cairo_program_runner, &self.config.adapted_stwo]
+            .into_iter()
+            .map(PathBuf::as_path)
+            .find(|binary| !find_program(binary))
+    }
+
+    /// Prove the executable with the given arguments file, writing all
+    /// intermediate artifacts, step logs and `proof.json` into `job_dir`.
+    pub async fn prove(
+        &self,
+        job_dir: &Path,
+        arguments: &Path,
+        progress: &dyn ProgressReporter,
+    ) -> Result<ToolchainRun, ToolchainError> {
+        let mut steps = Vec::new();
+
+        let program_input = job_dir.join("program-input.json");
+        let priv_json = job_dir.join("priv.json");
+        let pub_json = job_dir.join("pub.json");
+        let trace_file = job_dir.join("trace.json");
+        let memory_file = job_dir.join("memory.json");
+        let resources_file = job_dir.join("resources.json");
+        let proof_path = job_dir.join("proof.json");
+
+        if let Err(e) = self.write_program_input(&program_input, arguments).await {
+            return Err(ToolchainError {
+                step: ToolchainStep::ProgramInput,
+                message: e.to_string(),
+                steps,
+            });
+        }
+
+        progress.enter(ProofStage::Executing).await;
+        let mut runner = Command::new(&self.config.cairo_program_runner);
+        runner
+            .arg("--program").arg(&self.config.bootloader)
+            .arg("--program_input").arg(&program_input)
+            .arg("--air_public_input").arg(&pub_json)
+            .arg("--air_private_input").arg(&priv_json)
+            .arg("--trace_file").arg(&trace_file)
+            .arg("--memory_file").arg(&memory_file)
+            .arg("--layout").arg(&self.config.layout)
+            .arg("--proof_mode")
+            .arg("--execution_resources_file").arg(&resources_file)
+            .arg("--disable_trace_padding")
+            .arg("--merge_extra_segments");
+        let info = self
+            .run_step(ToolchainStep::CairoRunner, runner, self.config.timeouts.cairo_runner, job_dir)
+            .await;
+        let runner_ok = info.succeeded();
+        steps.push(info);
+
+        if !runner_ok {
+            // The bootloader swallows Cairo panics; re-run with cairo-execute for a readable error
+            let mut execute = Command::new(&self.config.cairo_execute);
+            execute
+                .arg("--prebuilt")
+                .arg("--args-file").arg(arguments)
+                .arg("--output-path").arg(job_dir.join("output.txt"))
+                .arg("--layout").arg(&self.config.layout)
+                .arg(&self.config.executable);
+            let info = self
+                .run_step(ToolchainStep::CairoExecute, execute, self.config.timeouts.cairo_execute, job_dir)
+                .await;
+            let message = first_non_empty(&info.stderr, &info.stdout);
+            steps.push(info);
+            return Err(ToolchainError {
+                step: ToolchainStep::CairoRunner,
+                message,
+                steps,
+            });
+        }
+
+        progress.enter(ProofStage::Proving).await;
+        let mut prove = Command::new(&self.config.adapted_stwo);
+        prove
+            .arg("--priv_json").arg(&priv_json)
+            .arg("--pub_json").arg(&pub_json)
+            .arg("--params_json").arg(&self.config.prover_params)
+            .arg("--proof_path").arg(&proof_path)
+            .arg("--proof-format").arg(&self.config.proof_format)
+            .arg("--verify");
+        let info = self
+            .run_step(ToolchainStep::Prove, prove, self.config.timeouts.prove, job_dir)
+            .await;
+        let prove_ok = info.succeeded();
+        let message = first_non_empty(&info.stderr, &info.stdout);
+        steps.push(info);
+
+        if !prove_ok {
+            return Err(ToolchainError {
+                step: ToolchainStep::Prove,
+                message,
+                steps,
+            });
+        }
+
+        // Traces can be gigabytes; keep only the proof, public input and logs
+        for temp_file in [&program_input, &trace_file, &memory_file, &priv_json] {
+            let _ = tokio::fs::remove_file(temp_file).await;
+        }
+
+        Ok(ToolchainRun { proof_path, steps })
+    }
+
+    async fn write_program_input(&self, path: &Path, arguments: &Path) -> Result<()> {
+        let program_input = serde_json::json!({
+            "single_page": true,
+            "tasks": [{
+                "type": "Cairo1Executable",
+                "path": std::path::absolute(&self.config.executable)?,
+                "program_hash_function": "blake",
+                "user_args_file": std::path::absolute(arguments)?,
+            }]
+        });
+        tokio::fs::write(path, serde_json::to_vec_pretty(&program_input)?).await?;
+        Ok(())
+    }
+
+    async fn run_step(
+        &self,
+        step: ToolchainStep,
+        command: Command,
+        timeout: Duration,
+        job_dir: &Path,
+    ) -> StepInfo {
+        tracing::debug!("[{}] command: {:?}", step, command.as_std());
+        let info = run_measured(step, command, timeout, self.config.time_binary.as_deref()).await;
+        tracing::debug!(
+            "[{}] exit code: {:?}, time: {:.2} s, max memory: {:?} kB",
+            step, info.exit_code, info.elapsed_secs, info.max_memory_kb
+        );
+
+        if let Err(e) = save_step_log(job_dir, &info).await {
+            tracing::warn!("Failed to save {} log: {}", step, e);
+        }
+        info
+    }
+}
+
+fn first_non_empty(a: &str, b: &str) -> String {
+    if a.trim().is_empty() { b.trim().to_string() } else { a.trim().to_string() }
+}
+
+/// Run a child process with a timeout, capturing output and resource usage
+async fn run_measured(
+    step: ToolchainStep,
+    command: Command,
+    timeout: Duration,
+    time_binary: Option<&Path>,
+) -> StepInfo {
+    let std_command = command.as_std();
+    let mut command = match time_binary {
+        Some(time) => {
+            let mut wrapped = Command::new(time);
+            wrapped.arg("-v").arg(std_command.get_program()).args(std_command.get_args());
+            wrapped
+        }
+        None => command,
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let start = Instant::now();
+    let (child, group) = match spawn_group(&mut command) {
+        Ok(spawned) => spawned,
+        Err(e) => {
+            return StepInfo {
+                step,
+                stdout: String::new(),
+                stderr: format!("Failed to spawn {:?}: {}", command.as_std().get_program(), e),
+                exit_code: None,
+                elapsed_secs: start.elapsed().as_secs_f64(),
+                max_memory_kb: None,
+                timed_out: false,
+            };
+        }
+    };
+
+    // Dropping the future on timeout or cancellation kills the child's process group
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => {
+            group.disarm();
+            let raw_stderr = String::from_utf8_lossy(&output.stderr);
+            let (stderr, max_memory_kb) = match time_binary {
+                Some(_) => split_time_output(&raw_stderr),
+                None => (raw_stderr.into_owned(), None),
+            };
+            StepInfo {
+                step,
+                stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+                stderr,
+                exit_code: output.status.code(),
+                elapsed_secs: start.elapsed().as_secs_f64(),
+                max_memory_kb,
+                timed_out: false,
+            }
+        }
+        Ok(Err(e)) => StepInfo {
+            step,
+            stdout: String::new(),
+            stderr: format!("Failed to wait for process: {}", e),
+            exit_code: None,
+            elapsed_secs: start.elapsed().as_secs_f64(),
+            max_memory_kb: None,
+            timed_out: false,
+        },
+        Err(_) => StepInfo {
+            step,
+            stdout: String::new(),
+            stderr: format!("Process timed out after {} seconds", timeout.as_secs()),
+            exit_code: None,
+            elapsed_secs: start.elapsed().as_secs_f64(),
+            max_memory_kb: None,
+            timed_out: true,
+        },
+    }
+}
+
+/// Separate GNU `time -v` report lines from the command's own stderr
+fn split_time_output(stderr: &str) -> (String, Option<u64>) {
+    let mut max_memory_kb = None;
+    let mut actual = Vec::new();
+
+    for line in stderr.lines() {
+        if let Some(value) = line.trim().strip_prefix("Maximum resident set size (kbytes):") {
+            max_memory_kb = value.trim().parse().ok();
+        } else if !line.starts_with('\t') && !line.starts_with("Command exited with non-zero status") {
+            actual.push(line);
+        }
+    }
+
+    (actual.join("\n"), max_memory_kb)
+}
+
+async fn save_step_log(job_dir: &Path, info: &StepInfo) -> Result<()> {
+    let mut log = format!(
+        "=== {} STEP LOG ===\nTimestamp: {}\nReturn Code: {:?}\nExecution Time: {:.2} seconds\n",
+        info.step,
+        chrono::Utc::now().to_rfc3339(),
+        info.exit_code,
+        info.elapsed_secs
+    );
+    if let Some(kb) = info.max_memory_kb {
+        log.push_str(&format!("Max Memory Usage: {:.1} MB\n", kb as f64 / 1024.0));
+    }
+    log.push('\n');
+    if !info.stdout.is_empty() {
+        log.push_str(&format!("=== STDOUT ===\n{}\n", info.stdout));
+    }
+    if !info.stderr.is_empty() {
+        log.push_str(&format!("=== STDERR ===\n{}\n", info.stderr));
+    }
+
+    let path = job_dir.join(format!("{}.log", info.step.to_string().to_lowercase()));
+    tokio::fs::write(path, log).await?;
+    Ok(())
+}
+
+/// Proves with the external Cairo toolchain, one job directory per proof,
+/// removed with its traces and logs when the job ends. The program proves a
+/// single UTXO against the chain data, so each job builds its own arguments file.
+#[derive(Debug, Clone)]
+pub struct ToolchainProver {
+    toolchain: CairoToolchain,
+    chain: ChainDataFiles,
+    work_dir: PathBuf,
+}
+
+impl ToolchainProver {
+    pub fn new(config: ToolchainConfig, chain: ChainDataFiles, work_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            toolchain: CairoToolchain::new(config),
+            chain,
+            work_dir: work_dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ProverBackend for ToolchainProver {
+    fn name(&self) -> &'static str {
+        "toolchain"
+    }
+
+    fn max_utxos(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn check_available(&self) -> Result<(), Error> {
+        match self.toolchain.missing_binary() {
+            Some(binary) => Err(Error::BackendUnavailable {
+                backend: self.name().to_string(),
+                reason: format!("{} not found", binary.display()),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    async fn prove(&self, request: &ProvingRequest, progress: &dyn ProgressReporter) -> Result<ZkProof> {
+        let [utxo] = request.utxos.as_slice() else {
+            return Err(anyhow!("The toolchain prover proves one UTXO per job, got {}", request.utxos.len()));
+        };
+        // The binaries may have gone away since the job was queued
+        self.check_available()?;
+        let job_dir = JobDir::create(&self.work_dir).await?;
+
+        progress.enter(ProofStage::FetchingBlockData).await;
+        let chain = self.chain.load().await?;
+        progress.enter(ProofStage::BuildingArgs).await;
+        let arguments = job_dir.path().join("args.json");
+        Args::new(&chain, utxo)?.write_args_file(&arguments).await?;
+
+        let run = self.toolchain.prove(job_dir.path(), &arguments, progress).await?;
+        for info in &run.steps {
+            tracing::info!(
+                "[{}] time: {:.2} s, max memory: {}",
+                info.step,
+                info.elapsed_secs,
+                info.max_memory_kb.map_or("N/A".to_string(), |kb| format!("{:.1} MB", kb as f64 / 1024.0))
+            );
+        }
+
+        let proof = tokio::fs::read_to_string(&run.proof_path)
+            .await
+            .map_err(|e| anyhow!("Failed to read proof {}: {}", run.proof_path.display(), e))?;
+
+        // The program output holds the proven data; this only records what the program was run on
+        // and what it cost, since the job directory and its step logs are about to be removed
+        let steps: Vec<StepStats> = run.steps.iter().map(StepStats::from).collect();
+        let public_inputs = serde_json::json!({
+            "target_utxo": utxo.outpoint().to_string(),
+            "steps": steps,
+        });
+
+        Ok(ZkProof {
+            proof,
+            public_inputs,
+            total_amount: request.total_amount,
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use crate::prover::NoProgress;
+    use uuid::Uuid;
+
+    fn stub(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// Stub toolchain: the runner writes pub/priv inputs, the prover writes a canned proof
+    fn stub_config(dir: &Path, runner_body: &str, prove_body: &str) -> ToolchainConfig {
+        ToolchainConfig {
+            cairo_program_runner: stub(dir, "cairo_program_runner", runner_body),
+            cairo_execute: stub(dir, "cairo-execute", "echo \"panicked: Target UTXO is spent\" >&2; exit 1"),
+            adapted_stwo: stub(dir, "adapted_stwo", prove_body),
+            time_binary: None,
+            ..ToolchainConfig::default()
+        }
+    }
+
+    const RUNNER_OK: &str = "while [ $# -gt 0 ]; do case $1 in \
+        --air_public_input) echo '{}' > $2;; --air_private_input) echo '{}' > $2;; esac; shift; done";
+    /// Chain state and an empty block list, in `batch.json` form
+    const CHAIN_BATCH: &str = r#"{"chain_state": {"block_height": 1, "total_work": "2", "best_block_hash": "00000000000000000000a8e6a3a2a0e0ac2a0a37e1b18c8d4a04bc2d4aa5e9f1", "current_target": "3", "epoch_start_time": 4, "prev_timestamps": []}, "blocks": []}"#;
+    const PROVE_OK: &str = "while [ $# -gt 0 ]; do case $1 in \
+        --proof_path) echo '{\"stub\":true}' > $2;; esac; shift; done";
+
+    /// Records the stages reported by the pipeline
+    #[derive(Default)]
+    struct Stages(std::sync::Mutex<Vec<ProofStage>>);
+
+    #[async_trait]
+    impl ProgressReporter for Stages {
+        async fn enter(&self, stage: ProofStage) {
+            self.0.lock().unwrap().push(stage);
+        }
+    }
+
+    /// Notifies once the pipeline enters `Proving`
+    #[derive(Default)]
+    struct EnteredProving(tokio::sync::Notify);
+
+    #[async_trait]
+    impl ProgressReporter for EnteredProving {
+        async fn enter(&self, stage: ProofStage) {
+            if stage == ProofStage::Proving {
+                self.0.notify_one();
+            }
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zkpoor-toolchain-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_success() {
+        let dir = temp_dir();
+        let toolchain = CairoToolchain::new(stub_config(&dir, RUNNER_OK, PROVE_OK));
+        let args = dir.join("args.json");
+        std::fs::write(&args, "[]").unwrap();
+
+        let stages = Stages::default();
+        let run = toolchain.prove(&dir, &args, &stages).await.unwrap();
+        let steps: Vec<_> = run.steps.iter().map(|s| s.step).collect();
+        assert_eq!(steps, vec![ToolchainStep::CairoRunner, ToolchainStep::Prove]);
+        assert_eq!(*stages.0.lock().unwrap(), vec![ProofStage::Executing, ProofStage::Proving]);
+        assert_eq!(std::fs::read_to_string(&run.proof_path).unwrap().trim(), r#"{"stub":true}"#);
+        assert!(dir.join("prove.log").exists());
+        assert!(!dir.join("program-input.json").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_prover_builds_arguments_per_job() {
+        let dir = temp_dir();
+        let chain = ChainDataFiles::new(dir.join("batch.json"), dir.join("proof_serde.json"));
+        std::fs::write(&chain.batch, CHAIN_BATCH).unwrap();
+        std::fs::write(&chain.chain_state_proof, r#"["0x7"]"#).unwrap();
+        let work_dir = dir.join("jobs");
+        // The job directory is gone once the job ends, so the runner keeps a copy
+        // of the arguments next to its `--program_input`
+        let args_seen = dir.join("args-seen.json");
+        let runner = format!("cp \"$(dirname \"$4\")/args.json\" {}\n{}", args_seen.display(), RUNNER_OK);
+        let prover = ToolchainProver::new(stub_config(&dir, &runner, PROVE_OK), chain, &work_dir);
+        let utxo = crate::types::Utxo {
+            txid: "92902693c34c80da75da19f97bfb3719013883d8d307e88011a648363dd2f334".parse().unwrap(),
+            vout: 1,
+            amount: crate::bitcoin::Amount::from_sat(1000).unwrap(),
+            script_pubkey: "76a914044f70e664eb08b6efe9c8daebb85364a16dd59b88ac".parse().unwrap(),
+        };
+        let request = ProvingRequest { utxos: vec![utxo.clone()], total_amount: utxo.amount };
+
+        let stages = Stages::default();
+        let proof = prover.prove(&request, &stages).await.unwrap();
+        assert_eq!(proof.proof.trim(), r#"{"stub":true}"#);
+        assert_eq!(proof.public_inputs["target_utxo"], utxo.outpoint().to_string());
+        assert!(proof.public_inputs.get("total_amount").is_none());
+        // Step stats outlive the job directory
+        let steps = proof.public_inputs["steps"].as_array().unwrap();
+        let names: Vec<_> = steps.iter().map(|step| step["step"].as_str().unwrap()).collect();
+        assert_eq!(names, ["CAIRO_RUNNER", "PROVE"]);
+        assert!(steps.iter().all(|step| step["elapsed_secs"].as_f64().unwrap() >= 0.0));
+        assert!(steps.iter().all(|step| step["max_memory_kb"].is_null()));
+        assert_eq!(
+            *stages.0.lock().unwrap(),
+            vec![ProofStage::FetchingBlockData, ProofStage::BuildingArgs, ProofStage::Executing, ProofStage::Proving]
+        );
+
+        // The job's arguments end with its UTXO, then the chain state proof
+        let args: Vec<String> = serde_json::from_str(&std::fs::read_to_string(&args_seen).unwrap()).unwrap();
+        assert_eq!(args[args.len() - 3..], ["0x93269092", "0x1", "0x7"]);
+        assert_eq!(std::fs::read_dir(&work_dir).unwrap().count(), 0);
+
+        let two = ProvingRequest { utxos: vec![utxo.clone(), utxo], total_amount: request.total_amount };
+        assert!(prover.prove(&two, &NoProgress).await.is_err());
+
+        // A missing binary makes the backend unavailable, before any stage is entered
+        prover.check_available().unwrap();
+        let config = ToolchainConfig { adapted_stwo: dir.join("missing_stwo"), ..stub_config(&dir, RUNNER_OK, PROVE_OK) };
+        let prover = ToolchainProver::new(config, prover.chain.clone(), &work_dir);
+        assert!(matches!(prover.check_available(), Err(Error::BackendUnavailable { .. })));
+        let err = prover.prove(&request, &NoProgress).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<Error>(), Some(Error::BackendUnavailable { .. })));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_prover_removes_failed_and_cancelled_jobs() {
+        let dir = temp_dir();
+        let chain = ChainDataFiles::new(dir.join("batch.json"), dir.join("proof_serde.json"));
+        std::fs::write(&chain.batch, CHAIN_BATCH).unwrap();
+        std::fs::write(&chain.chain_state_proof, r#"["0x7"]"#).unwrap();
+        let work_dir = dir.join("jobs");
+        let utxo = crate::types::Utxo {
+            txid: "92902693c34c80da75da19f97bfb3719013883d8d307e88011a648363dd2f334".parse().unwrap(),
+            vout: 1,
+            amount: crate::bitcoin::Amount::from_sat(1000).unwrap(),
+            script_pubkey: "76a914044f70e664eb08b6efe9c8daebb85364a16dd59b88ac".parse().unwrap(),
+        };
+        let request = ProvingRequest { utxos: vec![utxo.clone()], total_amount: utxo.amount };
+
+        // The runner leaves a trace behind before failing
+        let failing = "touch \"$(dirname \"$4\")/trace.json\"; exit 3";
+        let prover = ToolchainProver::new(stub_config(&dir, failing, PROVE_OK), chain.clone(), &work_dir);
+        assert!(prover.prove(&request, &NoProgress).await.is_err());
+        assert_eq!(std::fs::read_dir(&work_dir).unwrap().count(), 0);
+
+        // Cancel by dropping the job once its prover is running
+        let prover = ToolchainProver::new(stub_config(&dir, RUNNER_OK, "sleep 30"), chain, &work_dir);
+        let proving = EnteredProving::default();
+        tokio::select! {
+            _ = prover.prove(&request, &proving) => panic!("prover should still be running"),
+            _ = proving.0.notified() => {}
+        }
+        assert_eq!(std::fs::read_dir(&work_dir).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_runner_failure_collects_execute_diagnostics() {
+        let dir = temp_dir();
+        let toolchain = CairoToolchain::new(stub_config(&dir, "echo boom >&2; exit 3", PROVE_OK));
+        let args = dir.join("args.json");
+        std::fs::write(&args, "[]").unwrap();
+
+        let err = toolchain.prove(&dir, &args, &NoProgress).await.unwrap_err();
+        assert_eq!(err.step, ToolchainStep::CairoRunner);
+        assert_eq!(err.steps.len(), 2);
+        assert_eq!(err.steps[0].exit_code, Some(3));
+        assert_eq!(err.steps[0].stderr.trim(), "boom");
+        assert!(err.message.contains("Target UTXO is spent"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_step_timeout() {
+        let dir = temp_dir();
+        let mut config = stub_config(&dir, RUNNER_OK, "sleep 10");
+        config.timeouts.prove = Duration::from_millis(200);
+        let toolchain = CairoToolchain::new(config);
+        let args = dir.join("args.json");
+        std::fs::write(&args, "[]").unwrap();
+
+        let err = toolchain.prove(&dir, &args, &NoProgress).await.unwrap_err();
+        assert_eq!(err.step, ToolchainStep::Prove);
+        assert!(err.steps[1].timed_out);
+        assert!(err.steps[1].elapsed_secs < 5.0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_time_output() {
+        let stderr = "real error\n\tCommand being timed: \"adapted_stwo\"\n\tMaximum resident set size (kbytes): 2048\n\tExit status: 0";
+        let (stderr, max_memory_kb) = split_time_output(stderr);
+        assert_eq!(stderr, "real error");
+        assert_eq!(max_memory_kb, Some(2048));
+    }
+}
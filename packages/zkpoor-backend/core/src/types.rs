@@ -2,18 +2,51 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::artifacts::ArtifactRef;
+use crate::bitcoin::{Amount, OutPoint, ScriptPubKey, Txid};
+use crate::output::PublicOutput;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Utxo {
-    pub txid: String,
+    pub txid: Txid,
     pub vout: u32,
-    pub amount: u64, // in satoshis
-    pub script_pubkey: String,
+    pub amount: Amount,
+    pub script_pubkey: ScriptPubKey,
+}
+
+impl Utxo {
+    pub fn outpoint(&self) -> OutPoint {
+        OutPoint { txid: self.txid, vout: self.vout }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OwnershipProof {
     pub signature: String,
+    /// The message signed: a challenge issued by `ChallengeStore`
     pub challenge: String,
+    #[serde(default)]
+    pub format: SignatureFormat,
+}
+
+/// How an ownership proof's `signature` is encoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureFormat {
+    /// Bitcoin Core `signmessage`, including the BIP 137 segwit headers
+    #[default]
+    Legacy,
+    /// BIP-322 simple: the witness stack of the virtual `to_sign` transaction
+    Bip322Simple,
+    /// BIP-322 full: the whole `to_sign` transaction
+    Bip322Full,
+}
+
+/// What a job was asked to prove. Private to the submitter and auditors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofInputs {
+    pub utxos: Vec<Utxo>,
+    pub ownership_proofs: Vec<OwnershipProof>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -22,44 +55,157 @@ pub enum ProofStatus {
     InProgress,
     Completed,
     Failed,
+    /// Stopped by a `DELETE /proof/{id}` before it finished
+    Cancelled,
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl ProofStatus {
+    /// Whether the job will not change any more
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, ProofStatus::Completed | ProofStatus::Failed | ProofStatus::Cancelled)
+    }
+}
+
+/// Pipeline stages of a proof job, in order. Backends report the stages they
+/// go through; not every job passes through every stage.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ProofStage {
+    ValidatingInputs,
+    FetchingBlockData,
+    BuildingArgs,
+    Executing,
+    Proving,
+    SelfVerifying,
+}
+
+impl ProofStage {
+    /// Rough share of the job completed when this stage starts; proving dominates
+    pub fn start_percent(self) -> u8 {
+        match self {
+            ProofStage::ValidatingInputs => 0,
+            ProofStage::FetchingBlockData => 2,
+            ProofStage::BuildingArgs => 10,
+            ProofStage::Executing => 15,
+            ProofStage::Proving => 35,
+            ProofStage::SelfVerifying => 95,
+        }
+    }
+}
+
+/// Timing of one stage of a proof job
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StageProgress {
+    pub stage: ProofStage,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Broad cause of a failed proof job
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FailureCategory {
+    /// The submitted UTXOs or ownership proofs were rejected
+    InvalidInput,
+    /// The prover backend reported an error
+    ProverError,
+    /// The prover backend could not be run, e.g. its binary is missing
+    BackendUnavailable,
+    /// A prover step exceeded its time limit
+    Timeout,
+    /// The generated proof did not verify
+    SelfVerificationFailed,
+    /// Storage or other server-side failure
+    Internal,
+    /// The server stopped before the job finished
+    Interrupted,
+}
+
+/// Why a proof job failed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProofFailure {
+    pub category: FailureCategory,
+    pub message: String,
+    /// Stage the job was in when it failed
+    pub stage: Option<ProofStage>,
+    /// Last lines of the prover's stderr, when a prover process failed
+    pub stderr_tail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProofData {
     pub proof_id: Uuid,
     pub status: ProofStatus,
-    pub total_amount: Option<u64>, // Total BTC in satoshis
-    pub proof: Option<String>, // Base64 encoded proof
+    pub total_amount: Option<Amount>, // Total BTC in satoshis
+    /// Stored proof, downloadable via `ArtifactStore`
+    pub proof_artifact: Option<ArtifactRef>,
     pub public_inputs: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// Stage the job is in, or was in when it stopped
+    #[serde(default)]
+    pub stage: Option<ProofStage>,
+    /// Stages entered so far, oldest first
+    #[serde(default)]
+    pub stages: Vec<StageProgress>,
+    /// Estimated completion, 0-100
+    #[serde(default)]
+    pub progress_percent: Option<u8>,
+    /// Set when `status` is `Failed`
+    #[serde(default)]
+    pub failure: Option<ProofFailure>,
+    /// API key that submitted the job, if it was authenticated
+    #[serde(default)]
+    pub created_by: Option<Uuid>,
+    /// Submitted UTXOs and ownership proofs; strip with `redacted` before
+    /// showing the record to anyone who may not see them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<ProofInputs>,
 }
 
-#[derive(Debug, Clone)]
-pub struct ZkProof {
-    pub proof_data: Vec<u8>,
-    pub public_inputs: serde_json::Value,
-    pub total_amount: u64,
-}
-
-impl ZkProof {
-    pub fn to_base64(&self) -> String {
-        base64::encode(&self.proof_data)
+impl ProofData {
+    /// The record without its private inputs
+    pub fn redacted(mut self) -> Self {
+        self.inputs = None;
+        self
     }
-}
 
-// Mock base64 and hex utilities
-mod base64 {
-    use crate::types::hex;
+    /// Record the start of `stage`, finishing the previous one
+    pub fn enter_stage(&mut self, stage: ProofStage) {
+        let now = Utc::now();
+        self.finish_stage(now);
+        self.stages.push(StageProgress {
+            stage,
+            started_at: now,
+            finished_at: None,
+        });
+        self.stage = Some(stage);
+        self.progress_percent = Some(stage.start_percent());
+    }
 
-    pub fn encode(data: &[u8]) -> String {
-        // Mock base64 encoding for now
-        format!("mock_proof_{}", hex::encode(data))
+    /// Close the running stage, if any
+    pub fn finish_stage(&mut self, at: DateTime<Utc>) {
+        if let Some(last) = self.stages.last_mut() {
+            last.finished_at.get_or_insert(at);
+        }
     }
 }
 
-mod hex {
-    pub fn encode(data: &[u8]) -> String {
-        data.iter().map(|b| format!("{:02x}", b)).collect()
-    }
-}
\ No newline at end of file
+/// Outcome of verifying a proof
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationResult {
+    pub is_valid: bool,
+    /// Amount the prover claimed in the submitted public inputs. The proof does
+    /// not attest to it; only `public_output` is proven.
+    pub claimed_total_amount: Option<u64>,
+    /// Program output decoded from the verified proof
+    pub public_output: Option<PublicOutput>,
+    /// Why the proof was rejected, if it was
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ZkProof {
+    /// Serialized proof, in the format the matching `StarkVerifier` expects
+    pub proof: String,
+    pub public_inputs: serde_json::Value,
+    pub total_amount: Amount,
+}
@@ -0,0 +1,197 @@
+use std::collections::hash_map::{Entry, HashMap};
+
+use crate::bitcoin::{Amount, OutPoint};
+use crate::challenge::ChallengeStore;
+use crate::error::Error;
+use crate::ownership::OwnershipVerifier;
+use crate::types::{OwnershipProof, Utxo};
+
+/// Check the UTXOs of a prove request and their paired ownership proofs
+/// before it is queued, and return their total. Every problem is reported at
+/// once, as `Error::InvalidUtxos`: zero amounts, empty or unspendable scripts,
+/// outpoints listed more than once (as an exact duplicate or with a different
+/// amount or script), ownership proofs that do not sign a challenge issued by
+/// `challenges` and ownership proofs `ownership` rejects. The challenges are
+/// only checked here; redeem them once the job is accepted.
+pub fn validate_utxos(
+    utxos: &[Utxo],
+    ownership_proofs: &[OwnershipProof],
+    ownership: &dyn OwnershipVerifier,
+    challenges: &ChallengeStore,
+) -> Result<Amount, Error> {
+    let mut problems = Vec::new();
+    let mut seen: HashMap<OutPoint, usize> = HashMap::new();
+
+    for (index, utxo) in utxos.iter().enumerate() {
+        if let Some(proof) = ownership_proofs.get(index) {
+            // A signature over any other message proves nothing about this job
+            if let Err(reason) = challenges.check(&proof.challenge) {
+                problems.push(Error::BadChallenge { index, reason });
+            } else if let Err(e) = ownership.verify(utxo, proof) {
+                problems.push(Error::BadSignature { index, reason: e.to_string() });
+            }
+        }
+        if utxo.amount == Amount::ZERO {
+            problems.push(Error::InvalidUtxo { index, reason: "amount cannot be zero".to_string() });
+        }
+        if utxo.script_pubkey.is_empty() {
+            problems.push(Error::InvalidUtxo { index, reason: "script_pubkey is empty".to_string() });
+        } else if !utxo.script_pubkey.is_spendable() {
+            problems.push(Error::InvalidUtxo {
+                index,
+                reason: "script_pubkey is provably unspendable (OP_RETURN or over 10,000 bytes)".to_string(),
+            });
+        }
+
+        let first_index = match seen.entry(utxo.outpoint()) {
+            Entry::Vacant(entry) => {
+                entry.insert(index);
+                continue;
+            }
+            Entry::Occupied(entry) => *entry.get(),
+        };
+        let first = &utxos[first_index];
+        let outpoint = utxo.outpoint().to_string();
+        let conflict = if first.amount != utxo.amount {
+            Some(format!("amount {} sat differs from {} sat", utxo.amount, first.amount))
+        } else if first.script_pubkey != utxo.script_pubkey {
+            Some(format!("script_pubkey {} differs from {}", utxo.script_pubkey, first.script_pubkey))
+        } else {
+            None
+        };
+        problems.push(match conflict {
+            Some(reason) => Error::ConflictingOutpoint { index, outpoint, first_index, reason },
+            None => Error::DuplicateOutpoint { index, outpoint, first_index },
+        });
+    }
+
+    if !problems.is_empty() {
+        return Err(Error::InvalidUtxos(problems));
+    }
+    total_amount(utxos)
+}
+
+/// Sum of the UTXO amounts, which cannot exceed the 21M BTC supply
+pub fn total_amount(utxos: &[Utxo]) -> Result<Amount, Error> {
+    Amount::checked_sum(utxos.iter().map(|utxo| utxo.amount))
+        .ok_or_else(|| Error::InvalidRequest("Total UTXO amount exceeds the 21,000,000 BTC supply".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin::Txid;
+    use crate::types::SignatureFormat;
+    use crate::ownership::{hash160, signed_message_hash, MockOwnershipVerifier, SignatureVerifier};
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+    use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+
+    fn utxo(txid: u8, vout: u32, amount: u64) -> Utxo {
+        Utxo {
+            txid: Txid::from_bytes([txid; 32]),
+            vout,
+            amount: Amount::from_sat(amount).unwrap(),
+            script_pubkey: "76a914fb37342f6275b13936799def06f2eb4c0f20151588ac".parse().unwrap(),
+        }
+    }
+
+    fn validate(utxos: &[Utxo]) -> Result<Amount, Error> {
+        let challenges = ChallengeStore::default();
+        let proofs = vec![proof(&challenges.issue().message); utxos.len()];
+        validate_utxos(utxos, &proofs, &MockOwnershipVerifier, &challenges)
+    }
+
+    fn proof(challenge: &str) -> OwnershipProof {
+        OwnershipProof {
+            signature: "mock_signature".to_string(),
+            challenge: challenge.to_string(),
+            format: SignatureFormat::Legacy,
+        }
+    }
+
+    #[test]
+    fn test_valid_utxos_are_totalled() {
+        // Same txid, different vout: distinct outputs
+        let utxos = [utxo(1, 0, 1000), utxo(1, 1, 2000), utxo(2, 0, 3000)];
+        assert_eq!(validate(&utxos).unwrap().to_sat(), 6000);
+    }
+
+    #[test]
+    fn test_ownership_proofs_are_checked() {
+        let challenges = ChallengeStore::default();
+        let challenge = challenges.issue().message;
+        let utxos = [utxo(1, 0, 1000), utxo(2, 0, 2000)];
+        let proofs = [proof(&challenge), proof("mock_challenge")];
+        let Err(Error::InvalidUtxos(problems)) = validate_utxos(&utxos, &proofs, &SignatureVerifier::new(), &challenges) else {
+            panic!("expected InvalidUtxos");
+        };
+        let summary: Vec<_> = problems.iter().map(|problem| (problem.index(), problem.code())).collect();
+        assert_eq!(summary, [(Some(0), "bad_signature"), (Some(1), "bad_challenge")]);
+    }
+
+    #[test]
+    fn test_signatures_over_other_messages_are_rejected() {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[7; 32]).unwrap();
+        let pubkey_hash = hash160(&PublicKey::from_secret_key(&secp, &secret).serialize());
+        let mut owned = utxo(1, 0, 1000);
+        owned.script_pubkey = format!("76a914{}88ac", hex::encode(pubkey_hash)).parse().unwrap();
+        let sign = |message: &str| {
+            let digest = Message::from_digest(signed_message_hash(message));
+            let (recovery_id, compact) = secp.sign_ecdsa_recoverable(&digest, &secret).serialize_compact();
+            let mut bytes = vec![31 + recovery_id.to_i32() as u8];
+            bytes.extend_from_slice(&compact);
+            OwnershipProof { signature: BASE64.encode(bytes), ..proof(message) }
+        };
+        let challenges = ChallengeStore::default();
+        let verifier = SignatureVerifier::new();
+
+        // A genuine signature the key holder published for something else
+        let owned = [owned];
+        let published = [sign("zkpoor proof of reserves 2024-06-01")];
+        let Err(Error::InvalidUtxos(problems)) = validate_utxos(&owned, &published, &verifier, &challenges) else {
+            panic!("expected InvalidUtxos");
+        };
+        assert_eq!(problems[0].code(), "bad_challenge");
+
+        let issued = [sign(&challenges.issue().message)];
+        validate_utxos(&owned, &issued, &verifier, &challenges).unwrap();
+        challenges.redeem(&issued).unwrap();
+        assert!(validate_utxos(&owned, &issued, &verifier, &challenges).is_err());
+    }
+
+    #[test]
+    fn test_every_problem_is_reported_with_its_index() {
+        let mut op_return = utxo(3, 0, 1000);
+        op_return.script_pubkey = "6a00".parse().unwrap();
+        let mut other_script = utxo(1, 0, 1000);
+        other_script.script_pubkey = "0014751e76e8199196d454941c45d1b3a323f1433bd6".parse().unwrap();
+        let mut empty_script = utxo(4, 0, 0);
+        empty_script.script_pubkey = "".parse().unwrap();
+        let utxos = [utxo(1, 0, 1000), utxo(2, 0, 5000), utxo(1, 0, 1000), utxo(2, 0, 9000), op_return, other_script, empty_script];
+
+        let Err(Error::InvalidUtxos(problems)) = validate(&utxos) else {
+            panic!("expected InvalidUtxos");
+        };
+        let summary: Vec<_> = problems.iter().map(|problem| (problem.index(), problem.code())).collect();
+        assert_eq!(summary, [
+            (Some(2), "duplicate_outpoint"),
+            (Some(3), "conflicting_outpoint"),
+            (Some(4), "invalid_utxo"),
+            (Some(5), "conflicting_outpoint"),
+            (Some(6), "invalid_utxo"),
+            (Some(6), "invalid_utxo"),
+        ]);
+        assert!(matches!(problems[0], Error::DuplicateOutpoint { first_index: 0, .. }));
+        assert!(problems[1].to_string().contains("amount 9000 sat differs from 5000 sat"));
+        assert!(problems[3].to_string().contains("script_pubkey"));
+        assert!(problems[4].to_string().contains("amount cannot be zero"));
+        assert!(problems[5].to_string().contains("script_pubkey is empty"));
+    }
+
+    #[test]
+    fn test_total_is_capped() {
+        let utxos = [utxo(1, 0, 2_000_000_000_000_000), utxo(2, 0, 2_000_000_000_000_000)];
+        assert!(matches!(validate(&utxos), Err(Error::InvalidRequest(_))));
+    }
+}